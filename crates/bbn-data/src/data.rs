@@ -2,6 +2,7 @@ mod datetime;
 mod entry;
 mod entry_id;
 mod entry_meta;
+mod hash;
 mod timestamp;
 
 pub use self::datetime::DateTime;
@@ -10,5 +11,7 @@ pub use self::entry::Entry;
 pub use self::entry_id::EntryId;
 pub use self::entry_id::EntryIdError;
 pub use self::entry_id::EntryKey;
+pub use self::entry_id::InvalidIdTitleError;
 pub use self::entry_meta::EntryMeta;
+pub use self::hash::hash;
 pub use self::timestamp::Timestamp;
@@ -12,6 +12,10 @@ pub struct EntryId {
 #[error("parse entry id")]
 pub struct EntryIdError;
 
+#[derive(Debug, thiserror::Error)]
+#[error("invalid id title (use [A-Za-z0-9_-]): {0}")]
+pub struct InvalidIdTitleError(String);
+
 impl std::str::FromStr for EntryId {
     type Err = EntryIdError;
 
@@ -57,6 +61,18 @@ impl EntryId {
     pub fn id_title(&self) -> Option<&str> {
         self.id_title.as_deref()
     }
+
+    /// id_title はパスの一部になるので [A-Za-z0-9_-] だけを許す
+    pub fn validate_id_title(id_title: &str) -> Result<(), InvalidIdTitleError> {
+        if id_title.is_empty()
+            || !id_title
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(InvalidIdTitleError(id_title.to_string()));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -93,4 +109,12 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn validate_id_title_test() {
+        assert!(EntryId::validate_id_title("my-first_post1").is_ok());
+        assert!(EntryId::validate_id_title("").is_err());
+        assert!(EntryId::validate_id_title("a/b").is_err());
+        assert!(EntryId::validate_id_title("a b").is_err());
+    }
 }
@@ -0,0 +1,27 @@
+/// FNV-1a (64bit) の 16 進表記
+pub fn hash(parts: &[&[u8]]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    let mut hash = OFFSET_BASIS;
+    for part in parts {
+        // 区切りがないと ("ab", "c") と ("a", "bc") が同じになる
+        for byte in part.len().to_le_bytes().iter().chain(part.iter()) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_test() {
+        assert_eq!(hash(&[]), "cbf29ce484222325");
+        assert_eq!(hash(&[b"a"]), hash(&[b"a"]));
+        assert_ne!(hash(&[b"a"]), hash(&[b"b"]));
+        assert_ne!(hash(&[b"ab", b"c"]), hash(&[b"a", b"bc"]));
+    }
+}
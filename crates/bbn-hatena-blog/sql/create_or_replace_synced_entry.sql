@@ -0,0 +1,2 @@
+INSERT OR REPLACE INTO synced_entries(entry_id, content_hash, edited, synced_at)
+VALUES (?, ?, ?, ?)
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS synced_entries (
+  entry_id TEXT PRIMARY KEY,
+  content_hash TEXT NOT NULL,
+  edited INTEGER NOT NULL,
+  synced_at INTEGER NOT NULL
+)
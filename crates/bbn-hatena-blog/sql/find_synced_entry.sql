@@ -0,0 +1,4 @@
+SELECT content_hash,
+  edited
+FROM synced_entries
+WHERE entry_id = ?
@@ -1,3 +1,4 @@
+mod category_map;
 mod download_entry;
 mod hatena_blog_client;
 mod hatena_blog_entry;
@@ -9,8 +10,12 @@ mod indexing_id;
 mod member_request;
 mod member_request_id;
 mod member_response_id;
+mod rate_limiter;
+mod retry_policy;
+mod synced_entry;
 mod upload_entry;
 
+pub use self::category_map::*;
 pub use self::download_entry::*;
 pub use self::hatena_blog_client::*;
 pub use self::hatena_blog_entry::*;
@@ -22,4 +27,7 @@ pub use self::indexing_id::*;
 pub use self::member_request::*;
 pub use self::member_request_id::*;
 pub use self::member_response_id::*;
+pub use self::rate_limiter::*;
+pub use self::retry_policy::*;
+pub use self::synced_entry::*;
 pub use self::upload_entry::*;
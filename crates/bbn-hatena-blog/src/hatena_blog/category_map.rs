@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+
+/// bbn の tag -> Hatena Blog の category の対応表
+///
+/// 対応表にない tag は同じ名前の category に対応させる (逆も同じ)
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CategoryMap(BTreeMap<String, String>);
+
+impl From<BTreeMap<String, String>> for CategoryMap {
+    fn from(map: BTreeMap<String, String>) -> Self {
+        Self(map)
+    }
+}
+
+impl CategoryMap {
+    /// tags に対応する categories を返す
+    pub fn categories(&self, tags: &[String]) -> Vec<String> {
+        dedup(
+            tags.iter()
+                .map(|tag| self.0.get(tag).unwrap_or(tag).to_string()),
+        )
+    }
+
+    /// categories に対応する tags を返す
+    ///
+    /// 複数の tag が同じ category に対応する場合は名前順で最初の tag にする
+    pub fn tags(&self, categories: &[String]) -> Vec<String> {
+        dedup(categories.iter().map(|category| {
+            self.0
+                .iter()
+                .find(|(_, c)| *c == category)
+                .map(|(tag, _)| tag)
+                .unwrap_or(category)
+                .to_string()
+        }))
+    }
+}
+
+fn dedup(iter: impl Iterator<Item = String>) -> Vec<String> {
+    let mut v = Vec::<String>::new();
+    for s in iter {
+        if !v.contains(&s) {
+            v.push(s);
+        }
+    }
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn categories_test() {
+        let map = CategoryMap::default();
+        assert_eq!(map.categories(&strings(&["a", "b"])), strings(&["a", "b"]));
+        assert_eq!(map.categories(&[]), Vec::<String>::new());
+
+        let map = CategoryMap::from(BTreeMap::from([
+            ("a".to_string(), "A".to_string()),
+            ("a2".to_string(), "A".to_string()),
+        ]));
+        assert_eq!(
+            map.categories(&strings(&["b", "a", "a2"])),
+            strings(&["b", "A"])
+        );
+    }
+
+    #[test]
+    fn tags_test() {
+        let map = CategoryMap::default();
+        assert_eq!(map.tags(&strings(&["a", "b"])), strings(&["a", "b"]));
+
+        let map = CategoryMap::from(BTreeMap::from([
+            ("a".to_string(), "A".to_string()),
+            ("a2".to_string(), "A".to_string()),
+        ]));
+        assert_eq!(map.tags(&strings(&["b", "A", "a"])), strings(&["b", "a"]));
+    }
+}
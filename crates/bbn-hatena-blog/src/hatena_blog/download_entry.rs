@@ -30,12 +30,13 @@ pub async fn download_entry(
     let entry_meta = bbn_repository
         .find_meta_by_id(&entry_id)?
         .with_context(|| DownloadEntryError::NoEntryMeta)?;
-    let hatena_blog_entry = hatena_blog_repository
-        .find_entry_by_updated(entry_meta.pubdate.into())
+    let hatena_blog_entry_id = hatena_blog_repository
+        .find_entry_by_entry_meta(&entry_meta)
         .await?
+        .map(|entry| entry.id)
         .with_context(|| DownloadEntryError::NoHatenaBlogEntry)?;
     let response = hatena_blog_client
-        .get_entry(&hatena_blog_entry.id)
+        .get_entry(&hatena_blog_entry_id)
         .await?
         .with_context(|| DownloadEntryError::NoHatenaBlogEntry)?;
     let body = response.to_string();
@@ -1,5 +1,10 @@
+use std::future::Future;
+
 use crate::hatena_blog::HatenaBlogEntryId;
+use crate::hatena_blog::RateLimiter;
+use crate::hatena_blog::RetryPolicy;
 use hatena_blog_api::Client;
+use hatena_blog_api::ClientError;
 use hatena_blog_api::Config;
 use hatena_blog_api::CreateEntryResponse;
 use hatena_blog_api::EntryId;
@@ -12,17 +17,43 @@ use crate::hatena_blog::HatenaBlogListEntriesResponse;
 #[derive(Clone, Debug)]
 pub struct HatenaBlogClient {
     config: Config,
+    rate_limiter: RateLimiter,
+    retry_policy: RetryPolicy,
 }
 
 impl HatenaBlogClient {
     pub fn new(hatena_id: String, hatena_blog_id: String, hatena_api_key: String) -> Self {
         let config = Config::new(&hatena_id, None, &hatena_blog_id, &hatena_api_key);
-        Self { config }
+        Self {
+            config,
+            rate_limiter: RateLimiter::default(),
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    /// すべてのメソッドの呼び出しで共有する (clone したものとも共有する)
+    pub fn with_rate_limiter(self, rate_limiter: RateLimiter) -> Self {
+        Self {
+            rate_limiter,
+            ..self
+        }
     }
 
+    pub fn with_retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy,
+            ..self
+        }
+    }
+
+    /// 再試行しない (失敗しても作成されている場合があり、再試行すると重複するため)
     pub async fn create_entry(&self, params: EntryParams) -> anyhow::Result<CreateEntryResponse> {
-        let client = Client::new(&self.config);
-        Ok(client.create_entry(params).await?)
+        Ok(send(&self.rate_limiter, &RetryPolicy::none(), || {
+            let client = Client::new(&self.config);
+            let params = params.clone();
+            async move { client.create_entry(params).await }
+        })
+        .await?)
     }
 
     pub async fn update_entry(
@@ -30,27 +61,39 @@ impl HatenaBlogClient {
         hatena_blog_entry_id: &HatenaBlogEntryId,
         params: EntryParams,
     ) -> anyhow::Result<UpdateEntryResponse> {
-        let client = Client::new(&self.config);
         let entry_id = EntryId::from(hatena_blog_entry_id);
-        Ok(client.update_entry(&entry_id, params).await?)
+        Ok(self
+            .send(|| {
+                let client = Client::new(&self.config);
+                let entry_id = entry_id.clone();
+                let params = params.clone();
+                async move { client.update_entry(&entry_id, params).await }
+            })
+            .await?)
     }
 
     pub async fn get_entry(
         &self,
         hatena_blog_entry_id: &HatenaBlogEntryId,
     ) -> anyhow::Result<Option<GetEntryResponse>> {
-        let client = Client::new(&self.config);
         let entry_id = EntryId::from(hatena_blog_entry_id);
-        Ok(match client.get_entry(&entry_id).await {
+        let result = self
+            .send(|| {
+                let client = Client::new(&self.config);
+                let entry_id = entry_id.clone();
+                async move { client.get_entry(&entry_id).await }
+            })
+            .await;
+        Ok(match result {
             Ok(response) => Ok(Some(response)),
             Err(err) => match err {
-                hatena_blog_api::ClientError::NotFound => Ok(None),
-                hatena_blog_api::ClientError::RequestError(_)
-                | hatena_blog_api::ClientError::BadRequest
-                | hatena_blog_api::ClientError::Unauthorized
-                | hatena_blog_api::ClientError::MethodNotAllowed
-                | hatena_blog_api::ClientError::InternalServerError
-                | hatena_blog_api::ClientError::UnknownStatusCode => Err(err),
+                ClientError::NotFound => Ok(None),
+                ClientError::RequestError(_)
+                | ClientError::BadRequest
+                | ClientError::Unauthorized
+                | ClientError::MethodNotAllowed
+                | ClientError::InternalServerError
+                | ClientError::UnknownStatusCode => Err(err),
             },
         }?)
     }
@@ -59,9 +102,99 @@ impl HatenaBlogClient {
         &self,
         page: Option<&str>,
     ) -> anyhow::Result<HatenaBlogListEntriesResponse> {
-        let client = Client::new(&self.config);
-        Ok(HatenaBlogListEntriesResponse::from(
-            client.list_entries_in_page(page).await?,
-        ))
+        let response = self
+            .send(|| {
+                let client = Client::new(&self.config);
+                async move { client.list_entries_in_page(page).await }
+            })
+            .await?;
+        Ok(HatenaBlogListEntriesResponse::from(response))
+    }
+
+    async fn send<T, F, Fut>(&self, f: F) -> Result<T, ClientError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        send(&self.rate_limiter, &self.retry_policy, f).await
+    }
+}
+
+/// 流量制限に従って `f` を呼び出し、失敗した場合は `retry_policy` に従って再試行する
+async fn send<T, F, Fut>(
+    rate_limiter: &RateLimiter,
+    retry_policy: &RetryPolicy,
+    mut f: F,
+) -> Result<T, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    let mut retries = 0;
+    loop {
+        rate_limiter.acquire().await;
+        match f().await {
+            Err(err) if retry_policy.should_retry(retries, &err) => {
+                tokio::time::sleep(retry_policy.backoff(retries)).await;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU32;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    use super::*;
+
+    async fn count_attempts(errors: Vec<ClientError>) -> (u32, Result<(), ClientError>) {
+        let rate_limiter = RateLimiter::unlimited();
+        let retry_policy = RetryPolicy::new(2, Duration::ZERO, Duration::ZERO);
+        let attempts = AtomicU32::new(0);
+        let errors = std::sync::Mutex::new(errors.into_iter());
+        let result = send(&rate_limiter, &retry_policy, || {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            let result = match errors.lock().unwrap().next() {
+                None => Ok(()),
+                Some(err) => Err(err),
+            };
+            async move { result }
+        })
+        .await;
+        (attempts.load(Ordering::SeqCst), result)
+    }
+
+    #[tokio::test]
+    async fn send_test() {
+        let (attempts, result) = count_attempts(vec![]).await;
+        assert_eq!(attempts, 1);
+        assert!(result.is_ok());
+
+        // 一時的なエラーは再試行する
+        let (attempts, result) = count_attempts(vec![ClientError::InternalServerError]).await;
+        assert_eq!(attempts, 2);
+        assert!(result.is_ok());
+
+        let (attempts, result) = count_attempts(vec![
+            ClientError::InternalServerError,
+            ClientError::InternalServerError,
+            ClientError::InternalServerError,
+        ])
+        .await;
+        assert_eq!(attempts, 3);
+        assert!(matches!(result, Err(ClientError::InternalServerError)));
+
+        // 再試行しても成功しないエラーはすぐに失敗する
+        let (attempts, result) = count_attempts(vec![ClientError::Unauthorized]).await;
+        assert_eq!(attempts, 1);
+        assert!(matches!(result, Err(ClientError::Unauthorized)));
+
+        let (attempts, result) = count_attempts(vec![ClientError::BadRequest]).await;
+        assert_eq!(attempts, 1);
+        assert!(matches!(result, Err(ClientError::BadRequest)));
     }
 }
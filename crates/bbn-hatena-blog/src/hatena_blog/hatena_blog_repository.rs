@@ -6,6 +6,7 @@ use crate::hatena_blog::IndexingId;
 use crate::hatena_blog::MemberRequest;
 use crate::hatena_blog::MemberRequestId;
 use crate::hatena_blog::MemberResponseId;
+use crate::hatena_blog::SyncedEntry;
 use anyhow::Context as _;
 use bbn_data::DateTime;
 use bbn_data::EntryMeta;
@@ -49,6 +50,8 @@ impl HatenaBlogRepository {
             // member_requests
             include_str!("../../sql/create_table_member_requests.sql"),
             include_str!("../../sql/create_table_member_request_results.sql"),
+            // synced_entries
+            include_str!("../../sql/create_table_synced_entries.sql"),
         ];
         for migration in migrations.iter() {
             sqlx::query(migration).execute(&pool).await?;
@@ -162,6 +165,23 @@ impl HatenaBlogRepository {
         ))
     }
 
+    /// upload または download した時点のエントリーの状態を記録する
+    pub async fn create_or_replace_synced_entry(
+        &self,
+        hatena_blog_entry_id: &HatenaBlogEntryId,
+        synced_entry: &SyncedEntry,
+        synced_at: Timestamp,
+    ) -> anyhow::Result<()> {
+        sqlx::query(include_str!("../../sql/create_or_replace_synced_entry.sql"))
+            .bind(hatena_blog_entry_id.to_string())
+            .bind(synced_entry.content_hash.as_str())
+            .bind(i64::from(synced_entry.edited))
+            .bind(i64::from(synced_at))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     pub async fn create_successful_indexing(
         &self,
         indexing_id: IndexingId,
@@ -228,6 +248,7 @@ impl HatenaBlogRepository {
             .collect::<Vec<String>>())
     }
 
+    /// entry_meta に記録された entry id を優先し、なければ pubdate で探す
     pub async fn find_entry_by_entry_meta(
         &self,
         entry_meta: &EntryMeta,
@@ -351,6 +372,24 @@ impl HatenaBlogRepository {
             .await?;
         Ok(row.map(|(at,)| Timestamp::try_from(at)).transpose()?)
     }
+
+    pub async fn find_synced_entry(
+        &self,
+        hatena_blog_entry_id: &HatenaBlogEntryId,
+    ) -> anyhow::Result<Option<SyncedEntry>> {
+        let row: Option<(String, i64)> =
+            sqlx::query_as(include_str!("../../sql/find_synced_entry.sql"))
+                .bind(hatena_blog_entry_id.to_string())
+                .fetch_optional(&self.pool)
+                .await?;
+        row.map(|(content_hash, edited)| {
+            Ok(SyncedEntry {
+                content_hash,
+                edited: Timestamp::try_from(edited)?,
+            })
+        })
+        .transpose()
+    }
 }
 
 #[cfg(test)]
@@ -369,4 +408,32 @@ mod tests {
         assert_eq!(found, Some(created));
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn synced_entry_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_file = temp_dir.path().join("db");
+        let repository = HatenaBlogRepository::new(data_file).await?;
+        let id = HatenaBlogEntryId::from_str("1")?;
+        assert_eq!(repository.find_synced_entry(&id).await?, None);
+
+        let meta = EntryMeta::new(
+            5,
+            DateTime::from_str("2021-02-03T00:00:00+09:00")?,
+            vec![],
+            "title".to_string(),
+        );
+        let synced1 = SyncedEntry::new(&meta, "a", Timestamp::try_from(1_i64)?);
+        repository
+            .create_or_replace_synced_entry(&id, &synced1, Timestamp::now()?)
+            .await?;
+        assert_eq!(repository.find_synced_entry(&id).await?, Some(synced1));
+
+        let synced2 = SyncedEntry::new(&meta, "b", Timestamp::try_from(2_i64)?);
+        repository
+            .create_or_replace_synced_entry(&id, &synced2, Timestamp::now()?)
+            .await?;
+        assert_eq!(repository.find_synced_entry(&id).await?, Some(synced2));
+        Ok(())
+    }
 }
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// token bucket による流量制限
+///
+/// clone したものは同じ bucket を共有する
+#[derive(Clone, Debug)]
+pub struct RateLimiter(Arc<Mutex<Bucket>>);
+
+impl Default for RateLimiter {
+    /// 1 秒に 1 回
+    fn default() -> Self {
+        Self::new(1, Duration::from_secs(1))
+    }
+}
+
+impl RateLimiter {
+    /// 最大 `capacity` 回まで続けて実行でき、`interval` ごとに 1 回分回復する
+    pub fn new(capacity: u32, interval: Duration) -> Self {
+        Self(Arc::new(Mutex::new(Bucket::new(
+            capacity,
+            interval,
+            Instant::now(),
+        ))))
+    }
+
+    /// 制限しない
+    pub fn unlimited() -> Self {
+        Self::new(1, Duration::ZERO)
+    }
+
+    /// 1 回分を取得できるまで待つ
+    pub async fn acquire(&self) {
+        loop {
+            let wait = self.0.lock().unwrap().try_acquire(Instant::now());
+            match wait {
+                None => return,
+                Some(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    interval: Duration,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn new(capacity: u32, interval: Duration, now: Instant) -> Self {
+        let capacity = f64::from(capacity.max(1));
+        Self {
+            capacity,
+            interval,
+            tokens: capacity,
+            updated_at: now,
+        }
+    }
+
+    /// 取得できれば `None` を、できなければ次に取得できるまでの時間を返す
+    fn try_acquire(&mut self, now: Instant) -> Option<Duration> {
+        if self.interval.is_zero() {
+            return None;
+        }
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() / self.interval.as_secs_f64()).min(self.capacity);
+        self.updated_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(self.interval.mul_f64(1.0 - self.tokens))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_acquire_test() {
+        let now = Instant::now();
+        let second = Duration::from_secs(1);
+        let mut bucket = Bucket::new(2, second, now);
+        assert_eq!(bucket.try_acquire(now), None);
+        assert_eq!(bucket.try_acquire(now), None);
+        assert_eq!(bucket.try_acquire(now), Some(second));
+        assert_eq!(bucket.try_acquire(now + second / 2), Some(second / 2));
+        assert_eq!(bucket.try_acquire(now + second), None);
+        // capacity 以上には回復しない
+        assert_eq!(bucket.try_acquire(now + second * 10), None);
+        assert_eq!(bucket.try_acquire(now + second * 10), None);
+        assert_eq!(bucket.try_acquire(now + second * 10), Some(second));
+    }
+
+    #[test]
+    fn unlimited_test() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(1, Duration::ZERO, now);
+        for _ in 0..10 {
+            assert_eq!(bucket.try_acquire(now), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn acquire_test() {
+        let rate_limiter = RateLimiter::unlimited();
+        rate_limiter.acquire().await;
+        rate_limiter.clone().acquire().await;
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use hatena_blog_api::ClientError;
+
+/// HatenaBlogClient の再試行の方針
+///
+/// 失敗するたびに待ち時間を 2 倍にする (exponential backoff)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_retries: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(3, Duration::from_secs(1), Duration::from_secs(30))
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            initial_backoff,
+            max_backoff,
+            max_retries,
+        }
+    }
+
+    /// 再試行しない
+    pub fn none() -> Self {
+        Self::new(0, Duration::ZERO, Duration::ZERO)
+    }
+
+    /// `retries` 回目の再試行の前に待つ時間 (`retries` は 0 から数える)
+    pub fn backoff(&self, retries: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2_u32.saturating_pow(retries))
+            .min(self.max_backoff)
+    }
+
+    /// `retries` 回再試行した後に `error` で失敗した場合に再試行するか
+    ///
+    /// 一時的なもの (サーバーエラーと通信エラー) だけを再試行する
+    pub fn should_retry(&self, retries: u32, error: &ClientError) -> bool {
+        retries < self.max_retries
+            && match error {
+                ClientError::InternalServerError | ClientError::RequestError(_) => true,
+                ClientError::BadRequest
+                | ClientError::Unauthorized
+                | ClientError::NotFound
+                | ClientError::MethodNotAllowed
+                | ClientError::UnknownStatusCode => false,
+            }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_test() {
+        let policy = RetryPolicy::new(10, Duration::from_secs(1), Duration::from_secs(5));
+        assert_eq!(policy.backoff(0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(1), Duration::from_secs(2));
+        assert_eq!(policy.backoff(2), Duration::from_secs(4));
+        assert_eq!(policy.backoff(3), Duration::from_secs(5));
+        assert_eq!(policy.backoff(100), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn should_retry_test() {
+        let policy = RetryPolicy::default();
+        assert!(policy.should_retry(0, &ClientError::InternalServerError));
+        assert!(policy.should_retry(2, &ClientError::InternalServerError));
+        assert!(!policy.should_retry(3, &ClientError::InternalServerError));
+        assert!(!policy.should_retry(0, &ClientError::BadRequest));
+        assert!(!policy.should_retry(0, &ClientError::Unauthorized));
+        assert!(!policy.should_retry(0, &ClientError::NotFound));
+        assert!(!RetryPolicy::none().should_retry(0, &ClientError::InternalServerError));
+    }
+}
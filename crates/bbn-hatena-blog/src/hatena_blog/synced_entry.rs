@@ -0,0 +1,127 @@
+use bbn_data::EntryMeta;
+use bbn_data::Timestamp;
+use bbn_data::hash;
+
+/// 前回の同期 (upload または download) の時点のエントリーの状態
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SyncedEntry {
+    /// title と tags と content のハッシュ
+    pub content_hash: String,
+    /// Hatena Blog の edited
+    pub edited: Timestamp,
+}
+
+impl SyncedEntry {
+    pub fn new(meta: &EntryMeta, content: &str, edited: Timestamp) -> Self {
+        Self {
+            content_hash: content_hash(meta, content),
+            edited,
+        }
+    }
+}
+
+/// Hatena Blog に反映される title と tags と content だけを対象にする
+fn content_hash(meta: &EntryMeta, content: &str) -> String {
+    let mut parts = vec![meta.title.as_bytes(), content.as_bytes()];
+    parts.extend(meta.tags.iter().map(|tag| tag.as_bytes()));
+    hash(&parts)
+}
+
+/// 前回の同期からの変更
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SyncState {
+    /// 同期の記録がない
+    Unknown,
+    /// どちらも変更されていない
+    Unchanged,
+    /// bbn のエントリーだけが変更された
+    LocalChanged,
+    /// Hatena Blog のエントリーだけが変更された
+    RemoteChanged,
+    /// 両方が変更された
+    Conflict,
+}
+
+impl SyncState {
+    pub fn new(
+        synced: Option<&SyncedEntry>,
+        local_meta: &EntryMeta,
+        local_content: &str,
+        remote_edited: Timestamp,
+    ) -> Self {
+        let Some(synced) = synced else {
+            return Self::Unknown;
+        };
+        let local_changed = content_hash(local_meta, local_content) != synced.content_hash;
+        let remote_changed = remote_edited != synced.edited;
+        match (local_changed, remote_changed) {
+            (false, false) => Self::Unchanged,
+            (true, false) => Self::LocalChanged,
+            (false, true) => Self::RemoteChanged,
+            (true, true) => Self::Conflict,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bbn_data::DateTime;
+
+    use super::*;
+
+    #[test]
+    fn sync_state_test() -> anyhow::Result<()> {
+        let edited1 = Timestamp::try_from(1_i64)?;
+        let edited2 = Timestamp::try_from(2_i64)?;
+        let meta = EntryMeta::new(
+            5,
+            DateTime::from_str("2021-02-03T00:00:00+09:00")?,
+            vec!["tag1".to_string()],
+            "title".to_string(),
+        );
+        let synced = SyncedEntry::new(&meta, "a", edited1);
+        let f = SyncState::new;
+        assert_eq!(f(None, &meta, "a", edited1), SyncState::Unknown);
+        assert_eq!(f(Some(&synced), &meta, "a", edited1), SyncState::Unchanged);
+        assert_eq!(
+            f(Some(&synced), &meta, "b", edited1),
+            SyncState::LocalChanged
+        );
+        assert_eq!(
+            f(Some(&synced), &meta, "a", edited2),
+            SyncState::RemoteChanged
+        );
+        assert_eq!(f(Some(&synced), &meta, "b", edited2), SyncState::Conflict);
+
+        // title と tags の変更も bbn 側の変更とみなす
+        let retitled = EntryMeta {
+            title: "title2".to_string(),
+            ..meta.clone()
+        };
+        assert_eq!(
+            f(Some(&synced), &retitled, "a", edited1),
+            SyncState::LocalChanged
+        );
+        let retagged = EntryMeta {
+            tags: vec!["tag2".to_string()],
+            ..meta.clone()
+        };
+        assert_eq!(
+            f(Some(&synced), &retagged, "a", edited1),
+            SyncState::LocalChanged
+        );
+
+        // bbn 側だけの項目は対象にしない
+        let remeasured = EntryMeta {
+            minutes: 30,
+            ..meta.clone()
+        };
+        assert_eq!(
+            f(Some(&synced), &remeasured, "a", edited1),
+            SyncState::Unchanged
+        );
+        Ok(())
+    }
+}
@@ -1,11 +1,20 @@
+use crate::hatena_blog::CategoryMap;
 use crate::hatena_blog::HatenaBlogClient;
+use crate::hatena_blog::HatenaBlogEntry;
+use crate::hatena_blog::HatenaBlogEntryId;
 use crate::hatena_blog::HatenaBlogRepository;
+use crate::hatena_blog::SyncState;
+use crate::hatena_blog::SyncedEntry;
 use anyhow::Context;
+use bbn_data::DateTime;
 use bbn_data::EntryId;
+use bbn_data::EntryMeta;
 use bbn_data::Timestamp;
 use bbn_repository::BbnRepository;
 use date_range::date::Date;
+use hatena_blog_api::Entry;
 use hatena_blog_api::EntryParams;
+use std::collections::BTreeSet;
 use thiserror::Error;
 
 #[derive(Debug, Error, Eq, PartialEq)]
@@ -14,12 +23,28 @@ pub enum UploadEntryError {
     NoEntryId,
     #[error("no entry")]
     NoEntry,
+    #[error("no hatena-blog entry for the recorded entry id")]
+    NoHatenaBlogEntry,
+    #[error("both the local and remote entries have been edited since the last sync")]
+    Conflict,
+    #[error("the remote entry has been edited since the last sync")]
+    RemoteChanged,
+    #[error(
+        "the local and remote entries differ but have never been synced. Use `bbn hatena-blog download --mark-synced` to record the current entries as synced"
+    )]
+    NotSynced,
 }
 
+/// `force` が `false` の場合、前回の同期から Hatena Blog 側で編集されていれば上書きしない
+///
+/// 同期の記録がなく内容が異なる場合も上書きしない
+#[allow(clippy::too_many_arguments)]
 pub async fn upload_entry(
     date: Date,
     draft: bool,
+    force: bool,
     hatena_id: &str,
+    category_map: &CategoryMap,
     bbn_repository: &BbnRepository,
     hatena_blog_repository: &HatenaBlogRepository,
     hatena_blog_client: &HatenaBlogClient,
@@ -36,29 +61,111 @@ pub async fn upload_entry(
         entry.meta().title.clone(),
         entry.content().to_string(),
         updated.to_string(),
-        vec![],
+        category_map.categories(&entry.meta().tags),
         draft,
     );
     let hatena_blog_entry = hatena_blog_repository
         .find_entry_by_entry_meta(entry.meta())
         .await?;
-    let res = match hatena_blog_entry {
+    // 記録された entry id がデータファイルにない場合に重複して作成しない
+    if hatena_blog_entry.is_none() && entry.meta().hatena_blog_entry_id.is_some() {
+        anyhow::bail!(UploadEntryError::NoHatenaBlogEntry);
+    }
+    let (created, response) = match hatena_blog_entry.map(|entry| entry.id) {
         None => {
             let response = hatena_blog_client.create_entry(params).await?;
             let body = response.to_string();
             hatena_blog_repository
                 .create_member_response(Timestamp::now()?, body)
                 .await?;
-            (true, entry_id)
+            (true, Entry::try_from(response)?)
         }
-        Some(entry) => {
-            let response = hatena_blog_client.update_entry(&entry.id, params).await?;
+        Some(hatena_blog_entry_id) => {
+            if !force {
+                check_remote_changes(
+                    &hatena_blog_entry_id,
+                    entry.meta(),
+                    entry.content(),
+                    category_map,
+                    hatena_blog_repository,
+                    hatena_blog_client,
+                )
+                .await?;
+            }
+            let response = hatena_blog_client
+                .update_entry(&hatena_blog_entry_id, params)
+                .await?;
             let body = response.to_string();
             hatena_blog_repository
                 .create_member_response(Timestamp::now()?, body)
                 .await?;
-            (false, entry_id)
+            (false, Entry::try_from(response)?)
         }
     };
-    Ok(res)
+    // 次回から pubdate ではなく entry id で対応させるために記録する
+    let hatena_blog_entry = HatenaBlogEntry::from(response);
+    hatena_blog_repository
+        .create_or_replace_synced_entry(
+            &hatena_blog_entry.id,
+            &SyncedEntry::new(
+                entry.meta(),
+                entry.content(),
+                Timestamp::from(hatena_blog_entry.edited),
+            ),
+            Timestamp::now()?,
+        )
+        .await?;
+    let meta = with_hatena_blog_entry(entry.meta().clone(), &hatena_blog_entry);
+    if &meta != entry.meta() {
+        let content = entry.content().to_string();
+        bbn_repository.save(entry.update(content, meta))?;
+    }
+    Ok((created, entry_id))
+}
+
+async fn check_remote_changes(
+    hatena_blog_entry_id: &HatenaBlogEntryId,
+    local_meta: &EntryMeta,
+    local_content: &str,
+    category_map: &CategoryMap,
+    hatena_blog_repository: &HatenaBlogRepository,
+    hatena_blog_client: &HatenaBlogClient,
+) -> anyhow::Result<()> {
+    let synced_entry = hatena_blog_repository
+        .find_synced_entry(hatena_blog_entry_id)
+        .await?;
+    // データファイルは前回の download の時点のものなので最新のものを取得する
+    let Some(response) = hatena_blog_client.get_entry(hatena_blog_entry_id).await? else {
+        return Ok(());
+    };
+    let remote_entry = Entry::try_from(response)?;
+    let remote_edited = Timestamp::from(DateTime::from(remote_entry.edited));
+    match SyncState::new(
+        synced_entry.as_ref(),
+        local_meta,
+        local_content,
+        remote_edited,
+    ) {
+        SyncState::Conflict => anyhow::bail!(UploadEntryError::Conflict),
+        SyncState::RemoteChanged => anyhow::bail!(UploadEntryError::RemoteChanged),
+        // 同期の記録がなければどちらが編集されたかわからない
+        SyncState::Unknown
+            if remote_entry.title != local_meta.title
+                || remote_entry.content != local_content
+                || BTreeSet::from_iter(&remote_entry.categories)
+                    != BTreeSet::from_iter(&category_map.categories(&local_meta.tags)) =>
+        {
+            anyhow::bail!(UploadEntryError::NotSynced)
+        }
+        SyncState::Unknown | SyncState::Unchanged | SyncState::LocalChanged => Ok(()),
+    }
+}
+
+/// meta に Hatena Blog の entry id と URL を記録する
+pub fn with_hatena_blog_entry(meta: EntryMeta, hatena_blog_entry: &HatenaBlogEntry) -> EntryMeta {
+    EntryMeta {
+        hatena_blog_entry_id: Some(hatena_blog_entry.id.to_string()),
+        hatena_blog_entry_url: Some(hatena_blog_entry.url.clone()),
+        ..meta
+    }
 }
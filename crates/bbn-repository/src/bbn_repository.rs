@@ -2,6 +2,7 @@ use std::convert::TryFrom;
 use std::ffi::OsStr;
 use std::fs;
 use std::io;
+use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -9,18 +10,24 @@ use std::str::FromStr;
 use anyhow::Context;
 use date_range::date::Date;
 use date_range::date::YearMonth;
+use pulldown_cmark::Event;
+use pulldown_cmark::Parser;
+use pulldown_cmark::Tag;
 use serde::Deserialize;
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::journal;
 use crate::query::Query;
+use crate::validation;
+use crate::validation::Problem;
 use bbn_data::DateTime;
 use bbn_data::Entry;
 use bbn_data::EntryId;
 use bbn_data::EntryMeta;
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
-struct MetaJson {
+pub(crate) struct MetaJson {
     #[serde(skip_serializing_if = "Option::is_none")]
     hatena_blog_entry_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -100,10 +107,28 @@ impl BbnRepository {
 
     pub fn find_ids_by_query(&self, query: Query) -> anyhow::Result<Vec<EntryId>> {
         let posts = list_posts(self.data_dir.as_path(), &query)?;
-        posts
+        let entry_ids = posts
             .into_iter()
             .map(|post| self.post_to_entry_id(post))
-            .collect::<anyhow::Result<Vec<EntryId>>>()
+            .collect::<anyhow::Result<Vec<EntryId>>>()?;
+        if !query.requires_meta() {
+            return Ok(entry_ids);
+        }
+        let mut filtered = vec![];
+        for entry_id in entry_ids {
+            let meta = self
+                .find_meta_by_id(&entry_id)?
+                .with_context(|| format!("meta not found: {entry_id}"))?;
+            let content = if query.requires_content() {
+                self.find_content_by_id(&entry_id)?
+            } else {
+                None
+            };
+            if query.match_entry(&entry_id, &meta, content.as_deref()) {
+                filtered.push(entry_id);
+            }
+        }
+        Ok(filtered)
     }
 
     pub fn find_meta_by_id(&self, entry_id: &EntryId) -> anyhow::Result<Option<EntryMeta>> {
@@ -121,26 +146,156 @@ impl BbnRepository {
     }
 
     pub fn save(&self, entry: Entry) -> anyhow::Result<()> {
+        let _lock = self.lock_and_recover()?;
         // meta
         let meta_path = self
             .data_dir
             .join(entry.id().date().year().to_string())
             .join(entry.id().date().month().to_string())
             .join(format!("{}.json", entry.id()));
-        fs::create_dir_all(meta_path.parent().context("no parent dir")?)?;
         let meta_json = MetaJson::from(entry.meta().clone());
         let meta_json_content = serde_json::to_string(&meta_json)?;
-        fs::write(meta_path.as_path(), meta_json_content)?;
         // content
         let content_path = self
             .data_dir
             .join(entry.id().date().year().to_string())
             .join(entry.id().date().month().to_string())
             .join(format!("{}.md", entry.id()));
-        fs::write(content_path.as_path(), entry.content())?;
+        // meta と content の片方だけが更新された状態にならないようにまとめて置き換える
+        journal::write_files(
+            self.data_dir.as_path(),
+            &[
+                (meta_path.as_path(), meta_json_content.as_bytes()),
+                (content_path.as_path(), entry.content().as_bytes()),
+            ],
+            &[],
+        )?;
         Ok(())
     }
 
+    /// Deletes the entry
+    pub fn delete(&self, entry_id: &EntryId) -> anyhow::Result<()> {
+        let _lock = self.lock_and_recover()?;
+        if self.find_meta_by_id(entry_id)?.is_none() {
+            anyhow::bail!("the entry does not exist: {entry_id}");
+        }
+        let meta_path = self.meta_path(entry_id);
+        let content_path = self.content_path(entry_id);
+        journal::write_files(
+            self.data_dir.as_path(),
+            &[],
+            &[meta_path.as_path(), content_path.as_path()],
+        )
+    }
+
+    /// Changes the id title of the entry (e.g. 2021-07-06 -> 2021-07-06-id1)
+    pub fn rename_id_title(
+        &self,
+        entry_id: &EntryId,
+        id_title: Option<String>,
+    ) -> anyhow::Result<EntryId> {
+        self.relocate(entry_id, EntryId::new(*entry_id.date(), id_title))
+    }
+
+    /// Moves the entry to the date and rewrites `[YYYY-MM-DD]` links in the entries
+    pub fn move_to_date(&self, entry_id: &EntryId, date: Date) -> anyhow::Result<EntryId> {
+        self.relocate(
+            entry_id,
+            EntryId::new(date, entry_id.id_title().map(str::to_string)),
+        )
+    }
+
+    /// Moves the entry to `new_entry_id` (the date and the id title) in a single write
+    ///
+    /// The date change rewrites `[YYYY-MM-DD]` links in the entries
+    pub fn relocate(&self, entry_id: &EntryId, new_entry_id: EntryId) -> anyhow::Result<EntryId> {
+        let _lock = self.lock_and_recover()?;
+        if let Some(id_title) = new_entry_id.id_title() {
+            EntryId::validate_id_title(id_title)?;
+        }
+        let entry = self
+            .find_entry_by_id(entry_id)?
+            .with_context(|| format!("the entry does not exist: {entry_id}"))?;
+        if &new_entry_id == entry_id {
+            return Ok(new_entry_id);
+        }
+        let from = *entry_id.date();
+        let to = *new_entry_id.date();
+        // 同じ日付のエントリーは 1 つだけにする
+        if let Some(other) = self.find_id_by_date(to)?
+            && &other != entry_id
+        {
+            anyhow::bail!("the entry already exists: {other}");
+        }
+
+        let meta_json_content = serde_json::to_string(&MetaJson::from(entry.meta().clone()))?;
+        let content = rewrite_date_links(entry.content(), from, to);
+        let mut files = vec![
+            (self.meta_path(&new_entry_id), meta_json_content),
+            (self.content_path(&new_entry_id), content),
+        ];
+        if from != to {
+            for other in self.find_ids_by_query(Query::try_from("")?)? {
+                if &other == entry_id {
+                    continue;
+                }
+                let content = self
+                    .find_content_by_id(&other)?
+                    .with_context(|| format!("content not found: {other}"))?;
+                let rewritten = rewrite_date_links(content.as_str(), from, to);
+                if rewritten != content {
+                    files.push((self.content_path(&other), rewritten));
+                }
+            }
+        }
+        let meta_path = self.meta_path(entry_id);
+        let content_path = self.content_path(entry_id);
+        journal::write_files(
+            self.data_dir.as_path(),
+            &files
+                .iter()
+                .map(|(path, contents)| (path.as_path(), contents.as_bytes()))
+                .collect::<Vec<(&Path, &[u8])>>(),
+            &[meta_path.as_path(), content_path.as_path()],
+        )?;
+        Ok(new_entry_id)
+    }
+
+    /// Checks the consistency of the data directory
+    pub fn validate(&self) -> anyhow::Result<Vec<Problem>> {
+        let _lock = self.lock_and_recover()?;
+        validation::validate(self.data_dir.as_path())
+    }
+
+    /// Fixes the problem if it is safe. Returns `true` if the problem is fixed.
+    pub fn fix(&self, problem: &Problem) -> anyhow::Result<bool> {
+        let _lock = journal::Lock::acquire(self.data_dir.as_path())?;
+        validation::fix(problem)
+    }
+
+    fn meta_path(&self, entry_id: &EntryId) -> PathBuf {
+        self.data_dir
+            .join(entry_id.date().year().to_string())
+            .join(entry_id.date().month().to_string())
+            .join(format!("{entry_id}.json"))
+    }
+
+    fn content_path(&self, entry_id: &EntryId) -> PathBuf {
+        self.data_dir
+            .join(entry_id.date().year().to_string())
+            .join(entry_id.date().month().to_string())
+            .join(format!("{entry_id}.md"))
+    }
+
+    /// Locks the data directory and completes or discards the write interrupted by a crash
+    ///
+    /// 読み込みでは呼ばない (別のプロセスの書き込み中のジャーナルを捨ててしまうため)
+    fn lock_and_recover(&self) -> anyhow::Result<journal::Lock> {
+        let lock = journal::Lock::acquire(self.data_dir.as_path())?;
+        journal::recover(self.data_dir.as_path())?;
+        Ok(lock)
+    }
+
     fn find_ids_by_year_month(&self, year_month: YearMonth) -> anyhow::Result<Vec<EntryId>> {
         let entry_dir = self
             .data_dir
@@ -171,6 +326,52 @@ impl BbnRepository {
     }
 }
 
+/// `[2021-07-06]` と `[2021-07-06]: https://.../2021/07/06/` を `to` の日付に書き換える
+///
+/// コードブロックとインラインコードの中は書き換えない
+fn rewrite_date_links(content: &str, from: Date, to: Date) -> String {
+    let from_link = format!("[{from}]");
+    let to_link = format!("[{to}]");
+    let from_path = format!("/{}/", from.to_string().replace('-', "/"));
+    let to_path = format!("/{}/", to.to_string().replace('-', "/"));
+    let code_ranges = code_ranges(content);
+    let mut rewritten = String::with_capacity(content.len());
+    let mut last = 0;
+    for (index, _) in content.match_indices(from_link.as_str()) {
+        if index < last || code_ranges.iter().any(|range| range.contains(&index)) {
+            continue;
+        }
+        rewritten.push_str(&content[last..index]);
+        rewritten.push_str(to_link.as_str());
+        last = index + from_link.len();
+        let rest = &content[last..];
+        let is_line_start = index == 0 || content[..index].ends_with('\n');
+        if is_line_start && rest.starts_with(':') {
+            // 参照定義の URL も書き換える
+            let end = rest.find('\n').map_or(content.len(), |i| last + i);
+            rewritten.push_str(
+                content[last..end]
+                    .replace(from_path.as_str(), to_path.as_str())
+                    .as_str(),
+            );
+            last = end;
+        }
+    }
+    rewritten.push_str(&content[last..]);
+    rewritten
+}
+
+/// コードブロックとインラインコードのバイト範囲
+fn code_ranges(content: &str) -> Vec<Range<usize>> {
+    Parser::new(content)
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Code(_) | Event::Start(Tag::CodeBlock(_)) => Some(range),
+            _ => None,
+        })
+        .collect()
+}
+
 #[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
 struct Post {
     pub date: String,
@@ -204,6 +405,9 @@ fn list_posts_year(path: &Path, query: &Query) -> Result<Vec<Post>, ListPostsErr
     let mut years = vec![];
     for dir_entry in path.read_dir()? {
         let path_buf = dir_entry?.path();
+        if !path_buf.is_dir() {
+            continue;
+        }
         if let Some(year) = path_buf.file_name() {
             if query.match_year(year) {
                 years.push(path_buf);
@@ -224,6 +428,9 @@ fn list_posts_month(path: &Path, query: &Query) -> Result<Vec<Post>, ListPostsEr
     let mut months = vec![];
     for dir_entry in path.read_dir()? {
         let path_buf = dir_entry?.path();
+        if !path_buf.is_dir() {
+            continue;
+        }
         if let Some(month) = path_buf.file_name() {
             if query.match_month(month) {
                 months.push(path_buf);
@@ -240,40 +447,30 @@ fn list_posts_month(path: &Path, query: &Query) -> Result<Vec<Post>, ListPostsEr
 }
 
 fn list_posts_day(path: &Path, query: &Query) -> Result<Vec<Post>, ListPostsError> {
-    let mut days = vec![];
+    let mut posts = vec![];
     for dir_entry in path.read_dir()? {
         let path_buf = dir_entry?.path();
-        if path_buf.extension() != Some(OsStr::new("json")) {
+        if !path_buf.is_file() || path_buf.extension() != Some(OsStr::new("json")) {
             continue;
         }
         // YYYY-MM-DD(-ID_TITLE).json
-        if let Some(day) = path_buf
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .and_then(|s| s.get(8..8 + 2))
-            .map(OsStr::new)
-        {
-            if query.match_day(day) {
-                if let Some(date) = path_buf
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .and_then(|s| s.get(0..10))
-                {
-                    if query.match_date(date) {
-                        days.push(path_buf);
-                    }
-                }
-            }
+        // 不正なファイル名は無視する (bbn fsck で報告する)
+        let date = match get_date(path_buf.as_path()) {
+            Some(date) => date,
+            None => continue,
+        };
+        let day = match date.get(8..8 + 2) {
+            Some(day) => OsStr::new(day),
+            None => continue,
+        };
+        if query.match_day(day) && query.match_date(date) {
+            let id_title = get_id_title(path_buf.as_path()).map(|s| s.to_string());
+            posts.push(Post {
+                date: date.to_string(),
+                id_title,
+            });
         }
     }
-
-    let mut posts = vec![];
-    for day in days {
-        let path_buf = path.join(day);
-        let date = get_date(path_buf.as_path()).unwrap().to_string();
-        let id_title = get_id_title(path_buf.as_path()).map(|s| s.to_string());
-        posts.push(Post { date, id_title });
-    }
     Ok(posts)
 }
 
@@ -398,14 +595,59 @@ mod tests {
 
     #[test]
     fn find_ids_by_query_test() -> anyhow::Result<()> {
-        // TODO: Query
-
         let temp_dir = tempdir()?;
         let data_dir = create_test_dir(temp_dir.path())?;
 
         let repository = BbnRepository::new(data_dir);
         let entry_ids = repository.find_ids_by_query(Query::try_from("")?)?;
 
+        assert_eq!(
+            entry_ids,
+            vec![
+                EntryId::from_str("2021-07-06")?,
+                EntryId::from_str("2021-07-07-id1")?
+            ]
+        );
+
+        let entry_ids = repository.find_ids_by_query(Query::try_from("date:2021-07-07")?)?;
+        assert_eq!(entry_ids, vec![EntryId::from_str("2021-07-07-id1")?]);
+
+        let entry_ids = repository.find_ids_by_query(Query::try_from("tag:tag1")?)?;
+        assert_eq!(entry_ids, vec![EntryId::from_str("2021-07-06")?]);
+
+        let entry_ids = repository.find_ids_by_query(Query::try_from("date:2021-07 tag:tag1")?)?;
+        assert_eq!(entry_ids, vec![EntryId::from_str("2021-07-06")?]);
+
+        let entry_ids =
+            repository.find_ids_by_query(Query::try_from("date:2021-07-07 tag:tag1")?)?;
+        assert_eq!(entry_ids, vec![]);
+
+        let entry_ids = repository.find_ids_by_query(Query::try_from("tag:tag2")?)?;
+        assert_eq!(entry_ids, vec![]);
+
+        let entry_ids = repository.find_ids_by_query(Query::try_from("title:title2")?)?;
+        assert_eq!(entry_ids, vec![EntryId::from_str("2021-07-07-id1")?]);
+
+        let entry_ids = repository.find_ids_by_query(Query::try_from("content1")?)?;
+        assert_eq!(entry_ids, vec![EntryId::from_str("2021-07-06")?]);
+
+        let entry_ids = repository.find_ids_by_query(Query::try_from("-date:2021-07-06")?)?;
+        assert_eq!(entry_ids, vec![EntryId::from_str("2021-07-07-id1")?]);
+
+        let entry_ids = repository.find_ids_by_query(Query::try_from("-tag:tag1")?)?;
+        assert_eq!(entry_ids, vec![EntryId::from_str("2021-07-07-id1")?]);
+
+        let entry_ids =
+            repository.find_ids_by_query(Query::try_from("tag:tag1 OR title:title2")?)?;
+        assert_eq!(
+            entry_ids,
+            vec![
+                EntryId::from_str("2021-07-06")?,
+                EntryId::from_str("2021-07-07-id1")?
+            ]
+        );
+
+        let entry_ids = repository.find_ids_by_query(Query::try_from("title content")?)?;
         assert_eq!(
             entry_ids,
             vec![
@@ -511,4 +753,340 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn save_recovers_interrupted_write_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = create_test_dir(temp_dir.path())?;
+        let repository = BbnRepository::new(data_dir.clone());
+        let entry_dir = data_dir.join("2021").join("07");
+        let meta_path = entry_dir.join("2021-07-06.json");
+        let content_path = entry_dir.join("2021-07-06.md");
+        let journal_path = data_dir.join(".bbn-journal.json");
+        let entry_id = EntryId::from_str("2021-07-06")?;
+        let entry = repository.find_entry_by_id(&entry_id)?;
+
+        // meta の一時ファイルを書いたあと、content を書く前に中断
+        let mut journal = journal::Journal::new(&data_dir, &[&meta_path, &content_path], &[])?;
+        journal.prepare(&data_dir)?;
+        journal.write_temp(&data_dir, 0, b"{}")?;
+        // 読み込みでは書き込み中かもしれないジャーナルを捨てない
+        assert_eq!(repository.find_entry_by_id(&entry_id)?, entry);
+        assert!(journal_path.exists());
+        assert_eq!(repository.validate()?, vec![]);
+        assert!(!journal_path.exists());
+        assert_eq!(repository.find_entry_by_id(&entry_id)?, entry);
+
+        // 確定後、meta だけ rename したところで中断
+        let mut journal = journal::Journal::new(&data_dir, &[&meta_path, &content_path], &[])?;
+        journal.prepare(&data_dir)?;
+        journal.write_temp(
+            &data_dir,
+            0,
+            br#"{"minutes":7,"pubdate":"2021-07-06T23:59:59+09:00","tags":[],"title":"TITLE3"}"#,
+        )?;
+        journal.write_temp(&data_dir, 1, b"CONTENT3")?;
+        journal.commit(&data_dir)?;
+        fs::rename(entry_dir.join(".2021-07-06.json.tmp"), meta_path.as_path())?;
+        repository.save(
+            repository
+                .find_entry_by_id(&EntryId::from_str("2021-07-07-id1")?)?
+                .unwrap(),
+        )?;
+        assert!(!journal_path.exists());
+        assert_eq!(
+            repository.find_entry_by_id(&entry_id)?,
+            Some(Entry::new(
+                entry_id.clone(),
+                EntryMeta::new(
+                    7,
+                    DateTime::from_str("2021-07-06T23:59:59+09:00")?,
+                    vec![],
+                    "TITLE3".to_string()
+                ),
+                "CONTENT3".to_string()
+            ))
+        );
+        assert_eq!(repository.validate()?, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn delete_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = create_test_dir(temp_dir.path())?;
+        let repository = BbnRepository::new(data_dir.clone());
+        let entry_id = EntryId::from_str("2021-07-06")?;
+        repository.delete(&entry_id)?;
+        assert_eq!(repository.find_entry_by_id(&entry_id)?, None);
+        assert!(
+            !data_dir
+                .join("2021")
+                .join("07")
+                .join("2021-07-06.md")
+                .exists()
+        );
+        assert!(repository.delete(&entry_id).is_err());
+        assert_eq!(repository.validate()?, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn rename_id_title_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = create_test_dir(temp_dir.path())?;
+        let repository = BbnRepository::new(data_dir.clone());
+        let entry_id = EntryId::from_str("2021-07-07-id1")?;
+        let entry = repository
+            .find_entry_by_id(&entry_id)?
+            .context("not found")?;
+
+        let renamed = repository.rename_id_title(&entry_id, Some("id2".to_string()))?;
+        assert_eq!(renamed, EntryId::from_str("2021-07-07-id2")?);
+        assert_eq!(repository.find_entry_by_id(&entry_id)?, None);
+        let found = repository
+            .find_entry_by_id(&renamed)?
+            .context("not found")?;
+        assert_eq!(found.meta(), entry.meta());
+        assert_eq!(found.content(), entry.content());
+
+        let renamed = repository.rename_id_title(&renamed, None)?;
+        assert_eq!(renamed, EntryId::from_str("2021-07-07")?);
+        assert!(
+            repository
+                .rename_id_title(&renamed, Some("../id".to_string()))
+                .is_err()
+        );
+        assert!(
+            repository
+                .rename_id_title(&EntryId::from_str("2021-07-08")?, None)
+                .is_err()
+        );
+        assert_eq!(repository.validate()?, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn move_to_date_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = create_test_dir(temp_dir.path())?;
+        let repository = BbnRepository::new(data_dir.clone());
+        let entry_dir = data_dir.join("2021").join("07");
+        fs::write(
+            entry_dir.join("2021-07-06.md"),
+            "see [2021-07-07]\n\n[2021-07-07]: https://blog.bouzuya.net/2021/07/07/\n",
+        )?;
+
+        // 既存のエントリーは上書きしない
+        let entry_id = EntryId::from_str("2021-07-07-id1")?;
+        assert!(
+            repository
+                .move_to_date(&entry_id, Date::from_str("2021-07-06")?)
+                .is_err()
+        );
+
+        let moved = repository.move_to_date(&entry_id, Date::from_str("2021-08-01")?)?;
+        assert_eq!(moved, EntryId::from_str("2021-08-01-id1")?);
+        assert_eq!(repository.find_entry_by_id(&entry_id)?, None);
+        assert_eq!(
+            repository.find_content_by_id(&moved)?,
+            Some("CONTENT2".to_string())
+        );
+        assert_eq!(
+            repository.find_content_by_id(&EntryId::from_str("2021-07-06")?)?,
+            Some(
+                "see [2021-08-01]\n\n[2021-08-01]: https://blog.bouzuya.net/2021/08/01/\n"
+                    .to_string()
+            )
+        );
+        assert_eq!(repository.validate()?, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn relocate_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = create_test_dir(temp_dir.path())?;
+        let repository = BbnRepository::new(data_dir.clone());
+        let entry_id = EntryId::from_str("2021-07-07-id1")?;
+        let entry = repository
+            .find_entry_by_id(&entry_id)?
+            .context("not found")?;
+
+        // 移動先の日付が使われているときは何も変更しない
+        assert!(
+            repository
+                .relocate(&entry_id, EntryId::from_str("2021-07-06-id2")?)
+                .is_err()
+        );
+        let found = repository
+            .find_entry_by_id(&entry_id)?
+            .context("not found")?;
+        assert_eq!(found.meta(), entry.meta());
+        assert_eq!(found.content(), entry.content());
+        assert_eq!(
+            repository.find_entry_by_id(&EntryId::from_str("2021-07-07-id2")?)?,
+            None
+        );
+
+        let relocated = repository.relocate(&entry_id, EntryId::from_str("2021-08-01-id2")?)?;
+        assert_eq!(relocated, EntryId::from_str("2021-08-01-id2")?);
+        assert_eq!(repository.find_entry_by_id(&entry_id)?, None);
+        let found = repository
+            .find_entry_by_id(&relocated)?
+            .context("not found")?;
+        assert_eq!(found.meta(), entry.meta());
+        assert_eq!(found.content(), entry.content());
+        assert_eq!(repository.validate()?, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_date_links_test() -> anyhow::Result<()> {
+        let from = Date::from_str("2021-07-06")?;
+        let to = Date::from_str("2021-08-01")?;
+        assert_eq!(rewrite_date_links("", from, to), "");
+        assert_eq!(
+            rewrite_date_links("[2021-07-06] [2021-07-07]", from, to),
+            "[2021-08-01] [2021-07-07]"
+        );
+        assert_eq!(
+            rewrite_date_links(
+                "/2021/07/06/\n[2021-07-06]: https://example.com/2021/07/06/",
+                from,
+                to
+            ),
+            "/2021/07/06/\n[2021-08-01]: https://example.com/2021/08/01/"
+        );
+        // コードの中は書き換えない
+        assert_eq!(
+            rewrite_date_links(
+                "`[2021-07-06]` [2021-07-06]\n\n```\n[2021-07-06]\n[2021-07-06]: /2021/07/06/\n```\n\n    [2021-07-06]\n",
+                from,
+                to
+            ),
+            "`[2021-07-06]` [2021-08-01]\n\n```\n[2021-07-06]\n[2021-07-06]: /2021/07/06/\n```\n\n    [2021-07-06]\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn find_ids_by_query_ignores_invalid_paths_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = create_test_dir(temp_dir.path())?;
+        let entry_dir = data_dir.join("2021").join("07");
+        fs::write(entry_dir.join("2021.json"), "{}")?;
+        fs::create_dir_all(entry_dir.join("2021-07-08.json"))?;
+        fs::write(data_dir.join("README"), "")?;
+        fs::write(data_dir.join("2021").join("README"), "")?;
+
+        let repository = BbnRepository::new(data_dir);
+        let entry_ids = repository.find_ids_by_query(Query::try_from("")?)?;
+        assert_eq!(
+            entry_ids,
+            vec![
+                EntryId::from_str("2021-07-06")?,
+                EntryId::from_str("2021-07-07-id1")?
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn validate_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = create_test_dir(temp_dir.path())?;
+        let repository = BbnRepository::new(data_dir.clone());
+        assert_eq!(repository.validate()?, vec![]);
+
+        let entry_dir = data_dir.join("2021").join("07");
+        fs::write(
+            entry_dir.join("2021-07-07.json"),
+            r#"{"minutes":6,"pubdate":"2021-07-07T23:59:59+09:00","tags":[],"title":"TITLE3"}"#,
+        )?;
+        fs::write(entry_dir.join("2021-07-07.md"), "")?;
+        fs::write(
+            entry_dir.join("2021-07-08.json"),
+            r#"{"minutes":6,"pubdate":"2021-07-08","tags":[],"title":"TITLE4"}"#,
+        )?;
+        fs::write(entry_dir.join("2021-07-09.md"), "")?;
+        fs::write(entry_dir.join("2021.json"), "")?;
+        fs::write(
+            entry_dir.join("2021-08-01.json"),
+            r#"{"minutes":6,"pubdate":"2021-08-01T23:59:59+09:00","tags":[],"title":"TITLE5"}"#,
+        )?;
+        fs::write(entry_dir.join("2021-08-01.md"), "")?;
+        fs::create_dir_all(data_dir.join("foo"))?;
+
+        let problems = repository.validate()?;
+        assert_eq!(
+            problems,
+            vec![
+                Problem::InvalidMeta {
+                    path: entry_dir.join("2021-07-08.json"),
+                    message: "parse date time error".to_string()
+                },
+                Problem::MisplacedEntry {
+                    path: entry_dir.join("2021-08-01.json"),
+                    expected: data_dir.join("2021").join("08").join("2021-08-01.json")
+                },
+                Problem::MisplacedEntry {
+                    path: entry_dir.join("2021-08-01.md"),
+                    expected: data_dir.join("2021").join("08").join("2021-08-01.md")
+                },
+                Problem::InvalidFileName {
+                    path: entry_dir.join("2021.json")
+                },
+                Problem::UnexpectedPath {
+                    path: data_dir.join("foo")
+                },
+                Problem::MissingContent {
+                    path: entry_dir.join("2021-07-08.json"),
+                    expected: entry_dir.join("2021-07-08.md")
+                },
+                Problem::MissingMeta {
+                    path: entry_dir.join("2021-07-09.md"),
+                    expected: entry_dir.join("2021-07-09.json")
+                },
+                Problem::DuplicateDate {
+                    date: Date::from_str("2021-07-07")?,
+                    paths: vec![
+                        entry_dir.join("2021-07-07.json"),
+                        entry_dir.join("2021-07-07-id1.json")
+                    ]
+                },
+            ]
+        );
+
+        let fixed = problems
+            .iter()
+            .filter(|p| p.is_fixable())
+            .map(|p| repository.fix(p))
+            .collect::<anyhow::Result<Vec<bool>>>()?;
+        assert_eq!(fixed, vec![true, true, true]);
+        assert!(
+            data_dir
+                .join("2021")
+                .join("08")
+                .join("2021-08-01.json")
+                .is_file()
+        );
+        assert!(
+            data_dir
+                .join("2021")
+                .join("08")
+                .join("2021-08-01.md")
+                .is_file()
+        );
+        assert_eq!(fs::read_to_string(entry_dir.join("2021-07-08.md"))?, "");
+        assert_eq!(
+            repository
+                .validate()?
+                .iter()
+                .filter(|p| p.is_fixable())
+                .count(),
+            0
+        );
+        Ok(())
+    }
 }
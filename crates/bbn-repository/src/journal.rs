@@ -0,0 +1,396 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use bbn_data::hash;
+use serde::Deserialize;
+use serde::Serialize;
+
+// data_dir/.bbn-journal.json
+const JOURNAL_FILE_NAME: &str = ".bbn-journal.json";
+// data_dir/.bbn-lock
+const LOCK_FILE_NAME: &str = ".bbn-lock";
+
+/// data_dir への書き込みと `recover` を排他するためのロック
+///
+/// 別のプロセスがロックしている間は待つ。drop で解放する。
+pub(crate) struct Lock {
+    _file: fs::File,
+}
+
+impl Lock {
+    pub(crate) fn acquire(data_dir: &Path) -> anyhow::Result<Self> {
+        fs::create_dir_all(data_dir)?;
+        // ロックファイルは消さない (消すと別のファイルをロックするプロセスが出てくる)
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(data_dir.join(LOCK_FILE_NAME))?;
+        file.lock()?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// 複数のファイルをまとめて置き換えるためのジャーナル
+///
+/// 1. `committed: false` のジャーナルを書く
+/// 2. 一時ファイルを書く
+/// 3. `committed: true` にする (ここで確定する)
+/// 4. 一時ファイルを rename し、削除するファイルを消して、ジャーナルを消す
+///
+/// 途中で中断された場合は `recover` で、確定前なら一時ファイルを捨て、確定後なら rename をやり直す。
+/// `recover` は別のプロセスの書き込み中のジャーナルを捨てないように `Lock` を取得してから呼ぶ。
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct Journal {
+    committed: bool,
+    files: Vec<JournalFile>,
+    /// 削除するファイルの data_dir からの相対パス
+    #[serde(default)]
+    removes: Vec<PathBuf>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct JournalFile {
+    /// data_dir からの相対パス
+    path: PathBuf,
+    /// data_dir からの相対パス
+    temp: PathBuf,
+    /// 書き込む内容のハッシュ (rename 済みかを確かめるため)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+}
+
+impl Journal {
+    pub(crate) fn new(data_dir: &Path, paths: &[&Path], removes: &[&Path]) -> anyhow::Result<Self> {
+        let files = paths
+            .iter()
+            .map(|path| {
+                let path = relative_path(data_dir, path)?;
+                Ok(JournalFile {
+                    hash: None,
+                    temp: temp_path(path.as_path())?,
+                    path,
+                })
+            })
+            .collect::<anyhow::Result<Vec<JournalFile>>>()?;
+        let removes = removes
+            .iter()
+            .map(|path| relative_path(data_dir, path))
+            .collect::<anyhow::Result<Vec<PathBuf>>>()?;
+        Ok(Self {
+            committed: false,
+            files,
+            removes,
+        })
+    }
+
+    pub(crate) fn prepare(&self, data_dir: &Path) -> anyhow::Result<()> {
+        if journal_path(data_dir).exists() {
+            anyhow::bail!("the journal already exists");
+        }
+        self.write(data_dir)
+    }
+
+    /// `index` 番目のファイルの内容を一時ファイルに書く
+    pub(crate) fn write_temp(
+        &mut self,
+        data_dir: &Path,
+        index: usize,
+        contents: &[u8],
+    ) -> anyhow::Result<()> {
+        let file = self.files.get_mut(index).context("index out of range")?;
+        let path = data_dir.join(file.path.as_path());
+        fs::create_dir_all(path.parent().context("no parent dir")?)?;
+        write_and_sync(data_dir.join(file.temp.as_path()).as_path(), contents)?;
+        file.hash = Some(hash(&[contents]));
+        Ok(())
+    }
+
+    pub(crate) fn commit(&mut self, data_dir: &Path) -> anyhow::Result<()> {
+        if self.files.iter().any(|file| file.hash.is_none()) {
+            anyhow::bail!("some temp files are not written");
+        }
+        self.committed = true;
+        self.write(data_dir)
+    }
+
+    pub(crate) fn apply(&self, data_dir: &Path) -> anyhow::Result<()> {
+        if !self.committed {
+            anyhow::bail!("the journal is not committed");
+        }
+        for file in self.files.iter() {
+            let temp = data_dir.join(file.temp.as_path());
+            let path = data_dir.join(file.path.as_path());
+            if temp.is_file() {
+                fs::rename(temp, path)?;
+                continue;
+            }
+            // 前回の apply で rename 済みのものは飛ばす
+            let replaced = fs::read(path.as_path())
+                .is_ok_and(|contents| Some(hash(&[contents.as_slice()])) == file.hash);
+            if !replaced {
+                anyhow::bail!("the temp file is missing: {}", temp.display());
+            }
+        }
+        for path in self.removes.iter() {
+            let path = data_dir.join(path);
+            if path.is_file() {
+                fs::remove_file(path)?;
+            }
+        }
+        fs::remove_file(journal_path(data_dir))?;
+        Ok(())
+    }
+
+    fn rollback(&self, data_dir: &Path) -> anyhow::Result<()> {
+        for file in self.files.iter() {
+            let temp = data_dir.join(file.temp.as_path());
+            if temp.is_file() {
+                fs::remove_file(temp)?;
+            }
+        }
+        fs::remove_file(journal_path(data_dir))?;
+        Ok(())
+    }
+
+    fn write(&self, data_dir: &Path) -> anyhow::Result<()> {
+        // ジャーナル自体も一時ファイル経由で置き換える
+        let path = journal_path(data_dir);
+        let temp = data_dir.join(temp_path(Path::new(JOURNAL_FILE_NAME))?);
+        fs::create_dir_all(data_dir)?;
+        write_and_sync(temp.as_path(), serde_json::to_string(self)?.as_bytes())?;
+        fs::rename(temp, path)?;
+        Ok(())
+    }
+}
+
+/// `paths` と `contents` の組をまとめて置き換え、`removes` を削除する
+pub(crate) fn write_files(
+    data_dir: &Path,
+    files: &[(&Path, &[u8])],
+    removes: &[&Path],
+) -> anyhow::Result<()> {
+    let paths = files.iter().map(|(path, _)| *path).collect::<Vec<&Path>>();
+    let mut journal = Journal::new(data_dir, &paths, removes)?;
+    journal.prepare(data_dir)?;
+    for (index, (_, contents)) in files.iter().enumerate() {
+        journal.write_temp(data_dir, index, contents)?;
+    }
+    journal.commit(data_dir)?;
+    journal.apply(data_dir)
+}
+
+/// 中断された書き込みを完了させるか、なかったことにする
+///
+/// `Lock` を取得してから呼ぶこと
+pub(crate) fn recover(data_dir: &Path) -> anyhow::Result<()> {
+    let temp = data_dir.join(temp_path(Path::new(JOURNAL_FILE_NAME))?);
+    if temp.is_file() {
+        fs::remove_file(temp)?;
+    }
+    let path = journal_path(data_dir);
+    if !path.is_file() {
+        return Ok(());
+    }
+    let journal = serde_json::from_str::<'_, Journal>(fs::read_to_string(path)?.as_str())
+        .context("invalid journal")?;
+    if journal.committed {
+        journal.apply(data_dir)
+    } else {
+        journal.rollback(data_dir)
+    }
+}
+
+fn relative_path(data_dir: &Path, path: &Path) -> anyhow::Result<PathBuf> {
+    Ok(path
+        .strip_prefix(data_dir)
+        .with_context(|| format!("not in the data dir: {}", path.display()))?
+        .to_path_buf())
+}
+
+fn journal_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(JOURNAL_FILE_NAME)
+}
+
+// rename が atomic になるように同じディレクトリに置く
+// e.g. 2021/07/2021-07-06.json -> 2021/07/.2021-07-06.json.tmp
+fn temp_path(path: &Path) -> anyhow::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .context("no file name")?;
+    Ok(path.with_file_name(format!(".{file_name}.tmp")))
+}
+
+fn write_and_sync(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn temp_path_test() -> anyhow::Result<()> {
+        assert_eq!(
+            temp_path(Path::new("2021/07/2021-07-06.json"))?,
+            PathBuf::from("2021/07/.2021-07-06.json.tmp")
+        );
+        assert_eq!(
+            temp_path(Path::new(".bbn-journal.json"))?,
+            PathBuf::from("..bbn-journal.json.tmp")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn write_files_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        let a = data_dir.join("2021").join("07").join("a.json");
+        let b = data_dir.join("2021").join("07").join("a.md");
+        write_files(&data_dir, &[(&a, b"A1"), (&b, b"B1")], &[])?;
+        assert_eq!(fs::read_to_string(a.as_path())?, "A1");
+        assert_eq!(fs::read_to_string(b.as_path())?, "B1");
+        assert!(!journal_path(&data_dir).exists());
+        assert_eq!(fs::read_dir(data_dir.join("2021").join("07"))?.count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn write_files_with_removes_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        let a = data_dir.join("2021").join("07").join("a.md");
+        let b = data_dir.join("2021").join("08").join("b.md");
+        write_files(&data_dir, &[(&a, b"A1")], &[])?;
+        write_files(&data_dir, &[(&b, b"A1")], &[&a])?;
+        assert!(!a.exists());
+        assert_eq!(fs::read_to_string(b.as_path())?, "A1");
+
+        // 確定後、rename だけしたところで中断
+        let mut journal = Journal::new(&data_dir, &[&a], &[&b])?;
+        journal.prepare(&data_dir)?;
+        journal.write_temp(&data_dir, 0, b"A1")?;
+        journal.commit(&data_dir)?;
+        fs::rename(data_dir.join(journal.files[0].temp.as_path()), a.as_path())?;
+
+        recover(&data_dir)?;
+        assert_eq!(fs::read_to_string(a.as_path())?, "A1");
+        assert!(!b.exists());
+        assert!(!journal_path(&data_dir).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn recover_before_commit_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        let a = data_dir.join("2021").join("07").join("a.json");
+        let b = data_dir.join("2021").join("07").join("a.md");
+        write_files(&data_dir, &[(&a, b"A1"), (&b, b"B1")], &[])?;
+
+        // 1 つめの一時ファイルを書いたところで中断
+        let mut journal = Journal::new(&data_dir, &[&a, &b], &[])?;
+        journal.prepare(&data_dir)?;
+        journal.write_temp(&data_dir, 0, b"A2")?;
+
+        recover(&data_dir)?;
+        assert_eq!(fs::read_to_string(a.as_path())?, "A1");
+        assert_eq!(fs::read_to_string(b.as_path())?, "B1");
+        assert!(!journal_path(&data_dir).exists());
+        assert_eq!(fs::read_dir(data_dir.join("2021").join("07"))?.count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn recover_after_commit_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        let a = data_dir.join("2021").join("07").join("a.json");
+        let b = data_dir.join("2021").join("07").join("a.md");
+        write_files(&data_dir, &[(&a, b"A1"), (&b, b"B1")], &[])?;
+
+        // 1 つめだけ rename したところで中断
+        let mut journal = Journal::new(&data_dir, &[&a, &b], &[])?;
+        journal.prepare(&data_dir)?;
+        journal.write_temp(&data_dir, 0, b"A2")?;
+        journal.write_temp(&data_dir, 1, b"B2")?;
+        journal.commit(&data_dir)?;
+        fs::rename(data_dir.join(journal.files[0].temp.as_path()), a.as_path())?;
+
+        recover(&data_dir)?;
+        assert_eq!(fs::read_to_string(a.as_path())?, "A2");
+        assert_eq!(fs::read_to_string(b.as_path())?, "B2");
+        assert!(!journal_path(&data_dir).exists());
+        assert_eq!(fs::read_dir(data_dir.join("2021").join("07"))?.count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn apply_fails_if_temp_file_is_missing_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        let a = data_dir.join("2021").join("07").join("a.json");
+        let b = data_dir.join("2021").join("07").join("a.md");
+        write_files(&data_dir, &[(&a, b"A1"), (&b, b"B1")], &[])?;
+
+        // 確定後に rename していない一時ファイルが消えた
+        let mut journal = Journal::new(&data_dir, &[&a, &b], &[])?;
+        journal.prepare(&data_dir)?;
+        journal.write_temp(&data_dir, 0, b"A2")?;
+        journal.write_temp(&data_dir, 1, b"B2")?;
+        journal.commit(&data_dir)?;
+        fs::rename(data_dir.join(journal.files[0].temp.as_path()), a.as_path())?;
+        fs::remove_file(data_dir.join(journal.files[1].temp.as_path()))?;
+
+        assert!(recover(&data_dir).is_err());
+        assert_eq!(fs::read_to_string(b.as_path())?, "B1");
+        assert!(journal_path(&data_dir).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn commit_fails_if_temp_file_is_not_written_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        let a = data_dir.join("a.json");
+        let b = data_dir.join("b.json");
+        let mut journal = Journal::new(&data_dir, &[&a, &b], &[])?;
+        journal.prepare(&data_dir)?;
+        journal.write_temp(&data_dir, 0, b"A1")?;
+        assert!(journal.commit(&data_dir).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn lock_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        let lock = Lock::acquire(&data_dir)?;
+        let other = fs::File::open(data_dir.join(LOCK_FILE_NAME))?;
+        assert!(other.try_lock().is_err());
+        drop(lock);
+        assert!(other.try_lock().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn prepare_fails_if_journal_exists_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        let a = data_dir.join("a.json");
+        let journal = Journal::new(&data_dir, &[&a], &[])?;
+        journal.prepare(&data_dir)?;
+        assert!(journal.prepare(&data_dir).is_err());
+        Ok(())
+    }
+}
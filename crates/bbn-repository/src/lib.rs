@@ -1,5 +1,8 @@
 mod bbn_repository;
+mod journal;
 mod query;
+mod validation;
 
 pub use self::bbn_repository::*;
 pub use self::query::*;
+pub use self::validation::Problem;
@@ -4,17 +4,39 @@ use nom::IResult;
 use nom::Parser;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
+use nom::bytes::complete::take_till1;
 use nom::bytes::complete::take_while_m_n;
+use nom::bytes::complete::take_while1;
 use nom::character::complete::char;
+use nom::character::complete::multispace0;
+use nom::character::complete::multispace1;
 use nom::combinator::all_consuming;
+use nom::combinator::cut;
+use nom::combinator::eof;
 use nom::combinator::map;
+use nom::combinator::not;
+use nom::combinator::peek;
+use nom::combinator::value;
+use nom::multi::many0;
+use nom::sequence::delimited;
+use nom::sequence::preceded;
+use nom::sequence::terminated;
 use thiserror::Error;
 
+use bbn_data::EntryId;
+use bbn_data::EntryMeta;
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum Query<'a> {
     All,
     Date(Date<'a>),
     DateRange(DateRange<'a>),
+    Tag(&'a str),
+    Title(&'a str),
+    Text(&'a str),
+    And(Vec<Query<'a>>),
+    Or(Vec<Query<'a>>),
+    Not(Box<Query<'a>>),
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -116,40 +138,204 @@ impl<'a> std::fmt::Display for DateRange<'a> {
 
 #[derive(Debug, Error)]
 pub enum ParseQueryError {
-    #[error("parse error")]
-    Parse,
+    #[error("parse error at column {column}")]
+    Parse { column: usize },
 }
 
 impl<'a> Query<'a> {
+    /// `false` if no entry in the year directory can match
     pub fn match_year(&self, year: &OsStr) -> bool {
         match self {
             Query::All => true,
             Query::Date(date) => date.match_year(year),
             Query::DateRange(date_range) => date_range.match_year(year),
+            Query::Tag(_) | Query::Title(_) | Query::Text(_) => true,
+            Query::And(queries) => queries.iter().all(|q| q.match_year(year)),
+            Query::Or(queries) => queries.iter().any(|q| q.match_year(year)),
+            Query::Not(_) => true,
         }
     }
 
+    /// `false` if no entry in the month directory can match
     pub fn match_month(&self, month: &OsStr) -> bool {
         match self {
             Query::All => true,
             Query::Date(date) => date.match_month(month),
             Query::DateRange(date_range) => date_range.match_month(month),
+            Query::Tag(_) | Query::Title(_) | Query::Text(_) => true,
+            Query::And(queries) => queries.iter().all(|q| q.match_month(month)),
+            Query::Or(queries) => queries.iter().any(|q| q.match_month(month)),
+            Query::Not(_) => true,
         }
     }
 
+    /// `false` if no entry on the day can match
     pub fn match_day(&self, day: &OsStr) -> bool {
         match self {
             Query::All => true,
             Query::Date(date) => date.match_day(day),
             Query::DateRange(date_range) => date_range.match_day(day),
+            Query::Tag(_) | Query::Title(_) | Query::Text(_) => true,
+            Query::And(queries) => queries.iter().all(|q| q.match_day(day)),
+            Query::Or(queries) => queries.iter().any(|q| q.match_day(day)),
+            Query::Not(_) => true,
         }
     }
 
+    /// Matches the date terms.
+    ///
+    /// This is exact if `requires_meta()` is `false`. Otherwise, `false` means that no entry on
+    /// the date can match.
     pub fn match_date(&self, date: &str) -> bool {
+        self.match_date_terms(date).unwrap_or(true)
+    }
+
+    /// `None` if the result depends on terms other than `date:`
+    fn match_date_terms(&self, date: &str) -> Option<bool> {
+        match self {
+            Query::All => Some(true),
+            Query::Date(d) => Some(d.match_date(date)),
+            Query::DateRange(dr) => Some(dr.match_date(date)),
+            Query::Tag(_) | Query::Title(_) | Query::Text(_) => None,
+            Query::And(queries) => {
+                let mut result = Some(true);
+                for query in queries {
+                    match query.match_date_terms(date) {
+                        Some(true) => {}
+                        Some(false) => return Some(false),
+                        None => result = None,
+                    }
+                }
+                result
+            }
+            Query::Or(queries) => {
+                let mut result = Some(false);
+                for query in queries {
+                    match query.match_date_terms(date) {
+                        Some(true) => return Some(true),
+                        Some(false) => {}
+                        None => result = None,
+                    }
+                }
+                result
+            }
+            Query::Not(query) => query.match_date_terms(date).map(|b| !b),
+        }
+    }
+
+    /// `false` if the entry cannot match because of its tags
+    pub fn match_tags(&self, tags: &[String]) -> bool {
+        match self {
+            Query::All | Query::Date(_) | Query::DateRange(_) => true,
+            Query::Tag(tag) => tags.iter().any(|t| t == tag),
+            Query::Title(_) | Query::Text(_) => true,
+            Query::And(queries) => queries.iter().all(|q| q.match_tags(tags)),
+            Query::Or(queries) => queries.iter().any(|q| q.match_tags(tags)),
+            Query::Not(_) => true,
+        }
+    }
+
+    /// Matches all terms against the entry.
+    ///
+    /// `content` may be `None` only if `requires_content()` is `false`.
+    pub fn match_entry(&self, entry_id: &EntryId, meta: &EntryMeta, content: Option<&str>) -> bool {
         match self {
             Query::All => true,
-            Query::Date(d) => d.match_date(date),
-            Query::DateRange(dr) => dr.match_date(date),
+            Query::Date(_) | Query::DateRange(_) => {
+                self.match_date(entry_id.date().to_string().as_str())
+            }
+            Query::Tag(tag) => meta.tags.iter().any(|t| t == tag),
+            Query::Title(title) => contains_ignore_ascii_case(meta.title.as_str(), title),
+            Query::Text(text) => {
+                contains_ignore_ascii_case(meta.title.as_str(), text)
+                    || content.is_some_and(|c| contains_ignore_ascii_case(c, text))
+            }
+            Query::And(queries) => queries
+                .iter()
+                .all(|q| q.match_entry(entry_id, meta, content)),
+            Query::Or(queries) => queries
+                .iter()
+                .any(|q| q.match_entry(entry_id, meta, content)),
+            Query::Not(query) => !query.match_entry(entry_id, meta, content),
+        }
+    }
+
+    /// `true` if the query needs the entry meta (e.g. tags) to be matched
+    pub fn requires_meta(&self) -> bool {
+        match self {
+            Query::All | Query::Date(_) | Query::DateRange(_) => false,
+            Query::Tag(_) | Query::Title(_) | Query::Text(_) => true,
+            Query::And(queries) | Query::Or(queries) => queries.iter().any(|q| q.requires_meta()),
+            Query::Not(query) => query.requires_meta(),
+        }
+    }
+
+    /// `true` if the query needs the entry content to be matched
+    pub fn requires_content(&self) -> bool {
+        match self {
+            Query::All | Query::Date(_) | Query::DateRange(_) => false,
+            Query::Tag(_) | Query::Title(_) => false,
+            Query::Text(_) => true,
+            Query::And(queries) | Query::Or(queries) => {
+                queries.iter().any(|q| q.requires_content())
+            }
+            Query::Not(query) => query.requires_content(),
+        }
+    }
+
+    /// Returns the free-text terms (e.g. for highlighting)
+    ///
+    /// Negated terms are not included.
+    pub fn text_terms(&self) -> Vec<&'a str> {
+        match self {
+            Query::Text(text) => vec![*text],
+            Query::And(queries) | Query::Or(queries) => {
+                queries.iter().flat_map(|q| q.text_terms()).collect()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// Finds `needle` in `haystack` ignoring ASCII case and returns the byte offset
+pub fn find_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
+    // ASCII の大文字小文字変換はバイト長を変えないので offset はそのまま使える
+    haystack
+        .to_ascii_lowercase()
+        .find(needle.to_ascii_lowercase().as_str())
+}
+
+fn contains_ignore_ascii_case(haystack: &str, needle: &str) -> bool {
+    find_ignore_ascii_case(haystack, needle).is_some()
+}
+
+struct Value<'a>(&'a str);
+
+impl<'a> std::fmt::Display for Value<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // そのまま書くと別の構文として parse されてしまう値は quote する
+        let s = self.0;
+        if s.contains(|c: char| c.is_whitespace() || c == '(' || c == ')')
+            || s == OR
+            || s.starts_with('-')
+            || s.starts_with('"')
+            || KEYS.iter().any(|key| s.starts_with(key))
+        {
+            write!(f, "\"{s}\"")
+        } else {
+            write!(f, "{s}")
+        }
+    }
+}
+
+/// Writes `query` in parentheses if it is a compound query
+struct Grouped<'a, 'b>(&'b Query<'a>);
+
+impl<'a, 'b> std::fmt::Display for Grouped<'a, 'b> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Query::And(_) | Query::Or(_) => write!(f, "({})", self.0),
+            query => write!(f, "{query}"),
         }
     }
 }
@@ -160,6 +346,29 @@ impl<'a> std::fmt::Display for Query<'a> {
             Query::All => write!(f, ""),
             Query::Date(date) => write!(f, "date:{date}"),
             Query::DateRange(date_range) => write!(f, "date:{date_range}"),
+            Query::Tag(tag) => write!(f, "tag:{tag}"),
+            Query::Title(title) => write!(f, "title:{}", Value(title)),
+            Query::Text(text) => write!(f, "{}", Value(text)),
+            Query::And(queries) => {
+                for (i, query) in queries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    // 入れ子のグループは常に括弧で囲む
+                    write!(f, "{}", Grouped(query))?;
+                }
+                Ok(())
+            }
+            Query::Or(queries) => {
+                for (i, query) in queries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " {OR} ")?;
+                    }
+                    write!(f, "{}", Grouped(query))?;
+                }
+                Ok(())
+            }
+            Query::Not(query) => write!(f, "-{}", Grouped(query)),
         }
     }
 }
@@ -235,28 +444,153 @@ fn dd(s: &str) -> IResult<&str, Date> {
     Ok((s, Date(None, None, Some(d))))
 }
 
-fn parse(s: &str) -> IResult<&str, Query> {
-    if s.is_empty() {
-        return Ok((s, Query::All));
-    }
+const OR: &str = "OR";
+
+const KEYS: [&str; 3] = ["date:", "tag:", "title:"];
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && c != '(' && c != ')'
+}
+
+fn end_of_term(s: &str) -> IResult<&str, ()> {
+    peek(alt((
+        value((), multispace1),
+        value((), char(')')),
+        value((), eof),
+    )))
+    .parse(s)
+}
+
+fn word(s: &str) -> IResult<&str, &str> {
+    take_while1(is_word_char).parse(s)
+}
+
+fn quoted(s: &str) -> IResult<&str, &str> {
+    delimited(char('"'), take_till1(|c: char| c == '"'), cut(char('"'))).parse(s)
+}
+
+fn term_value(s: &str) -> IResult<&str, &str> {
+    // '"' で始まる場合は必ず quoted として扱い、閉じられていなければエラーにする
+    alt((quoted, preceded(not(char('"')), word))).parse(s)
+}
+
+fn date_term(s: &str) -> IResult<&str, Query<'_>> {
     let (s, _) = tag("date:")(s)?;
-    let (s, date) = all_consuming(alt((
+    cut(alt((
         map(date_range, Query::DateRange),
         map(alt((yyyymmdd, yyyymm, yyyy, mmdd, mm, dd)), |d| {
             Query::Date(d)
         }),
     )))
-    .parse(s)?;
-    Ok((s, date))
+    .parse(s)
+}
+
+fn tag_term(s: &str) -> IResult<&str, Query<'_>> {
+    let (s, _) = tag("tag:")(s)?;
+    cut(map(word, Query::Tag)).parse(s)
+}
+
+fn title_term(s: &str) -> IResult<&str, Query<'_>> {
+    let (s, _) = tag("title:")(s)?;
+    cut(map(term_value, Query::Title)).parse(s)
+}
+
+fn text_term(s: &str) -> IResult<&str, Query<'_>> {
+    map(
+        preceded(
+            not(alt((
+                tag(KEYS[0]),
+                tag(KEYS[1]),
+                tag(KEYS[2]),
+                tag("-"),
+                terminated(tag(OR), end_of_term),
+            ))),
+            term_value,
+        ),
+        Query::Text,
+    )
+    .parse(s)
+}
+
+fn term(s: &str) -> IResult<&str, Query<'_>> {
+    terminated(
+        alt((date_term, tag_term, title_term, text_term)),
+        cut(end_of_term),
+    )
+    .parse(s)
+}
+
+fn group(s: &str) -> IResult<&str, Query<'_>> {
+    delimited(
+        terminated(char('('), multispace0),
+        cut(or_expr),
+        cut(preceded(multispace0, char(')'))),
+    )
+    .parse(s)
+}
+
+fn unary(s: &str) -> IResult<&str, Query<'_>> {
+    alt((
+        map(preceded(char('-'), cut(unary)), |q| Query::Not(Box::new(q))),
+        group,
+        term,
+    ))
+    .parse(s)
+}
+
+fn and_expr(s: &str) -> IResult<&str, Query<'_>> {
+    map(
+        (unary, many0(preceded(multispace1, unary))),
+        |(first, rest)| flatten(first, rest, Query::And),
+    )
+    .parse(s)
+}
+
+fn or_expr(s: &str) -> IResult<&str, Query<'_>> {
+    map(
+        (
+            and_expr,
+            many0(preceded((multispace1, tag(OR), multispace1), cut(and_expr))),
+        ),
+        |(first, rest)| flatten(first, rest, Query::Or),
+    )
+    .parse(s)
+}
+
+fn flatten<'a>(
+    first: Query<'a>,
+    mut rest: Vec<Query<'a>>,
+    f: fn(Vec<Query<'a>>) -> Query<'a>,
+) -> Query<'a> {
+    if rest.is_empty() {
+        first
+    } else {
+        rest.insert(0, first);
+        f(rest)
+    }
+}
+
+fn parse(s: &str) -> IResult<&str, Query> {
+    if s.trim().is_empty() {
+        return Ok(("", Query::All));
+    }
+    all_consuming(delimited(multispace0, or_expr, multispace0)).parse(s)
 }
 
 impl<'a> std::convert::TryFrom<&'a str> for Query<'a> {
     type Error = ParseQueryError;
 
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        parse(value)
-            .map(|(_, q)| Ok(q))
-            .map_err(|_| ParseQueryError::Parse)?
+        parse(value).map(|(_, q)| q).map_err(|e| {
+            let rest = match e {
+                nom::Err::Incomplete(_) => "",
+                nom::Err::Error(e) | nom::Err::Failure(e) => e.input,
+            };
+            let offset = value.len() - rest.len();
+            ParseQueryError::Parse {
+                column: value[..offset].chars().count() + 1,
+            }
+        })
     }
 }
 
@@ -276,6 +610,247 @@ mod tests {
         f("date:--02");
         f("date:---03");
         f("date:2021-02-03/2022-03-04");
+        f("tag:rust");
+        f("date:2021 tag:rust");
+        f("tag:rust tag:diary date:--02-03");
+        f("title:rust");
+        f("title:\"hello world\"");
+        f("rust");
+        f("\"hello world\" tag:rust");
+        f("tag:rust OR tag:typescript");
+        f("-tag:diary");
+        f("date:2021 (tag:rust OR tag:typescript) -tag:diary");
+        f("(tag:a tag:b) OR tag:c");
+        f("-(tag:a OR tag:b) -(tag:c tag:d)");
+        f("\"OR\" \"-a\" \"tag:a\" \"(a)\"");
+        f("tag:a OR (tag:b OR tag:c)");
+        f("(tag:a OR tag:b) OR tag:c");
+        f("tag:a ((tag:b tag:c) OR tag:d)");
+        assert_eq!(
+            Query::try_from("tag:a tag:b OR tag:c").unwrap().to_string(),
+            "(tag:a tag:b) OR tag:c"
+        );
+    }
+
+    #[test]
+    fn display_round_trip_test() {
+        let f = |q: Query| {
+            let s = q.to_string();
+            assert_eq!(Query::try_from(s.as_str()).unwrap(), q, "{s}");
+        };
+        f(Query::Or(vec![
+            Query::Tag("a"),
+            Query::Or(vec![Query::Tag("b"), Query::Tag("c")]),
+        ]));
+        f(Query::Or(vec![
+            Query::Or(vec![Query::Tag("a"), Query::Tag("b")]),
+            Query::Tag("c"),
+        ]));
+        f(Query::And(vec![
+            Query::Tag("a"),
+            Query::And(vec![Query::Tag("b"), Query::Tag("c")]),
+        ]));
+        f(Query::And(vec![
+            Query::Or(vec![
+                Query::Tag("a"),
+                Query::And(vec![Query::Tag("b"), Query::Tag("c")]),
+            ]),
+            Query::Not(Box::new(Query::Or(vec![
+                Query::Tag("d"),
+                Query::Or(vec![Query::Tag("e"), Query::Tag("f")]),
+            ]))),
+        ]));
+    }
+
+    #[test]
+    fn parse_boolean_test() {
+        assert_eq!(
+            Query::try_from("tag:a OR tag:b tag:c").unwrap(),
+            Query::Or(vec![
+                Query::Tag("a"),
+                Query::And(vec![Query::Tag("b"), Query::Tag("c")])
+            ])
+        );
+        assert_eq!(
+            Query::try_from("(tag:a OR tag:b) tag:c").unwrap(),
+            Query::And(vec![
+                Query::Or(vec![Query::Tag("a"), Query::Tag("b")]),
+                Query::Tag("c")
+            ])
+        );
+        assert_eq!(
+            Query::try_from("-tag:a --tag:b").unwrap(),
+            Query::And(vec![
+                Query::Not(Box::new(Query::Tag("a"))),
+                Query::Not(Box::new(Query::Not(Box::new(Query::Tag("b")))))
+            ])
+        );
+        assert_eq!(Query::try_from("( tag:a )").unwrap(), Query::Tag("a"));
+        assert_eq!(
+            Query::try_from("date:--02-03 OR date:---04").unwrap(),
+            Query::Or(vec![
+                Query::Date(Date(None, Some("02"), Some("03"))),
+                Query::Date(Date(None, None, Some("04")))
+            ])
+        );
+        // OR は大文字のみ演算子として扱う
+        assert_eq!(
+            Query::try_from("a or b").unwrap(),
+            Query::And(vec![Query::Text("a"), Query::Text("or"), Query::Text("b")])
+        );
+        assert_eq!(Query::try_from("ORACLE").unwrap(), Query::Text("ORACLE"));
+    }
+
+    #[test]
+    fn parse_error_column_test() {
+        let f = |s: &str| match Query::try_from(s) {
+            Ok(q) => panic!("{s} parsed as {q:?}"),
+            Err(ParseQueryError::Parse { column }) => column,
+        };
+        assert_eq!(f("date:2021x"), 10);
+        assert_eq!(f("date:20"), 6);
+        assert_eq!(f("tag:rust date:x"), 15);
+        assert_eq!(f("(tag:rust"), 10);
+        assert_eq!(f("tag:rust)"), 9);
+        assert_eq!(f("tag:a OR"), 7);
+        assert_eq!(f("tag:a OR OR"), 10);
+        assert_eq!(f("-"), 2);
+        assert_eq!(f("()"), 2);
+        assert_eq!(f("日本 \"語"), 6);
+        assert_eq!(
+            Query::try_from("date:2021x").unwrap_err().to_string(),
+            "parse error at column 10"
+        );
+    }
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(Query::try_from("  ").unwrap(), Query::All);
+        assert_eq!(Query::try_from("tag:rust").unwrap(), Query::Tag("rust"));
+        assert_eq!(
+            Query::try_from(" date:2021   tag:rust ").unwrap(),
+            Query::And(vec![
+                Query::Date(Date(Some("2021"), None, None)),
+                Query::Tag("rust")
+            ])
+        );
+        assert!(Query::try_from("tag:").is_err());
+        assert!(Query::try_from("date:2021x").is_err());
+        assert!(Query::try_from("date:2021tag:rust").is_err());
+        assert_eq!(Query::try_from("rust").unwrap(), Query::Text("rust"));
+        assert_eq!(Query::try_from("foo:bar").unwrap(), Query::Text("foo:bar"));
+        assert_eq!(
+            Query::try_from("title:\"a b\" \"c d\"").unwrap(),
+            Query::And(vec![Query::Title("a b"), Query::Text("c d")])
+        );
+        assert!(Query::try_from("title:").is_err());
+        assert!(Query::try_from("\"a b").is_err());
+        assert!(Query::try_from("\"a\"b").is_err());
+    }
+
+    #[test]
+    fn match_entry() -> anyhow::Result<()> {
+        use std::str::FromStr;
+
+        use bbn_data::DateTime;
+
+        let entry_id = EntryId::from_str("2021-02-03")?;
+        let meta = EntryMeta::new(
+            5,
+            DateTime::from_str("2021-02-03T00:00:00+09:00")?,
+            vec!["rust".to_string()],
+            "Hello Rust".to_string(),
+        );
+        let content = "I wrote about Nom.";
+        let f = |s: &str, content: Option<&str>| -> bool {
+            Query::try_from(s)
+                .unwrap()
+                .match_entry(&entry_id, &meta, content)
+        };
+        assert!(f("", None));
+        assert!(f("date:2021", None));
+        assert!(!f("date:2022", None));
+        assert!(f("tag:rust", None));
+        assert!(f("title:rust", None));
+        assert!(f("title:\"hello rust\"", None));
+        assert!(!f("title:nom", Some(content)));
+        assert!(f("rust", Some(content)));
+        assert!(f("nom", Some(content)));
+        assert!(f("\"about nom\"", Some(content)));
+        assert!(!f("nom", None));
+        assert!(!f("serde", Some(content)));
+        assert!(f("date:2021 tag:rust title:hello nom", Some(content)));
+        assert!(!f("date:2021 tag:rust title:hello serde", Some(content)));
+        assert!(f("serde OR nom", Some(content)));
+        assert!(!f("serde OR tag:diary", Some(content)));
+        assert!(f("-tag:diary", None));
+        assert!(!f("-tag:rust", None));
+        assert!(f("date:2021 (tag:rust OR tag:typescript) -tag:diary", None));
+        assert!(!f("date:2021 (tag:go OR tag:typescript) -tag:diary", None));
+        assert!(!f("-(date:2021 tag:rust)", None));
+        Ok(())
+    }
+
+    #[test]
+    fn requires_content() {
+        let f = |s: &str| Query::try_from(s).unwrap().requires_content();
+        assert!(!f(""));
+        assert!(!f("date:2021 tag:rust title:rust"));
+        assert!(f("rust"));
+        assert!(f("date:2021 rust"));
+    }
+
+    #[test]
+    fn text_terms() {
+        assert_eq!(
+            Query::try_from("").unwrap().text_terms(),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            Query::try_from("title:a b tag:c \"d e\"")
+                .unwrap()
+                .text_terms(),
+            vec!["b", "d e"]
+        );
+        assert_eq!(
+            Query::try_from("a OR (b -c)").unwrap().text_terms(),
+            vec!["a", "b"]
+        );
+    }
+
+    #[test]
+    fn find_ignore_ascii_case_test() {
+        assert_eq!(find_ignore_ascii_case("Hello Rust", "rust"), Some(6));
+        assert_eq!(find_ignore_ascii_case("日本語 Rust", "RUST"), Some(10));
+        assert_eq!(find_ignore_ascii_case("Hello", "rust"), None);
+    }
+
+    #[test]
+    fn match_tags() {
+        let f = |s: &str, t: &[&str]| -> bool {
+            let q = Query::try_from(s).unwrap();
+            q.match_tags(&t.iter().map(|t| t.to_string()).collect::<Vec<String>>())
+        };
+        assert!(f("", &[]));
+        assert!(f("date:2021", &[]));
+        assert!(f("tag:rust", &["rust"]));
+        assert!(f("tag:rust", &["diary", "rust"]));
+        assert!(!f("tag:rust", &[]));
+        assert!(!f("tag:rust", &["rustacean"]));
+        assert!(f("tag:rust tag:diary", &["diary", "rust"]));
+        assert!(!f("tag:rust tag:diary", &["rust"]));
+        assert!(f("date:2021 tag:rust", &["rust"]));
+    }
+
+    #[test]
+    fn requires_meta() {
+        let f = |s: &str| Query::try_from(s).unwrap().requires_meta();
+        assert!(!f(""));
+        assert!(!f("date:2021"));
+        assert!(f("tag:rust"));
+        assert!(f("date:2021 tag:rust"));
+        assert!(f("title:rust"));
+        assert!(f("rust"));
     }
 
     #[test]
@@ -295,6 +870,9 @@ mod tests {
         assert!(f("date:2021-02-03/2022-03-04", "2021"));
         assert!(f("date:2021-02-03/2022-03-04", "2022"));
         assert!(!f("date:2021-02-03/2022-03-04", "2023"));
+        assert!(f("tag:rust", "2021"));
+        assert!(f("date:2021 tag:rust", "2021"));
+        assert!(!f("date:2021 tag:rust", "2020"));
     }
 
     #[test]
@@ -362,5 +940,16 @@ mod tests {
         assert!(f("date:2021-02-03/2022-03-04", "2021-02-03"));
         assert!(f("date:2021-02-03/2022-03-04", "2022-03-04"));
         assert!(!f("date:2021-02-03/2022-03-04", "2022-03-05"));
+        assert!(f("tag:rust", "2021-02-03"));
+        assert!(f("date:2021-02 tag:rust", "2021-02-03"));
+        assert!(!f("date:2021-02 tag:rust", "2021-03-01"));
+        assert!(f("-date:2021-02", "2021-03-01"));
+        assert!(!f("-date:2021-02", "2021-02-03"));
+        assert!(f("date:2021-02 OR date:2021-04", "2021-04-01"));
+        assert!(!f("date:2021-02 OR date:2021-04", "2021-03-01"));
+        // tag: の結果次第なので除外できない
+        assert!(f("date:2021-02 OR tag:rust", "2021-03-01"));
+        assert!(f("-(date:2021-02 tag:rust)", "2021-02-03"));
+        assert!(!f("-(date:2021-02 OR tag:rust)", "2021-02-03"));
     }
 }
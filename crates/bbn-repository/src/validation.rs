@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::Context;
+use bbn_data::EntryId;
+use bbn_data::EntryMeta;
+use date_range::date::Date;
+use date_range::date::Month;
+use date_range::date::Year;
+
+use crate::bbn_repository::MetaJson;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Problem {
+    /// Two or more entries have the same date
+    DuplicateDate { date: Date, paths: Vec<PathBuf> },
+    /// The file name is not `YYYY-MM-DD(-ID_TITLE).(json|md)`
+    InvalidFileName { path: PathBuf },
+    /// The meta file cannot be parsed (e.g. invalid pubdate)
+    InvalidMeta { path: PathBuf, message: String },
+    /// The file is not in the `YYYY/MM` directory of its date
+    MisplacedEntry { path: PathBuf, expected: PathBuf },
+    /// The `.json` file exists but the `.md` file does not
+    MissingContent { path: PathBuf, expected: PathBuf },
+    /// The `.md` file exists but the `.json` file does not
+    MissingMeta { path: PathBuf, expected: PathBuf },
+    /// The path is not in the `YYYY/MM` shape
+    UnexpectedPath { path: PathBuf },
+}
+
+impl Problem {
+    /// `true` if `BbnRepository::fix` can fix the problem without losing data
+    pub fn is_fixable(&self) -> bool {
+        match self {
+            Problem::DuplicateDate { .. }
+            | Problem::InvalidFileName { .. }
+            | Problem::InvalidMeta { .. }
+            | Problem::MissingMeta { .. }
+            | Problem::UnexpectedPath { .. } => false,
+            Problem::MisplacedEntry { expected, .. } => !expected.exists(),
+            Problem::MissingContent { .. } => true,
+        }
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::DuplicateDate { date, paths } => write!(
+                f,
+                "duplicate entries on {}: {}",
+                date,
+                paths
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Problem::InvalidFileName { path } => {
+                write!(f, "{}: invalid file name", path.display())
+            }
+            Problem::InvalidMeta { path, message } => {
+                write!(f, "{}: invalid meta: {}", path.display(), message)
+            }
+            Problem::MisplacedEntry { path, expected } => write!(
+                f,
+                "{}: misplaced (expected: {})",
+                path.display(),
+                expected.display()
+            ),
+            Problem::MissingContent { path, expected } => write!(
+                f,
+                "{}: content file not found (expected: {})",
+                path.display(),
+                expected.display()
+            ),
+            Problem::MissingMeta { path, expected } => write!(
+                f,
+                "{}: meta file not found (expected: {})",
+                path.display(),
+                expected.display()
+            ),
+            Problem::UnexpectedPath { path } => write!(f, "{}: unexpected path", path.display()),
+        }
+    }
+}
+
+// data/YYYY/MM/YYYY-MM-DD(-ID_TITLE).(json|md)
+pub(crate) fn validate(data_dir: &Path) -> anyhow::Result<Vec<Problem>> {
+    let mut problems = vec![];
+    // (entry_id, extension) -> path
+    let mut files = BTreeMap::<(EntryId, String), PathBuf>::new();
+    for year_dir in sorted_children(data_dir)? {
+        let year = match dir_name(year_dir.as_path(), parse_year) {
+            Some(year) => year,
+            None => {
+                problems.push(Problem::UnexpectedPath { path: year_dir });
+                continue;
+            }
+        };
+        for month_dir in sorted_children(year_dir.as_path())? {
+            let month = match dir_name(month_dir.as_path(), parse_month) {
+                Some(month) => month,
+                None => {
+                    problems.push(Problem::UnexpectedPath { path: month_dir });
+                    continue;
+                }
+            };
+            for path in sorted_children(month_dir.as_path())? {
+                if !path.is_file() {
+                    problems.push(Problem::UnexpectedPath { path });
+                    continue;
+                }
+                let (entry_id, extension) = match parse_file_name(path.as_path()) {
+                    Some(parsed) => parsed,
+                    None => {
+                        problems.push(Problem::InvalidFileName { path });
+                        continue;
+                    }
+                };
+                let date = entry_id.date();
+                if date.year() != year || date.month() != month {
+                    let expected = data_dir
+                        .join(date.year().to_string())
+                        .join(date.month().to_string())
+                        .join(path.file_name().context("no file name")?);
+                    problems.push(Problem::MisplacedEntry {
+                        path: path.clone(),
+                        expected,
+                    });
+                }
+                if extension == "json"
+                    && let Err(e) = read_meta(path.as_path())
+                {
+                    problems.push(Problem::InvalidMeta {
+                        path: path.clone(),
+                        message: e.to_string(),
+                    });
+                }
+                files.insert((entry_id, extension), path);
+            }
+        }
+    }
+
+    let mut paths_by_date = BTreeMap::<Date, BTreeMap<EntryId, PathBuf>>::new();
+    for ((entry_id, extension), path) in files.iter() {
+        let (other, missing) = if extension == "json" {
+            ("md", MissingKind::Content)
+        } else {
+            ("json", MissingKind::Meta)
+        };
+        if !files.contains_key(&(entry_id.clone(), other.to_string())) {
+            let expected = path.with_extension(other);
+            problems.push(match missing {
+                MissingKind::Content => Problem::MissingContent {
+                    path: path.clone(),
+                    expected,
+                },
+                MissingKind::Meta => Problem::MissingMeta {
+                    path: path.clone(),
+                    expected,
+                },
+            });
+        }
+        paths_by_date
+            .entry(*entry_id.date())
+            .or_default()
+            .entry(entry_id.clone())
+            .or_insert_with(|| path.with_extension("json"));
+    }
+    for (date, entries) in paths_by_date {
+        if entries.len() > 1 {
+            problems.push(Problem::DuplicateDate {
+                date,
+                paths: entries.into_values().collect(),
+            });
+        }
+    }
+    Ok(problems)
+}
+
+/// Returns `true` if the problem is fixed
+pub(crate) fn fix(problem: &Problem) -> anyhow::Result<bool> {
+    if !problem.is_fixable() {
+        return Ok(false);
+    }
+    match problem {
+        Problem::MisplacedEntry { path, expected } => {
+            fs::create_dir_all(expected.parent().context("no parent dir")?)?;
+            fs::rename(path, expected)?;
+            Ok(true)
+        }
+        Problem::MissingContent { expected, .. } => {
+            fs::write(expected, "")?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+enum MissingKind {
+    Content,
+    Meta,
+}
+
+fn sorted_children(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for dir_entry in dir.read_dir()? {
+        let path = dir_entry?.path();
+        // 隠しファイル (e.g. .git, 書き込み中の一時ファイル) は対象外
+        if path
+            .file_name()
+            .and_then(OsStr::to_str)
+            .is_some_and(|s| s.starts_with('.'))
+        {
+            continue;
+        }
+        paths.push(path);
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn dir_name<T>(path: &Path, parse: fn(&str) -> Option<T>) -> Option<T> {
+    if !path.is_dir() {
+        return None;
+    }
+    path.file_name().and_then(|s| s.to_str()).and_then(parse)
+}
+
+fn parse_year(s: &str) -> Option<Year> {
+    Year::from_str(s).ok().filter(|y| y.to_string() == s)
+}
+
+fn parse_month(s: &str) -> Option<Month> {
+    Month::from_str(s).ok().filter(|m| m.to_string() == s)
+}
+
+fn parse_file_name(path: &Path) -> Option<(EntryId, String)> {
+    let extension = path.extension().and_then(OsStr::to_str)?;
+    if extension != "json" && extension != "md" {
+        return None;
+    }
+    let stem = path.file_stem().and_then(OsStr::to_str)?;
+    let entry_id = EntryId::from_str(stem)
+        .ok()
+        .filter(|id| id.to_string() == stem)
+        .filter(|id| id.id_title() != Some(""))?;
+    Some((entry_id, extension.to_string()))
+}
+
+fn read_meta(path: &Path) -> anyhow::Result<EntryMeta> {
+    let json_content = fs::read_to_string(path)?;
+    let meta_json = serde_json::from_str::<'_, MetaJson>(json_content.as_str())?;
+    EntryMeta::try_from(meta_json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_file_name_test() -> anyhow::Result<()> {
+        let f = |s: &str| parse_file_name(Path::new(s));
+        assert_eq!(
+            f("2021-02-03.json"),
+            Some((EntryId::from_str("2021-02-03")?, "json".to_string()))
+        );
+        assert_eq!(
+            f("2021-02-03-id.md"),
+            Some((EntryId::from_str("2021-02-03-id")?, "md".to_string()))
+        );
+        assert_eq!(f("2021-02-03.txt"), None);
+        assert_eq!(f("2021-02.json"), None);
+        assert_eq!(f("2021-02-30.json"), None);
+        assert_eq!(f("2021-02-03_id.json"), None);
+        assert_eq!(f("2021-02-03-.json"), None);
+        assert_eq!(f(".json"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_year_month_test() {
+        assert!(parse_year("2021").is_some());
+        assert!(parse_year("21").is_none());
+        assert!(parse_year("foo").is_none());
+        assert!(parse_month("02").is_some());
+        assert!(parse_month("2").is_none());
+        assert!(parse_month("13").is_none());
+    }
+}
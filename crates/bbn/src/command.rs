@@ -2,9 +2,16 @@ mod build;
 mod completion;
 mod config;
 mod date_range;
+mod edit;
+mod fsck;
 mod hatena_blog;
 mod link_completion;
 mod list;
+mod mv;
+mod new;
+mod rm;
+mod search;
+mod serve;
 mod sitemap_xml;
 mod view;
 
@@ -24,12 +31,26 @@ enum Subcommand {
     Config(config::Command),
     /// Prints the date range
     DateRange(date_range::Command),
+    /// Edits the blog post in $EDITOR
+    Edit(edit::Command),
+    /// Checks the consistency of the data directory
+    Fsck(fsck::Command),
     /// Manages Hatena Blog posts
     HatenaBlog(hatena_blog::Command),
     /// Completes links
     LinkCompletion(link_completion::Command),
     /// Lists the blog posts
     List(list::Command),
+    /// Moves the blog post to another date and/or id title
+    Mv(mv::Command),
+    /// Creates a new blog post
+    New(new::Command),
+    /// Removes the blog post
+    Rm(rm::Command),
+    /// Searches the blog posts by title and content
+    Search(search::Command),
+    /// Serves the built files with live reload
+    Serve(serve::Command),
     /// Builds sitemap.xml
     SitemapXml(sitemap_xml::Command),
     /// Views the blog post
@@ -43,9 +64,16 @@ impl Command {
             Subcommand::Completion(command) => command.handle::<Command>(),
             Subcommand::Config(command) => command.handle().await,
             Subcommand::DateRange(command) => command.handle(),
+            Subcommand::Edit(command) => command.handle(),
+            Subcommand::Fsck(command) => command.handle(),
             Subcommand::HatenaBlog(command) => command.handle().await,
             Subcommand::LinkCompletion(command) => command.handle(),
             Subcommand::List(command) => command.handle(),
+            Subcommand::Mv(command) => command.handle(),
+            Subcommand::New(command) => command.handle(),
+            Subcommand::Rm(command) => command.handle(),
+            Subcommand::Search(command) => command.handle(),
+            Subcommand::Serve(command) => command.handle(),
             Subcommand::SitemapXml(command) => command.handle(),
             Subcommand::View(command) => command.handle(),
         }
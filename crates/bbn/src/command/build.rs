@@ -1,36 +1,62 @@
 mod html;
 mod json;
+mod manifest;
+mod parallel;
+mod prune;
+mod site;
+mod theme;
 
 use anyhow::Context;
+use bbn_repository::BbnRepository;
+use std::collections::BTreeSet;
+use std::num::NonZeroUsize;
+use std::path::Path;
 use std::path::PathBuf;
 
+pub use self::prune::prune;
+pub use self::site::Site;
+use self::theme::Theme;
 use crate::config_repository::ConfigRepository;
+use crate::site_config::SiteConfig;
 
+/// `bbn build` と `bbn serve` に共通するオプション
 #[derive(Debug, clap::Args)]
-pub struct Command {
+pub struct BuildArgs {
+    #[arg(
+        long = "atom-entries",
+        default_value_t = 20,
+        help = "Number of entries in atom.xml"
+    )]
+    atom_entries: usize,
     #[arg(long = "data-dir", help = "Data directory path")]
     data_dir: Option<PathBuf>,
-    #[arg(long = "html", help = "Builds HTML files")]
-    html: bool,
-    #[arg(long = "json", help = "Builds JSON files")]
-    json: bool,
+    #[arg(
+        long = "jobs",
+        help = "Number of parallel jobs (default: the number of CPUs)"
+    )]
+    jobs: Option<NonZeroUsize>,
     #[arg(long = "out-dir", help = "Output directory path")]
     out_dir: Option<PathBuf>,
-    #[arg(long = "verbose", help = "Prints written file paths to stdout")]
-    verbose: bool,
+    #[arg(
+        long = "page-size",
+        help = "Number of entries per list page (default: page-size in the configuration or 9)"
+    )]
+    page_size: Option<NonZeroUsize>,
+    #[arg(
+        long = "theme-dir",
+        help = "Theme directory path (templates/ and public/ override the built-in ones)"
+    )]
+    theme_dir: Option<PathBuf>,
 }
 
-impl Command {
-    pub fn handle(self) -> anyhow::Result<()> {
-        // --json または両方未指定のとき JSON を生成する
-        let run_json = self.json || !self.html;
-        // --html または両方未指定のとき HTML を生成する
-        let run_html = self.html || !self.json;
-
-        // CLI オプションが不足している場合のみ設定ファイルを読み込む
-        let need_config = self.out_dir.is_none() || (run_json && self.data_dir.is_none());
-        let config = if need_config {
-            let config_repository = ConfigRepository::new()?;
+impl BuildArgs {
+    /// 設定ファイルで CLI オプションを補う
+    pub fn into_builder(self) -> anyhow::Result<Builder> {
+        // CLI オプションが不足している場合は設定ファイルを必須とする
+        // 不足していない場合もサイト設定のために存在すれば読み込む
+        let need_config = self.out_dir.is_none() || self.data_dir.is_none();
+        let config_repository = ConfigRepository::new()?;
+        let config = if need_config || config_repository.path()?.exists() {
             Some(
                 config_repository
                     .load()
@@ -39,6 +65,7 @@ impl Command {
         } else {
             None
         };
+        let site_config = config.as_ref().map(SiteConfig::from).unwrap_or_default();
 
         let out_dir = match self.out_dir {
             Some(d) => d,
@@ -50,15 +77,159 @@ impl Command {
                 .to_path_buf(),
         };
 
+        let data_dir = match self.data_dir {
+            Some(d) => d,
+            None => config.as_ref().unwrap().data_dir().to_path_buf(),
+        };
+
+        let theme_dir = self.theme_dir.or_else(|| {
+            config
+                .as_ref()
+                .and_then(|c| c.theme_dir().map(|p| p.to_path_buf()))
+        });
+
+        Ok(Builder {
+            atom_entries: self.atom_entries,
+            data_dir,
+            jobs: self::parallel::jobs(self.jobs),
+            out_dir,
+            page_size: self.page_size.unwrap_or(site_config.page_size()),
+            site_config,
+            theme_dir,
+        })
+    }
+}
+
+/// CLI オプションと設定ファイルから決まるビルドの設定
+pub struct Builder {
+    atom_entries: usize,
+    data_dir: PathBuf,
+    jobs: NonZeroUsize,
+    out_dir: PathBuf,
+    page_size: NonZeroUsize,
+    site_config: SiteConfig,
+    theme_dir: Option<PathBuf>,
+}
+
+impl Builder {
+    /// JSON と HTML は同じリポジトリの内容から出力する
+    pub fn load_site(&self) -> anyhow::Result<Site> {
+        Site::load(&BbnRepository::new(self.data_dir.clone()), self.jobs)
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        self.data_dir.as_path()
+    }
+
+    pub fn out_dir(&self) -> &Path {
+        self.out_dir.as_path()
+    }
+
+    pub fn theme_dir(&self) -> Option<&Path> {
+        self.theme_dir.as_deref()
+    }
+
+    /// 出力されるすべてのファイルを返す
+    ///
+    /// JSON は meta と content が前回から変わったエントリーのものだけを書き込む
+    pub fn build(
+        &self,
+        run_json: bool,
+        run_html: bool,
+        force: bool,
+        verbose: bool,
+    ) -> anyhow::Result<BTreeSet<PathBuf>> {
+        let site = self.load_site()?;
+        self.build_site(&site, None, run_json, run_html, force, verbose)
+    }
+
+    /// `prev` がある場合、HTML は `prev` から変わったエントリーに関係するページだけを書き込む
+    ///
+    /// 出力されるすべてのファイル (書き込まなかったものも含む) を返す
+    pub fn build_site(
+        &self,
+        site: &Site,
+        prev: Option<&Site>,
+        run_json: bool,
+        run_html: bool,
+        force: bool,
+        verbose: bool,
+    ) -> anyhow::Result<BTreeSet<PathBuf>> {
+        let mut files = BTreeSet::new();
         if run_json {
-            let data_dir = match self.data_dir {
-                Some(d) => d,
-                None => config.as_ref().unwrap().data_dir().to_path_buf(),
-            };
-            self::json::run(data_dir, out_dir.clone(), self.verbose)?;
+            let output = self::json::run(site, self.out_dir.clone(), force, self.jobs, verbose)?;
+            files.extend(output.files);
         }
         if run_html {
-            self::html::run(out_dir, self.verbose)?;
+            // テーマは毎回読み込む (bbn serve でテンプレートの変更を反映するため)
+            let theme = Theme::new(self.theme_dir.as_deref())?;
+            let affected = prev.map(|prev| site.affected(prev));
+            files.extend(self::html::run(
+                site,
+                affected.as_ref(),
+                self.out_dir.clone(),
+                &self.site_config,
+                &theme,
+                self.atom_entries,
+                self.page_size,
+                self.jobs,
+                verbose,
+            )?);
+        }
+        Ok(files)
+    }
+}
+
+#[derive(Debug, clap::Args)]
+pub struct Command {
+    #[command(flatten)]
+    build_args: BuildArgs,
+    #[arg(
+        long = "dry-run",
+        requires = "prune",
+        help = "Prints the files to be removed by --prune without removing them"
+    )]
+    dry_run: bool,
+    #[arg(
+        long = "force",
+        help = "Rebuilds all files even if the entries are not changed"
+    )]
+    force: bool,
+    #[arg(long = "html", help = "Builds HTML files")]
+    html: bool,
+    #[arg(long = "json", help = "Builds JSON files")]
+    json: bool,
+    #[arg(
+        long = "prune",
+        help = "Removes the HTML, JSON and atom.xml files in the output directory that are not produced by the build"
+    )]
+    prune: bool,
+    #[arg(long = "verbose", help = "Prints written file paths to stdout")]
+    verbose: bool,
+}
+
+impl Command {
+    pub fn handle(self) -> anyhow::Result<()> {
+        // --json または両方未指定のとき JSON を生成する
+        let run_json = self.json || !self.html;
+        // --html または両方未指定のとき HTML を生成する
+        let run_html = self.html || !self.json;
+        // 片方だけのビルドでは、もう片方が出力するファイルがわからない
+        if self.prune && !(run_json && run_html) {
+            anyhow::bail!("--prune requires building both JSON and HTML files");
+        }
+
+        let builder = self.build_args.into_builder()?;
+        let files = builder.build(run_json, run_html, self.force, self.verbose)?;
+
+        if self.prune {
+            for path in prune(builder.out_dir(), &files, self.dry_run)? {
+                if self.dry_run {
+                    println!("would remove: {}", path.display());
+                } else {
+                    println!("removed: {}", path.display());
+                }
+            }
         }
 
         Ok(())
@@ -1,39 +1,26 @@
 use anyhow::Context;
 use askama::Template;
+use bbn_data::EntryKey;
+use bbn_data::Timestamp;
 use include_dir::{Dir, include_dir};
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::fs::File;
 use std::fs::{self};
-use std::io::BufReader;
 use std::io::Write;
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::path::PathBuf;
 
-static PUBLIC_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/public");
+use super::parallel;
+use super::site::Affected;
+use super::site::Related;
+use super::site::Site;
+use super::site::SiteEntry;
+use super::theme::Theme;
+use crate::site_config::SiteConfig;
 
-#[derive(serde::Deserialize)]
-struct PostEntry {
-    date: String,
-    #[allow(dead_code)]
-    minutes: u32,
-    #[allow(dead_code)]
-    pubdate: String,
-    #[allow(dead_code)]
-    tags: Vec<String>,
-    title: String,
-}
-
-#[derive(serde::Deserialize)]
-struct EntryDetail {
-    data: String,
-    date: String,
-    html: String,
-    #[allow(dead_code)]
-    minutes: u32,
-    pubdate: String,
-    #[allow(dead_code)]
-    tags: Vec<String>,
-    title: String,
-}
+static PUBLIC_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/public");
 
 struct DateParts {
     yyyy: String,
@@ -57,47 +44,138 @@ fn entry_path(date: &DateParts) -> String {
     format!("/{}/{}/{}/", date.yyyy, date.mm, date.dd)
 }
 
+/// "YYYY" -> "/YYYY/", "YYYY-MM" -> "/YYYY/MM/"
+fn archive_path(period: &str) -> String {
+    format!("/{}/", period.replace('-', "/"))
+}
+
+/// タグのページを出力するディレクトリのパス "/tags/{name}/"
+///
+/// パスに使えない文字や URL で特別な意味を持つ文字 ("/", "?", "#", "%", 空白など) は "%XX" にする
+fn tag_dir_path(name: &str) -> anyhow::Result<String> {
+    if name.is_empty() {
+        anyhow::bail!("invalid tag name: {}", name);
+    }
+    if name == "." || name == ".." {
+        return Ok(format!("/tags/{}/", name.replace('.', "%2E")));
+    }
+    let mut segment = String::new();
+    for c in name.chars() {
+        if c.is_control() || " \"#%/<>?\\^`{|}".contains(c) {
+            for b in c.to_string().bytes() {
+                segment.push_str(&format!("%{:02X}", b));
+            }
+        } else {
+            segment.push(c);
+        }
+    }
+    Ok(format!("/tags/{}/", segment))
+}
+
+/// タグのページの URL のパス
+///
+/// ディレクトリ名の "%" は URL では "%25" になる
+fn tag_path(name: &str) -> anyhow::Result<String> {
+    Ok(tag_dir_path(name)?.replace('%', "%25"))
+}
+
 fn og_description(data: &str) -> String {
     data.chars().take(100).collect()
 }
 
-#[derive(Template)]
+#[derive(Template, serde::Serialize)]
 #[template(path = "page.html")]
 struct PageTemplate {
+    base_url: String,
     canonical_url: String,
     content: String,
     description: String,
+    lang: String,
     nav: String,
+    rel: PageRel,
+    site_title: String,
     title: String,
 }
 
-#[derive(Template)]
+/// `<link rel="prev">` と `<link rel="next">` の URL (ない場合は "")
+#[derive(Default, serde::Serialize)]
+struct PageRel {
+    next: String,
+    prev: String,
+}
+
+#[derive(Template, serde::Serialize)]
+#[template(path = "nav_page.html")]
+struct NavPageTemplate {
+    last_page: usize,
+    newer_path: String,
+    older_path: String,
+    page: usize,
+}
+
+#[derive(Template, serde::Serialize)]
 #[template(path = "nav_entry_detail.html")]
 struct NavEntryDetailTemplate {
     dd: String,
     mm: String,
-    next_path: Option<String>,
-    prev_path: Option<String>,
+    next_path: String,
+    prev_path: String,
     yyyy: String,
 }
 
-#[derive(Template)]
+#[derive(Template, serde::Serialize)]
+#[template(path = "nav_archive.html")]
+struct NavArchiveTemplate {
+    next_path: String,
+    parent_label: String,
+    parent_path: String,
+    prev_path: String,
+}
+
+#[derive(Template, serde::Serialize)]
 #[template(path = "entry_detail_content.html")]
 struct EntryDetailContentTemplate {
     date_str: String,
+    has_tags: bool,
     html: String,
     path: String,
     pubdate: String,
+    tags: Vec<TagLink>,
     title: String,
 }
 
+#[derive(serde::Serialize)]
+struct TagLink {
+    name: String,
+    path: String,
+}
+
+#[derive(serde::Serialize)]
+struct AtomEntryItem {
+    html: String,
+    pubdate: String,
+    title: String,
+    url: String,
+}
+
+#[derive(Template, serde::Serialize)]
+#[template(path = "atom.xml")]
+struct AtomTemplate {
+    entries: Vec<AtomEntryItem>,
+    feed_url: String,
+    site_title: String,
+    site_url: String,
+    updated: String,
+}
+
+#[derive(serde::Serialize)]
 struct EntryListItem {
     date_str: String,
     path: String,
     title: String,
 }
 
-#[derive(Template)]
+#[derive(Template, serde::Serialize)]
 #[template(path = "entry_list_content.html")]
 struct EntryListContentTemplate {
     items: Vec<EntryListItem>,
@@ -105,254 +183,729 @@ struct EntryListContentTemplate {
     list_url: String,
 }
 
+#[derive(serde::Serialize)]
+struct RelatedSection {
+    items: Vec<EntryListItem>,
+    title: String,
+}
+
+#[derive(Template, serde::Serialize)]
+#[template(path = "related_content.html")]
+struct RelatedContentTemplate {
+    list_title: String,
+    list_url: String,
+    sections: Vec<RelatedSection>,
+}
+
+#[derive(serde::Serialize)]
+struct TagListItem {
+    count: u32,
+    name: String,
+    path: String,
+}
+
+#[derive(Template, serde::Serialize)]
+#[template(path = "tag_list_content.html")]
+struct TagListContentTemplate {
+    items: Vec<TagListItem>,
+    list_title: String,
+    list_url: String,
+}
+
 fn render_nav_entry_detail(
-    prev: Option<&PostEntry>,
-    next: Option<&PostEntry>,
+    theme: &Theme,
+    prev: Option<&SiteEntry>,
+    next: Option<&SiteEntry>,
     date: &DateParts,
 ) -> anyhow::Result<String> {
-    let prev_path = prev
-        .map(|p| parse_date(&p.date))
-        .transpose()?
-        .map(|d| entry_path(&d));
-    let next_path = next
-        .map(|n| parse_date(&n.date))
-        .transpose()?
-        .map(|d| entry_path(&d));
-    NavEntryDetailTemplate {
-        prev_path,
-        next_path,
-        yyyy: date.yyyy.clone(),
-        mm: date.mm.clone(),
-        dd: date.dd.clone(),
-    }
-    .render()
-    .context("nav_entry_detail テンプレートのレンダリングに失敗")
+    let prev_path = prev.map(SiteEntry::path).unwrap_or_default();
+    let next_path = next.map(SiteEntry::path).unwrap_or_default();
+    theme
+        .render(
+            "nav_entry_detail.html",
+            &NavEntryDetailTemplate {
+                prev_path,
+                next_path,
+                yyyy: date.yyyy.clone(),
+                mm: date.mm.clone(),
+                dd: date.dd.clone(),
+            },
+        )
+        .context("nav_entry_detail テンプレートのレンダリングに失敗")
+}
+
+fn render_nav_archive(
+    theme: &Theme,
+    prev: Option<&str>,
+    next: Option<&str>,
+    period: &str,
+) -> anyhow::Result<String> {
+    // 月のページの親は年のページ、年のページの親はルートページ
+    let (parent_path, parent_label) = match period.split_once('-') {
+        Some((yyyy, _)) => (archive_path(yyyy), yyyy.to_string()),
+        None => ("/".to_string(), "home".to_string()),
+    };
+    theme
+        .render(
+            "nav_archive.html",
+            &NavArchiveTemplate {
+                next_path: next.map(archive_path).unwrap_or_default(),
+                parent_label,
+                parent_path,
+                prev_path: prev.map(archive_path).unwrap_or_default(),
+            },
+        )
+        .context("nav_archive テンプレートのレンダリングに失敗")
 }
 
 fn render_nav_entry_list() -> String {
     String::new()
 }
 
-fn render_entry_detail_content(detail: &EntryDetail, date: &DateParts) -> anyhow::Result<String> {
+fn render_entry_detail_content(
+    theme: &Theme,
+    entry: &SiteEntry,
+    date: &DateParts,
+) -> anyhow::Result<String> {
     let path = entry_path(date);
-    EntryDetailContentTemplate {
-        path,
-        date_str: detail.date.clone(),
-        title: detail.title.clone(),
-        html: detail.html.clone(),
-        pubdate: detail.pubdate.clone(),
-    }
-    .render()
-    .context("entry_detail_content テンプレートのレンダリングに失敗")
+    let tags = entry
+        .meta()
+        .tags
+        .iter()
+        .map(|name| {
+            Ok(TagLink {
+                name: name.clone(),
+                path: tag_path(name)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<TagLink>>>()?;
+    theme
+        .render(
+            "entry_detail_content.html",
+            &EntryDetailContentTemplate {
+                path,
+                date_str: entry.date(),
+                title: entry.meta().title.clone(),
+                has_tags: !tags.is_empty(),
+                html: entry.html().to_string(),
+                pubdate: entry.meta().pubdate.to_string(),
+                tags,
+            },
+        )
+        .context("entry_detail_content テンプレートのレンダリングに失敗")
 }
 
 fn render_entry_list_content(
-    entries: &[&PostEntry],
+    theme: &Theme,
+    entries: &[&SiteEntry],
     list_title: &str,
     list_url: &str,
 ) -> anyhow::Result<String> {
     let items = entries
         .iter()
-        .map(|entry| {
-            let date = parse_date(&entry.date)?;
-            let path = entry_path(&date);
-            Ok(EntryListItem {
-                path,
-                date_str: entry.date.clone(),
-                title: entry.title.clone(),
+        .map(|entry| entry_list_item(entry))
+        .collect::<Vec<_>>();
+    theme
+        .render(
+            "entry_list_content.html",
+            &EntryListContentTemplate {
+                list_url: list_url.to_string(),
+                list_title: list_title.to_string(),
+                items,
+            },
+        )
+        .context("entry_list_content テンプレートのレンダリングに失敗")
+}
+
+fn entry_list_item(entry: &SiteEntry) -> EntryListItem {
+    EntryListItem {
+        path: entry.path(),
+        date_str: entry.date(),
+        title: entry.meta().title.clone(),
+    }
+}
+
+fn render_related_content(
+    theme: &Theme,
+    site: &Site,
+    related: &Related,
+    list_title: &str,
+    list_url: &str,
+) -> anyhow::Result<String> {
+    // エントリーのない日付へのリンクは除く
+    let items = |dates: &mut dyn Iterator<Item = &EntryKey>| {
+        dates
+            .filter_map(|date| site.find(date))
+            .map(entry_list_item)
+            .collect::<Vec<EntryListItem>>()
+    };
+    let sections = [
+        RelatedSection {
+            items: items(&mut related.inbound.iter()),
+            title: "この記事へのリンク".to_string(),
+        },
+        RelatedSection {
+            items: items(&mut related.outbound.iter()),
+            title: "この記事からのリンク".to_string(),
+        },
+        RelatedSection {
+            items: items(&mut related.same.iter()),
+            title: "ほかの年の同じ日".to_string(),
+        },
+        // prev と next はどちらも降順なので日付の昇順に並べ直す
+        RelatedSection {
+            items: items(&mut related.prev.iter().rev().chain(related.next.iter().rev())),
+            title: "前後の記事".to_string(),
+        },
+    ]
+    .into_iter()
+    .filter(|section| !section.items.is_empty())
+    .collect::<Vec<RelatedSection>>();
+    theme
+        .render(
+            "related_content.html",
+            &RelatedContentTemplate {
+                list_title: list_title.to_string(),
+                list_url: list_url.to_string(),
+                sections,
+            },
+        )
+        .context("related_content テンプレートのレンダリングに失敗")
+}
+
+fn render_tag_list_content(theme: &Theme, site: &Site) -> anyhow::Result<String> {
+    let items = site
+        .tag_counts()
+        .into_iter()
+        .map(|(name, count)| {
+            Ok(TagListItem {
+                count,
+                path: tag_path(&name)?,
+                name,
             })
         })
-        .collect::<anyhow::Result<Vec<_>>>()?;
-    EntryListContentTemplate {
-        list_url: list_url.to_string(),
-        list_title: list_title.to_string(),
-        items,
-    }
-    .render()
-    .context("entry_list_content テンプレートのレンダリングに失敗")
+        .collect::<anyhow::Result<Vec<TagListItem>>>()?;
+    theme
+        .render(
+            "tag_list_content.html",
+            &TagListContentTemplate {
+                items,
+                list_title: "タグ一覧".to_string(),
+                list_url: "/tags/".to_string(),
+            },
+        )
+        .context("tag_list_content テンプレートのレンダリングに失敗")
 }
 
+#[allow(clippy::too_many_arguments)]
 fn render_page(
+    theme: &Theme,
+    site_config: &SiteConfig,
     title: &str,
     canonical_url: &str,
     description: &str,
     nav: &str,
     content: &str,
+    rel: PageRel,
 ) -> anyhow::Result<String> {
-    PageTemplate {
-        title: title.to_string(),
-        canonical_url: canonical_url.to_string(),
-        description: description.to_string(),
-        nav: nav.to_string(),
-        content: content.to_string(),
-    }
-    .render()
-    .context("page テンプレートのレンダリングに失敗")
+    theme
+        .render(
+            "page.html",
+            &PageTemplate {
+                base_url: site_config.base_url().to_string(),
+                title: title.to_string(),
+                canonical_url: canonical_url.to_string(),
+                description: description.to_string(),
+                lang: site_config.lang().to_string(),
+                nav: nav.to_string(),
+                content: content.to_string(),
+                rel,
+                site_title: site_config.site_title().to_string(),
+            },
+        )
+        .context("page テンプレートのレンダリングに失敗")
 }
 
-fn write_html(out_dir: &Path, path: &str, html: &str, verbose: bool) -> anyhow::Result<()> {
-    // path は "/" で始まり "/" で終わる想定
-    // index.html を出力
-    let index_path = out_dir
-        .join(path.trim_start_matches('/'))
-        .join("index.html");
-    if let Some(parent) = index_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    let mut file = File::create(&index_path)?;
-    file.write_all(html.as_bytes())?;
-    if verbose {
-        println!("{}", index_path.display());
-    }
+fn render_atom(
+    theme: &Theme,
+    site_config: &SiteConfig,
+    entries: &[SiteEntry],
+    atom_entries: usize,
+) -> anyhow::Result<String> {
+    // 最新（末尾）から atom_entries 件
+    let entries = entries
+        .iter()
+        .rev()
+        .take(atom_entries)
+        .map(|entry| AtomEntryItem {
+            html: entry.html().to_string(),
+            pubdate: entry.meta().pubdate.to_string(),
+            title: format!("{} {}", entry.date(), entry.meta().title),
+            url: site_config.url(&entry.path()),
+        })
+        .collect::<Vec<_>>();
+    let updated = match entries.iter().map(|entry| entry.pubdate.as_str()).max() {
+        Some(pubdate) => pubdate.to_string(),
+        None => Timestamp::now()?.to_rfc3339(),
+    };
+    theme
+        .render(
+            "atom.xml",
+            &AtomTemplate {
+                entries,
+                feed_url: site_config.url("/atom.xml"),
+                site_title: site_config.site_title().to_string(),
+                site_url: site_config.url("/"),
+                updated,
+            },
+        )
+        .context("atom テンプレートのレンダリングに失敗")
+}
+
+fn write_atom_xml(out_dir: &Path, xml: &str) -> anyhow::Result<PathBuf> {
+    let path = out_dir.join("atom.xml");
+    fs::create_dir_all(out_dir)?;
+    let mut file = File::create(&path)?;
+    file.write_all(xml.as_bytes())?;
+    Ok(path)
+}
 
-    // ルートパス以外は .html も出力
+/// `path` のページの出力先 (index.html と、ルートパス以外は .html)
+fn html_paths(out_dir: &Path, path: &str) -> Vec<PathBuf> {
+    // path は "/" で始まり "/" で終わる想定
+    let mut paths = vec![
+        out_dir
+            .join(path.trim_start_matches('/'))
+            .join("index.html"),
+    ];
     if path != "/" {
         let trimmed = path.trim_start_matches('/').trim_end_matches('/');
-        let html_path = out_dir.join(format!("{}.html", trimmed));
-        if let Some(parent) = html_path.parent() {
+        paths.push(out_dir.join(format!("{}.html", trimmed)));
+    }
+    paths
+}
+
+/// 書き込んだファイルのパスを返す
+fn write_html(out_dir: &Path, path: &str, html: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let written = html_paths(out_dir, path);
+    for path in written.iter() {
+        if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
-        let mut file = File::create(&html_path)?;
+        let mut file = File::create(path)?;
         file.write_all(html.as_bytes())?;
-        if verbose {
-            println!("{}", html_path.display());
+    }
+    Ok(written)
+}
+
+fn print_written(written: &[PathBuf], verbose: bool) {
+    if verbose {
+        for path in written {
+            println!("{}", path.display());
         }
     }
+}
 
-    Ok(())
+/// ページ番号 (1 から) -> `path` または "{path}page/{page}/"
+fn list_page_path(path: &str, page: usize) -> String {
+    if page == 1 {
+        path.to_string()
+    } else {
+        format!("{}page/{}/", path, page)
+    }
 }
 
-fn get_page_entries<'a>(
-    entries: &'a [PostEntry],
-    focus_index: Option<usize>,
-) -> Vec<&'a PostEntry> {
-    let len = entries.len();
-    if len == 0 {
-        return vec![];
+/// 新しい順に page_size 件ずつに分ける (エントリーがなくても 1 ページ目は返す)
+///
+/// `entries` は古い順
+fn paginate<'a>(entries: &[&'a SiteEntry], page_size: NonZeroUsize) -> Vec<Vec<&'a SiteEntry>> {
+    let entries = entries.iter().rev().copied().collect::<Vec<&SiteEntry>>();
+    if entries.is_empty() {
+        return vec![vec![]];
     }
-    match focus_index {
-        Some(idx) => {
-            let start = idx.saturating_sub(4);
-            let end = (idx + 5).min(len);
-            entries[start..end].iter().collect()
-        }
-        // ルートページ: 最新（末尾）から9件
-        None => {
-            let start = len.saturating_sub(9);
-            entries[start..].iter().rev().collect()
+    entries
+        .chunks(page_size.get())
+        .map(|chunk| chunk.to_vec())
+        .collect()
+}
+
+fn render_nav_page(
+    theme: &Theme,
+    path: &str,
+    page: usize,
+    last_page: usize,
+) -> anyhow::Result<String> {
+    theme
+        .render(
+            "nav_page.html",
+            &NavPageTemplate {
+                last_page,
+                newer_path: if page > 1 {
+                    list_page_path(path, page - 1)
+                } else {
+                    String::new()
+                },
+                older_path: if page < last_page {
+                    list_page_path(path, page + 1)
+                } else {
+                    String::new()
+                },
+                page,
+            },
+        )
+        .context("nav_page テンプレートのレンダリングに失敗")
+}
+
+/// ページに分けて出力する entry-list (ルートページ、期間ごとのページ、タグごとのページ)
+struct EntryList<'a> {
+    /// 1 ページ目のページのタイトル
+    title: String,
+    list_title: String,
+    /// 1 ページ目の URL のパス
+    path: String,
+    /// 1 ページ目の出力先 (タグのページは URL のパスと異なる)
+    dir_path: String,
+    /// ページのナビゲーションの前に置くナビゲーション
+    nav: String,
+    /// 古い順
+    entries: Vec<&'a SiteEntry>,
+}
+
+/// `list` の 1 ページ目と "{path}page/{page}/" のページ (entry-list) を出力する
+///
+/// `skip` の場合は書き込まずに出力先だけを返す
+fn write_list_pages(
+    out_dir: &Path,
+    site_config: &SiteConfig,
+    theme: &Theme,
+    list: &EntryList,
+    page_size: NonZeroUsize,
+    skip: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut written = vec![];
+    let pages = paginate(&list.entries, page_size);
+    let last_page = pages.len();
+    for (i, page_entries) in pages.iter().enumerate() {
+        let page = i + 1;
+        let path = list_page_path(&list.path, page);
+        let dir_path = list_page_path(&list.dir_path, page);
+        if skip {
+            written.extend(html_paths(out_dir, &dir_path));
+            continue;
         }
+        let (title, list_title) = if page == 1 {
+            (list.title.clone(), list.list_title.clone())
+        } else {
+            let list_title = format!("{} ({} ページ目)", list.list_title, page);
+            (list_title.clone(), list_title)
+        };
+        let content = render_entry_list_content(theme, page_entries, &list_title, &path)?;
+        let nav = format!(
+            "{}{}",
+            list.nav,
+            render_nav_page(theme, &list.path, page, last_page)?
+        );
+        let rel = PageRel {
+            next: if page < last_page {
+                site_config.url(&list_page_path(&list.path, page + 1))
+            } else {
+                String::new()
+            },
+            prev: if page > 1 {
+                site_config.url(&list_page_path(&list.path, page - 1))
+            } else {
+                String::new()
+            },
+        };
+        let html = render_page(
+            theme,
+            site_config,
+            &title,
+            &site_config.url(&path),
+            "",
+            &nav,
+            &content,
+            rel,
+        )?;
+        written.extend(write_html(out_dir, &dir_path, &html)?);
     }
+    Ok(written)
 }
 
-fn find_id_title(out_dir: &Path, date: &DateParts) -> Option<String> {
-    // build-json は {yyyy}/{mm}/{dd}/{idTitle}/index.json を出力する
-    // {yyyy}/{mm}/{dd}/ ディレクトリ内のサブディレクトリを探す
-    let dir = out_dir.join(&date.yyyy).join(&date.mm).join(&date.dd);
-    if let Ok(read_dir) = fs::read_dir(&dir) {
-        for entry in read_dir.flatten() {
-            let path = entry.path();
-            if path.is_dir() {
-                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    // "related" ディレクトリは build-html が作るものなので除外
-                    if name != "related" {
-                        return Some(name.to_string());
-                    }
-                }
-            }
-        }
+/// エントリーのページ (entry-detail, idTitle, entry-list (related)) の出力先
+fn entry_page_paths(out_dir: &Path, entry: &SiteEntry) -> anyhow::Result<Vec<PathBuf>> {
+    let path = entry_path(&parse_date(&entry.date())?);
+    Ok([
+        path.clone(),
+        format!("{}{}/", path, entry.id_title()),
+        format!("{}related/", path),
+    ]
+    .iter()
+    .flat_map(|path| html_paths(out_dir, path))
+    .collect())
+}
+
+/// エントリーのページ (entry-detail, idTitle, entry-list (related)) を出力する
+fn write_entry_pages(
+    out_dir: &Path,
+    site_config: &SiteConfig,
+    theme: &Theme,
+    site: &Site,
+    related: &Related,
+    i: usize,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let entries = site.entries();
+    let entry = &entries[i];
+    let date = parse_date(&entry.date())?;
+    let path = entry_path(&date);
+    let mut written = vec![];
+
+    // entry-detail ページ
+    {
+        let prev = if i > 0 { Some(&entries[i - 1]) } else { None };
+        let next = entries.get(i + 1);
+        let nav = render_nav_entry_detail(theme, prev, next, &date)?;
+        let content = render_entry_detail_content(theme, entry, &date)?;
+        let title = format!("{} {}", entry.date(), entry.meta().title);
+        let canonical_url = site_config.url(&path);
+        let description = og_description(entry.content());
+        let html = render_page(
+            theme,
+            site_config,
+            &title,
+            &canonical_url,
+            &description,
+            &nav,
+            &content,
+            PageRel::default(),
+        )?;
+        written.extend(write_html(out_dir, &path, &html)?);
+
+        // idTitle ページ（entry-detail と同内容）
+        let id_title_path = format!("{}{}/", path, entry.id_title());
+        written.extend(write_html(out_dir, &id_title_path, &html)?);
     }
-    None
-}
-
-pub fn run(out_dir: PathBuf, verbose: bool) -> anyhow::Result<()> {
-    // posts.json を読み込み
-    let posts_path = out_dir.join("posts.json");
-    let file = File::open(&posts_path)
-        .with_context(|| format!("failed to open {}", posts_path.display()))?;
-    let reader = BufReader::new(file);
-    let posts: Vec<PostEntry> = serde_json::from_reader(reader)
-        .with_context(|| format!("failed to parse {}", posts_path.display()))?;
-
-    // 各エントリの詳細を読み込み
-    let mut details: Vec<EntryDetail> = Vec::with_capacity(posts.len());
-    for post in &posts {
-        let date = parse_date(&post.date)?;
-        let json_path = out_dir
-            .join(&date.yyyy)
-            .join(&date.mm)
-            .join(&date.dd)
-            .join("index.json");
-        let file = File::open(&json_path)
-            .with_context(|| format!("failed to open {}", json_path.display()))?;
-        let reader = BufReader::new(file);
-        let detail: EntryDetail = serde_json::from_reader(reader)
-            .with_context(|| format!("failed to parse {}", json_path.display()))?;
-        details.push(detail);
-    }
-
-    // ルートページ（最新エントリ一覧）
+
+    // entry-list (related) ページ
     {
-        let page_entries = get_page_entries(&posts, None);
-        let content = render_entry_list_content(&page_entries, "最近の記事", "/")?;
+        let list_title = format!("{} {} の関連記事", entry.date(), entry.meta().title);
+        let list_url = format!("{}related/", path);
+        let content = render_related_content(theme, site, related, &list_title, &list_url)?;
         let nav = render_nav_entry_list();
+        let title = format!("{} {} の関連記事", entry.date(), entry.meta().title);
+        let canonical_url = site_config.url(&format!("{}related/", path));
         let html = render_page(
-            "blog.bouzuya.net",
-            "https://blog.bouzuya.net/",
+            theme,
+            site_config,
+            &title,
+            &canonical_url,
             "",
             &nav,
             &content,
+            PageRel::default(),
         )?;
-        write_html(&out_dir, "/", &html, verbose)?;
+        written.extend(write_html(out_dir, &format!("{}related/", path), &html)?);
     }
 
-    // 各エントリのページ
-    for (i, (post, detail)) in posts.iter().zip(details.iter()).enumerate() {
-        let date = parse_date(&post.date)?;
-        let path = entry_path(&date);
+    Ok(written)
+}
 
-        // entry-detail ページ
-        {
-            let prev = if i > 0 { Some(&posts[i - 1]) } else { None };
-            let next = if i + 1 < posts.len() {
-                Some(&posts[i + 1])
-            } else {
-                None
-            };
-            let nav = render_nav_entry_detail(prev, next, &date)?;
-            let content = render_entry_detail_content(detail, &date)?;
-            let title = format!("{} {}", detail.date, detail.title);
-            let canonical_url = format!("https://blog.bouzuya.net{}", path);
-            let description = og_description(&detail.data);
-            let html = render_page(&title, &canonical_url, &description, &nav, &content)?;
-            write_html(&out_dir, &path, &html, verbose)?;
-
-            // idTitle ページ（entry-detail と同内容）
-            let id_title = find_id_title(&out_dir, &date).unwrap_or_else(|| "diary".to_string());
-            let id_title_path = format!(
-                "{}{}/",
-                path.trim_end_matches('/'),
-                format!("/{}", id_title)
-            );
-            write_html(&out_dir, &id_title_path, &html, verbose)?;
-        }
+/// 期間ごとのページ (entry-list (archive)) を出力する
+///
+/// `archives` は "YYYY" または "YYYY-MM" -> その期間のエントリー
+/// `affected` にない期間のページは書き込まずに出力先だけを返す
+fn write_archive_pages(
+    out_dir: &Path,
+    site_config: &SiteConfig,
+    theme: &Theme,
+    archives: &BTreeMap<String, Vec<&SiteEntry>>,
+    page_size: NonZeroUsize,
+    affected: Option<&BTreeSet<String>>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut written = vec![];
+    let periods = archives.keys().map(String::as_str).collect::<Vec<&str>>();
+    for (i, (period, entries)) in archives.iter().enumerate() {
+        let path = archive_path(period);
+        let skip = affected.is_some_and(|affected| !affected.contains(period));
+        let nav = if skip {
+            String::new()
+        } else {
+            let prev = i.checked_sub(1).map(|j| periods[j]);
+            let next = periods.get(i + 1).copied();
+            render_nav_archive(theme, prev, next, period)?
+        };
+        let title = format!("{} の記事", period);
+        let list = EntryList {
+            title: title.clone(),
+            list_title: title,
+            path: path.clone(),
+            dir_path: path,
+            nav,
+            entries: entries.clone(),
+        };
+        written.extend(write_list_pages(
+            out_dir,
+            site_config,
+            theme,
+            &list,
+            page_size,
+            skip,
+        )?);
+    }
+    Ok(written)
+}
+
+/// タグのページ (tag-list, タグごとの entry-list) を出力する
+///
+/// `affected` にないタグのページは書き込まずに出力先だけを返す
+fn write_tag_pages(
+    out_dir: &Path,
+    site_config: &SiteConfig,
+    theme: &Theme,
+    site: &Site,
+    page_size: NonZeroUsize,
+    affected: Option<&BTreeSet<String>>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut written = vec![];
+
+    // tag-list ページ
+    {
+        let content = render_tag_list_content(theme, site)?;
+        let nav = render_nav_entry_list();
+        let html = render_page(
+            theme,
+            site_config,
+            "タグ一覧",
+            &site_config.url("/tags/"),
+            "",
+            &nav,
+            &content,
+            PageRel::default(),
+        )?;
+        written.extend(write_html(out_dir, "/tags/", &html)?);
+    }
+
+    // タグごとの entry-list ページ (新しい順)
+    for name in site.tag_counts().into_keys() {
+        let skip = affected.is_some_and(|affected| !affected.contains(&name));
+        let title = format!("タグ {} の記事", name);
+        let list = EntryList {
+            title: title.clone(),
+            list_title: title,
+            path: tag_path(&name)?,
+            dir_path: tag_dir_path(&name)?,
+            nav: render_nav_entry_list(),
+            entries: site
+                .entries()
+                .iter()
+                .filter(|entry| entry.meta().tags.contains(&name))
+                .collect::<Vec<&SiteEntry>>(),
+        };
+        written.extend(write_list_pages(
+            out_dir,
+            site_config,
+            theme,
+            &list,
+            page_size,
+            skip,
+        )?);
+    }
 
-        // entry-list (related) ページ
-        {
-            let page_entries = get_page_entries(&posts, Some(i));
-            let list_title = format!("{} {} の関連記事", detail.date, detail.title);
-            let list_url = format!("{}related/", path);
-            let content = render_entry_list_content(&page_entries, &list_title, &list_url)?;
-            let nav = render_nav_entry_list();
-            let title = format!("{} {} の関連記事", detail.date, detail.title);
-            let canonical_url = format!("https://blog.bouzuya.net{}related/", path);
-            let html = render_page(&title, &canonical_url, "", &nav, &content)?;
-            write_html(&out_dir, &format!("{}related/", path), &html, verbose)?;
+    Ok(written)
+}
+
+/// 出力するすべてのファイルのパスを返す
+///
+/// `affected` が `Some` の場合は、そこにないエントリー・期間・タグのページを書き込まない
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    site: &Site,
+    affected: Option<&Affected>,
+    out_dir: PathBuf,
+    site_config: &SiteConfig,
+    theme: &Theme,
+    atom_entries: usize,
+    page_size: NonZeroUsize,
+    jobs: NonZeroUsize,
+    verbose: bool,
+) -> anyhow::Result<BTreeSet<PathBuf>> {
+    let mut files = BTreeSet::new();
+    let entries = site.entries();
+
+    // ルートページ（最新エントリ一覧）と "/page/{page}/"
+    {
+        let list = EntryList {
+            title: site_config.site_title().to_string(),
+            list_title: "最近の記事".to_string(),
+            path: "/".to_string(),
+            dir_path: "/".to_string(),
+            nav: String::new(),
+            entries: entries.iter().collect::<Vec<&SiteEntry>>(),
+        };
+        let written = write_list_pages(&out_dir, site_config, theme, &list, page_size, false)?;
+        print_written(&written, verbose);
+        files.extend(written);
+    }
+
+    // Atom フィード
+    {
+        let xml = render_atom(theme, site_config, entries, atom_entries)?;
+        let written = write_atom_xml(&out_dir, &xml)?;
+        print_written(std::slice::from_ref(&written), verbose);
+        files.insert(written);
+    }
+
+    // 各エントリのページ (並列に出力し、出力したパスは順に表示する)
+    let related = site.related();
+    let indices = (0..entries.len()).collect::<Vec<usize>>();
+    let written = parallel::map(jobs, &indices, |i| {
+        let date = entries[*i].entry_id().date();
+        if affected.is_some_and(|affected| !affected.entries.contains(date)) {
+            return entry_page_paths(&out_dir, &entries[*i]);
         }
+        write_entry_pages(&out_dir, site_config, theme, site, &related[*i], *i)
+    })?;
+    for written in written {
+        print_written(&written, verbose);
+        files.extend(written);
+    }
+
+    // 年・月のページ
+    for archives in [site.years(), site.months()] {
+        let written = write_archive_pages(
+            &out_dir,
+            site_config,
+            theme,
+            &archives,
+            page_size,
+            affected.map(|affected| &affected.periods),
+        )?;
+        print_written(&written, verbose);
+        files.extend(written);
+    }
+
+    // タグのページ
+    {
+        let written = write_tag_pages(
+            &out_dir,
+            site_config,
+            theme,
+            site,
+            page_size,
+            affected.map(|affected| &affected.tags),
+        )?;
+        print_written(&written, verbose);
+        files.extend(written);
     }
 
     // public ディレクトリ内のファイル・ディレクトリを再帰的に out_dir に出力
+    // テーマの public ディレクトリに同じパスのファイルがあればそちらを優先する
+    let theme_public_files = theme.public_files()?;
     for entry in PUBLIC_DIR.find("**/*")? {
         let entry_path = entry.path();
+        if theme_public_files.contains_key(entry_path) {
+            continue;
+        }
         if let Some(file) = entry.as_file() {
             let out_path = out_dir.join(entry_path);
             if let Some(parent) = out_path.parent() {
@@ -362,14 +915,32 @@ pub fn run(out_dir: PathBuf, verbose: bool) -> anyhow::Result<()> {
             if verbose {
                 println!("{}", out_path.display());
             }
+            files.insert(out_path);
+        }
+    }
+    for (entry_path, theme_path) in theme_public_files {
+        let out_path = out_dir.join(entry_path);
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&theme_path, &out_path)?;
+        if verbose {
+            println!("{}", out_path.display());
         }
+        files.insert(out_path);
     }
 
-    Ok(())
+    Ok(files)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
+    use bbn_data::DateTime;
+    use bbn_data::EntryId;
+    use bbn_data::EntryMeta;
+
     use super::*;
 
     #[test]
@@ -380,6 +951,40 @@ mod tests {
         assert_eq!(date.dd, "15");
     }
 
+    #[test]
+    fn test_archive_path() {
+        assert_eq!(archive_path("2021"), "/2021/");
+        assert_eq!(archive_path("2021-02"), "/2021/02/");
+    }
+
+    #[test]
+    fn test_render_nav_archive() -> anyhow::Result<()> {
+        let nav = render_nav_archive(&Theme::default(), Some("2021-01"), None, "2021-02")?;
+        assert!(nav.contains(r#"<a class="nav-prev" href="/2021/01/">prev</a>"#));
+        assert!(nav.contains(r#"<a class="nav-list" href="/2021/">2021</a>"#));
+        assert!(nav.contains("nav-next-placeholder"));
+
+        let nav = render_nav_archive(&Theme::default(), None, Some("2022"), "2021")?;
+        assert!(nav.contains("nav-prev-placeholder"));
+        assert!(nav.contains(r#"<a class="nav-list" href="/">home</a>"#));
+        assert!(nav.contains(r#"<a class="nav-next" href="/2022/">next</a>"#));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tag_path() -> anyhow::Result<()> {
+        assert_eq!(tag_path("rust")?, "/tags/rust/");
+        assert_eq!(tag_path("日記")?, "/tags/日記/");
+        assert!(tag_path("").is_err());
+        assert_eq!(tag_dir_path("..")?, "/tags/%2E%2E/");
+        assert_eq!(tag_path("..")?, "/tags/%252E%252E/");
+        assert_eq!(tag_dir_path("a/b\\c")?, "/tags/a%2Fb%5Cc/");
+        assert_eq!(tag_dir_path("c++ & c#?")?, "/tags/c++%20&%20c%23%3F/");
+        assert_eq!(tag_dir_path("100%")?, "/tags/100%25/");
+        assert_eq!(tag_path("100%")?, "/tags/100%2525/");
+        Ok(())
+    }
+
     #[test]
     fn test_og_description() {
         let short = "hello world";
@@ -389,41 +994,200 @@ mod tests {
         assert_eq!(og_description(&long).len(), 100);
     }
 
-    #[test]
-    fn test_get_page_entries() {
-        let make_post = |date: &str, title: &str| PostEntry {
-            date: date.to_string(),
-            minutes: 5,
-            pubdate: format!("{}T00:00:00+09:00", date),
-            tags: vec![],
-            title: title.to_string(),
-        };
+    fn make_entry(date: &str, title: &str) -> anyhow::Result<SiteEntry> {
+        SiteEntry::new(
+            EntryId::from_str(date)?,
+            EntryMeta::new(
+                5,
+                DateTime::from_str(&format!("{}T00:00:00+09:00", date))?,
+                vec![],
+                title.to_string(),
+            ),
+            format!("content {}", title),
+        )
+    }
 
-        let posts: Vec<PostEntry> = (1..=20)
-            .map(|i| make_post(&format!("2024-01-{:02}", i), &format!("Title {}", i)))
-            .collect();
+    #[test]
+    fn test_paginate() -> anyhow::Result<()> {
+        let entries = (1..=20)
+            .map(|i| make_entry(&format!("2024-01-{:02}", i), &format!("Title {}", i)))
+            .collect::<anyhow::Result<Vec<SiteEntry>>>()?;
+        let entries = entries.iter().collect::<Vec<&SiteEntry>>();
+        let page_size = NonZeroUsize::new(9).unwrap();
 
         // ルートページ: 最新9件（逆順）
-        let root = get_page_entries(&posts, None);
+        let pages = paginate(&entries, page_size);
+        assert_eq!(pages.len(), 3);
+        let root = &pages[0];
         assert_eq!(root.len(), 9);
-        assert_eq!(root[0].date, "2024-01-20");
-        assert_eq!(root[8].date, "2024-01-12");
-
-        // フォーカス位置10（index 9）: 前後4件
-        let focused = get_page_entries(&posts, Some(9));
-        assert_eq!(focused.len(), 9);
-        assert_eq!(focused[0].date, "2024-01-06");
-        assert_eq!(focused[4].date, "2024-01-10");
-        assert_eq!(focused[8].date, "2024-01-14");
-
-        // 先頭付近
-        let near_start = get_page_entries(&posts, Some(1));
-        assert_eq!(near_start.len(), 6);
-        assert_eq!(near_start[0].date, "2024-01-01");
-
-        // 末尾付近
-        let near_end = get_page_entries(&posts, Some(18));
-        assert_eq!(near_end.len(), 6);
-        assert_eq!(near_end[near_end.len() - 1].date, "2024-01-20");
+        assert_eq!(root[0].date(), "2024-01-20");
+        assert_eq!(root[8].date(), "2024-01-12");
+        assert_eq!(pages[2].len(), 2);
+        assert_eq!(pages[2][1].date(), "2024-01-01");
+
+        let pages = paginate(&entries[..3], page_size);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0][0].date(), "2024-01-03");
+        assert_eq!(paginate(&[], page_size).len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_page_path() {
+        assert_eq!(list_page_path("/", 1), "/");
+        assert_eq!(list_page_path("/", 2), "/page/2/");
+        assert_eq!(list_page_path("/tags/rust/", 1), "/tags/rust/");
+        assert_eq!(list_page_path("/tags/rust/", 3), "/tags/rust/page/3/");
+    }
+
+    #[test]
+    fn test_render_nav_page() -> anyhow::Result<()> {
+        let nav = render_nav_page(&Theme::default(), "/", 1, 3)?;
+        assert!(nav.contains(r#"<a class="nav-older" href="/page/2/" rel="next">older</a>"#));
+        assert!(nav.contains("nav-newer-placeholder"));
+
+        let nav = render_nav_page(&Theme::default(), "/", 2, 3)?;
+        assert!(nav.contains(r#"href="/page/3/""#));
+        assert!(nav.contains(r#"<a class="nav-newer" href="/" rel="prev">newer</a>"#));
+
+        let nav = render_nav_page(&Theme::default(), "/", 3, 3)?;
+        assert!(nav.contains("nav-older-placeholder"));
+
+        let nav = render_nav_page(&Theme::default(), "/tags/rust/", 2, 3)?;
+        assert!(nav.contains(r#"href="/tags/rust/page/3/""#));
+        assert!(nav.contains(r#"href="/tags/rust/""#));
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_related_content() -> anyhow::Result<()> {
+        let site = Site::new(
+            (1..=20)
+                .map(|i| make_entry(&format!("2024-01-{:02}", i), &format!("Title {}", i)))
+                .collect::<anyhow::Result<Vec<SiteEntry>>>()?,
+        );
+        let date = |s: &str| EntryKey::from_str(s);
+        let related = Related {
+            inbound: vec![date("2024-01-02")?],
+            next: vec![date("2024-01-14")?, date("2024-01-11")?],
+            outbound: vec![date("2023-12-31")?],
+            prev: vec![date("2024-01-09")?, date("2024-01-06")?],
+            same: vec![],
+        };
+        let html = render_related_content(
+            &Theme::default(),
+            &site,
+            &related,
+            "TITLE",
+            "/2024/01/10/related/",
+        )?;
+        assert!(html.contains("この記事へのリンク"));
+        assert!(html.contains(r#"<a href="/2024/01/02/">"#));
+        // エントリーのないリンク先や空のセクションは出力しない
+        assert!(!html.contains("この記事からのリンク"));
+        assert!(!html.contains("ほかの年の同じ日"));
+        // 前後の記事は日付の昇順
+        let nearby = ["2024-01-06", "2024-01-09", "2024-01-11", "2024-01-14"]
+            .iter()
+            .map(|d| html.find(&format!(r#"<span class="id">{}</span>"#, d)))
+            .collect::<Option<Vec<usize>>>()
+            .unwrap();
+        assert!(nearby.windows(2).all(|w| w[0] < w[1]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_builtin_templates_in_theme() -> anyhow::Result<()> {
+        // 組み込みのテンプレートをそのままテーマにコピーしても同じ出力になる
+        let temp_dir = tempfile::tempdir()?;
+        let templates_dir = temp_dir.path().join("templates");
+        fs::create_dir_all(templates_dir.as_path())?;
+        let builtin_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("templates");
+        for dir_entry in fs::read_dir(builtin_dir.as_path())? {
+            let path = dir_entry?.path();
+            fs::copy(
+                path.as_path(),
+                templates_dir.join(path.file_name().unwrap()),
+            )?;
+        }
+        let builtin = Theme::default();
+        let theme = Theme::new(Some(temp_dir.path()))?;
+
+        let entry1 = SiteEntry::new(
+            EntryId::from_str("2024-01-01")?,
+            EntryMeta::new(
+                5,
+                DateTime::from_str("2024-01-01T00:00:00+09:00")?,
+                vec!["rust".to_string(), "日記".to_string()],
+                "<Title> & \"1\"".to_string(),
+            ),
+            "[2024-01-02] 'a' <b>\n\n[2024-01-02]: https://blog.bouzuya.net/2024/01/02/\n"
+                .to_string(),
+        )?;
+        let entry2 = make_entry("2024-01-02", "Title 2")?;
+        let site = Site::new(vec![entry1, entry2]);
+        let [entry1, entry2] = site.entries() else {
+            unreachable!()
+        };
+        let date = parse_date(&entry1.date())?;
+        let related = site.related();
+        let render = |theme: &Theme| -> anyhow::Result<Vec<String>> {
+            Ok(vec![
+                render_nav_entry_detail(theme, None, Some(entry2), &date)?,
+                render_nav_entry_detail(theme, Some(entry1), None, &date)?,
+                render_nav_archive(theme, Some("2023-12"), None, "2024-01")?,
+                render_nav_archive(theme, None, Some("2024-02"), "2024-01")?,
+                render_nav_page(theme, "/", 1, 2)?,
+                render_nav_page(theme, "/", 2, 2)?,
+                render_entry_detail_content(theme, entry1, &date)?,
+                render_entry_detail_content(theme, entry2, &date)?,
+                render_entry_list_content(theme, &[entry1, entry2], "<list>", "/")?,
+                render_related_content(theme, &site, &related[0], "<related>", "/")?,
+                render_tag_list_content(theme, &site)?,
+                render_page(
+                    theme,
+                    &SiteConfig::default(),
+                    "<title>",
+                    "https://blog.bouzuya.net/",
+                    "'description'",
+                    "<nav></nav>",
+                    "<p>content</p>",
+                    PageRel::default(),
+                )?,
+                render_page(
+                    theme,
+                    &SiteConfig::default(),
+                    "title",
+                    "https://blog.bouzuya.net/page/2/",
+                    "",
+                    "",
+                    "",
+                    PageRel {
+                        next: "https://blog.bouzuya.net/page/3/".to_string(),
+                        prev: "https://blog.bouzuya.net/".to_string(),
+                    },
+                )?,
+                render_atom(theme, &SiteConfig::default(), site.entries(), 2)?,
+            ])
+        };
+        assert_eq!(render(&theme)?, render(&builtin)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_atom() -> anyhow::Result<()> {
+        let entries = (1..=3)
+            .map(|i| make_entry(&format!("2024-01-{:02}", i), &i.to_string()))
+            .collect::<anyhow::Result<Vec<SiteEntry>>>()?;
+
+        // 最新2件（逆順）
+        let xml = render_atom(&Theme::default(), &SiteConfig::default(), &entries, 2)?;
+        assert!(xml.contains("<updated>2024-01-03T00:00:00+09:00</updated>"));
+        assert!(xml.contains("<id>https://blog.bouzuya.net/2024/01/03/</id>"));
+        assert!(xml.contains("<id>https://blog.bouzuya.net/2024/01/02/</id>"));
+        assert!(!xml.contains("<id>https://blog.bouzuya.net/2024/01/01/</id>"));
+        assert!(xml.find("2024/01/03").unwrap() < xml.find("2024/01/02").unwrap());
+        assert!(xml.contains("&#60;p&#62;content 3&#60;/p&#62;"));
+        Ok(())
     }
 }
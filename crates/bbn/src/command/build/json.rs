@@ -1,19 +1,19 @@
-use anyhow::Context;
 use bbn_data::EntryKey;
-use bbn_repository::BbnRepository;
-use bbn_repository::Query;
-use pulldown_cmark::Parser;
-use pulldown_cmark::html;
-use regex::Regex;
+use bbn_data::hash;
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 use std::convert::TryFrom;
-use std::fs::File;
 use std::fs::{self};
-use std::io::BufWriter;
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::path::PathBuf;
-use std::str::FromStr;
+
+use super::manifest::Manifest;
+use super::manifest::write_if_changed;
+use super::parallel;
+use super::site::Related;
+use super::site::Site;
+use super::site::SiteEntry;
 
 // <https://github.com/bouzuya/kraken/tree/v4.0.2/doc#all-json>
 // all json (`/posts.json`)
@@ -54,42 +54,44 @@ pub struct TagsJsonItem {
     pub count: u32,
 }
 
-fn write_all_json(out_dir: &Path, all_json: &AllJson, verbose: bool) -> anyhow::Result<()> {
-    let path = out_dir.join("posts.json");
-    let file = File::create(&path)?;
-    let writer = BufWriter::new(file);
-    serde_json::to_writer(writer, all_json)?;
-    if verbose {
-        println!("{}", path.display());
-    }
-    Ok(())
+/// 書き込んだ場合はそのパスを返す
+fn write_json<T: serde::Serialize>(
+    path: &Path,
+    value: &T,
+    force: bool,
+) -> anyhow::Result<Option<PathBuf>> {
+    let written = write_if_changed(path, serde_json::to_string(value)?.as_bytes(), force)?;
+    Ok(written.then(|| path.to_path_buf()))
 }
 
-fn write_daily_json(out_dir: &Path, daily_json: &DailyJson, verbose: bool) -> anyhow::Result<()> {
-    let date = daily_json.date.split('-').collect::<Vec<&str>>();
+fn daily_json_paths(out_dir: &Path, date: &str, id_title: Option<&str>) -> Vec<PathBuf> {
+    let date = date.split('-').collect::<Vec<&str>>();
     let yyyy = date[0];
     let mm = date[1];
     let dd = date[2];
-    let id_title = daily_json.id_title.as_deref().unwrap_or("diary");
-    let file_names = vec![
-        format!("{yyyy}/{mm}/{dd}.json"),
-        format!("{yyyy}/{mm}/{dd}/index.json"),
-        format!("{yyyy}/{mm}/{dd}/{id_title}.json"),
-        format!("{yyyy}/{mm}/{dd}/{id_title}/index.json"),
-    ];
-    for file_name in file_names {
-        let path = out_dir.join(file_name);
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        let file = File::create(&path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer(writer, daily_json)?;
-        if verbose {
-            println!("{}", path.display());
-        }
+    let id_title = id_title.unwrap_or("diary");
+    vec![
+        out_dir.join(format!("{yyyy}/{mm}/{dd}.json")),
+        out_dir.join(format!("{yyyy}/{mm}/{dd}/index.json")),
+        out_dir.join(format!("{yyyy}/{mm}/{dd}/{id_title}.json")),
+        out_dir.join(format!("{yyyy}/{mm}/{dd}/{id_title}/index.json")),
+    ]
+}
+
+fn write_daily_json(
+    out_dir: &Path,
+    daily_json: &DailyJson,
+    force: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut written = vec![];
+    for path in daily_json_paths(
+        out_dir,
+        daily_json.date.as_str(),
+        daily_json.id_title.as_deref(),
+    ) {
+        written.extend(write_json(path.as_path(), daily_json, force)?);
     }
-    Ok(())
+    Ok(written)
 }
 
 // <https://github.com/bouzuya/kraken/tree/v4.0.2/doc#related-json>
@@ -103,40 +105,31 @@ pub struct RelatedJson {
     pub same: Vec<String>,
 }
 
-fn write_related_json(
-    out_dir: &Path,
-    date: &str,
-    related_json: &RelatedJson,
-    verbose: bool,
-) -> anyhow::Result<()> {
+fn related_json_paths(out_dir: &Path, date: &str) -> Vec<PathBuf> {
     let parts = date.split('-').collect::<Vec<&str>>();
     let yyyy = parts[0];
     let mm = parts[1];
     let dd = parts[2];
-    let file_names = vec![
-        format!("{yyyy}/{mm}/{dd}/related.json"),
-        format!("{yyyy}/{mm}/{dd}/related/index.json"),
-    ];
-    for file_name in file_names {
-        let path = out_dir.join(file_name);
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        let file = File::create(&path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer(writer, related_json)?;
-        if verbose {
-            println!("{}", path.display());
-        }
-    }
-    Ok(())
+    vec![
+        out_dir.join(format!("{yyyy}/{mm}/{dd}/related.json")),
+        out_dir.join(format!("{yyyy}/{mm}/{dd}/related/index.json")),
+    ]
 }
 
-fn write_linked_json(
+fn write_related_json(
     out_dir: &Path,
-    inbounds: &BTreeMap<EntryKey, BTreeSet<EntryKey>>,
-    verbose: bool,
-) -> anyhow::Result<()> {
+    date: &str,
+    related_json: &RelatedJson,
+    force: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut written = vec![];
+    for path in related_json_paths(out_dir, date) {
+        written.extend(write_json(path.as_path(), related_json, force)?);
+    }
+    Ok(written)
+}
+
+fn linked_json(inbounds: &BTreeMap<EntryKey, BTreeSet<EntryKey>>) -> BTreeMap<String, Vec<String>> {
     let mut linked = BTreeMap::new();
     for (k, v) in inbounds.iter() {
         linked.insert(
@@ -144,111 +137,124 @@ fn write_linked_json(
             v.iter().map(|v_i| v_i.to_string()).collect::<Vec<String>>(),
         );
     }
-    let path = out_dir.join("linked.json");
-    let file = File::create(&path)?;
-    let writer = BufWriter::new(file);
-    serde_json::to_writer(writer, &linked)?;
-    if verbose {
-        println!("{}", path.display());
-    }
-    Ok(())
+    linked
 }
 
-fn write_tags_json(out_dir: &Path, tags_json: &TagsJson, verbose: bool) -> anyhow::Result<()> {
-    let path = out_dir.join("tags.json");
-    let file = File::create(&path)?;
-    let writer = BufWriter::new(file);
-    serde_json::to_writer(writer, tags_json)?;
-    if verbose {
-        println!("{}", path.display());
-    }
-    Ok(())
+/// ビルドで出力されるもの
+pub struct Output {
+    /// 書き込みを省略したものも含む、出力されるすべてのファイル
+    pub files: BTreeSet<PathBuf>,
 }
 
-fn markdown_to_html(markdown: &str) -> String {
-    let mut html_output = String::new();
-    html::push_html(&mut html_output, Parser::new(markdown));
-    html_output
+/// エントリーごとに独立して処理できる部分の結果
+struct EntryOutput {
+    all_json_item: AllJsonItem,
+    files: Vec<PathBuf>,
+    hash: String,
+    written: Vec<PathBuf>,
 }
 
-fn parse_links(markdown: &str) -> anyhow::Result<BTreeSet<EntryKey>> {
-    let mut links = BTreeSet::new();
-    let regex = Regex::new(r#"\[([0-9]{4}-[0-1][0-9]-[0-3][0-9])\]"#)?;
-    for captures in regex.captures_iter(markdown) {
-        let m = captures.get(1).context("no captures")?;
-        links.insert(date_range::date::Date::from_str(m.as_str())?);
-    }
-    Ok(links)
+fn all_json_item(entry: &SiteEntry) -> anyhow::Result<AllJsonItem> {
+    let meta = entry.meta();
+    Ok(AllJsonItem {
+        date: entry.date(),
+        minutes: u32::try_from(meta.minutes)?,
+        pubdate: meta.pubdate.to_string(),
+        tags: meta.tags.clone(),
+        title: meta.title.clone(),
+    })
 }
 
-pub fn run(data_dir: PathBuf, out_dir: PathBuf, verbose: bool) -> anyhow::Result<()> {
-    let bbn_repository = BbnRepository::new(data_dir);
-    let query = Query::try_from("date:1970-01-01/9999-12-31")?;
-    let mut entry_ids = bbn_repository.find_ids_by_query(query)?;
-    entry_ids.sort();
-
-    let mut all_json_items = vec![];
-    let mut tag_count_map = BTreeMap::new();
-    let mut inbounds = BTreeMap::new();
-    let mut outbounds = BTreeMap::new();
-    let mut same_days = BTreeMap::new();
-    for entry_id in entry_ids {
-        let meta = bbn_repository
-            .find_meta_by_id(&entry_id)?
-            .context("meta not found")?;
-        let content = bbn_repository
-            .find_content_by_id(&entry_id)?
-            .context("content not found")?;
-        let links = parse_links(&content)?;
-
-        for name in meta.tags.clone() {
-            *tag_count_map.entry(name).or_insert(0) += 1;
-        }
-
-        let all_json_item = AllJsonItem {
-            date: entry_id.date().to_string(),
-            minutes: u32::try_from(meta.minutes)?,
-            pubdate: meta.pubdate.to_string(),
-            tags: meta.tags.clone(),
-            title: meta.title.clone(),
-        };
-        all_json_items.push(all_json_item);
+// archive json (`/YYYY.json`, `/YYYY/MM.json`)
+// "YYYY" -> "/YYYY.json", "YYYY-MM" -> "/YYYY/MM.json"
+fn archive_json_path(out_dir: &Path, period: &str) -> PathBuf {
+    out_dir.join(format!("{}.json", period.replace('-', "/")))
+}
 
-        let html = markdown_to_html(&content);
+fn build_entry(
+    entry: &SiteEntry,
+    out_dir: &Path,
+    prev_manifest: &Manifest,
+    force: bool,
+) -> anyhow::Result<EntryOutput> {
+    let entry_id = entry.entry_id();
+    let meta = entry.meta();
+    let all_json_item = all_json_item(entry)?;
+
+    // meta と content が前回と同じなら Markdown の変換と書き込みを省略する
+    let hash = hash(&[
+        entry_id.to_string().as_bytes(),
+        serde_json::to_string(&all_json_item)?.as_bytes(),
+        entry.content().as_bytes(),
+    ]);
+    let files = daily_json_paths(out_dir, all_json_item.date.as_str(), entry_id.id_title());
+    let unchanged = !force
+        && prev_manifest.get(entry_id.to_string().as_str()) == Some(hash.as_str())
+        && files.iter().all(|path| path.is_file());
+
+    let written = if unchanged {
+        vec![]
+    } else {
         let daily_json = DailyJson {
-            data: content,
-            date: entry_id.date().to_string(),
-            html,
+            data: entry.content().to_string(),
+            date: entry.date(),
+            html: entry.html().to_string(),
             id_title: entry_id.id_title().map(|s| s.to_owned()),
             minutes: u32::try_from(meta.minutes)?,
             pubdate: meta.pubdate.to_string(),
-            tags: meta.tags,
-            title: meta.title,
+            tags: meta.tags.clone(),
+            title: meta.title.clone(),
         };
-        write_daily_json(out_dir.as_path(), &daily_json, verbose)?;
+        write_daily_json(out_dir, &daily_json, force)?
+    };
+    Ok(EntryOutput {
+        all_json_item,
+        files,
+        hash,
+        written,
+    })
+}
 
-        for link in links.iter().cloned() {
-            inbounds
-                .entry(link)
-                .or_insert_with(BTreeSet::new)
-                .insert(entry_id.date().to_owned());
+fn print_written(written: &[PathBuf], verbose: bool) {
+    if verbose {
+        for path in written {
+            println!("{}", path.display());
         }
+    }
+}
+
+pub fn run(
+    site: &Site,
+    out_dir: PathBuf,
+    force: bool,
+    jobs: NonZeroUsize,
+    verbose: bool,
+) -> anyhow::Result<Output> {
+    let mut files = BTreeSet::new();
+    let prev_manifest = Manifest::load(out_dir.as_path());
+    let mut manifest = Manifest::new();
 
-        outbounds.insert(entry_id.date().to_owned(), links);
+    // エントリーごとの処理は並列に行い、集計は entry_id の順に行う
+    let entry_outputs = parallel::map(jobs, site.entries(), |entry| {
+        build_entry(entry, out_dir.as_path(), &prev_manifest, force)
+    })?;
 
-        let mmdd = format!(
-            "--{}-{}",
-            entry_id.date().month(),
-            entry_id.date().day_of_month()
-        );
-        same_days
-            .entry(mmdd)
-            .or_insert_with(BTreeSet::new)
-            .insert(entry_id.date().to_owned());
+    let mut all_json_items = vec![];
+    for (entry, entry_output) in site.entries().iter().zip(entry_outputs) {
+        let EntryOutput {
+            all_json_item,
+            files: entry_files,
+            hash,
+            written,
+        } = entry_output;
+        print_written(&written, verbose);
+        files.extend(entry_files);
+        manifest.insert(entry.entry_id().to_string(), hash);
+        all_json_items.push(all_json_item);
     }
 
     let tags_json = TagsJson(
-        tag_count_map
+        site.tag_counts()
             .into_iter()
             .map(|(name, count)| TagsJsonItem { name, count })
             .collect::<Vec<_>>(),
@@ -257,85 +263,79 @@ pub fn run(data_dir: PathBuf, out_dir: PathBuf, verbose: bool) -> anyhow::Result
     let all_json = AllJson(all_json_items);
 
     // related.json の出力
-    let dates = all_json
-        .0
-        .iter()
-        .map(|item| item.date.clone())
-        .collect::<Vec<_>>();
-    for (i, item) in all_json.0.iter().enumerate() {
-        let date = &item.date;
-        let date_key =
-            date_range::date::Date::from_str(date).context("related.json: 日付のパースに失敗")?;
-
-        // inbound: この日付へのリンクを持つエントリ
-        let inbound = inbounds
-            .get(&date_key)
-            .map(|set| set.iter().map(|d| d.to_string()).collect::<Vec<_>>())
-            .unwrap_or_default();
-
-        // outbound: このエントリがリンクしているエントリ
-        let outbound = outbounds
-            .get(&date_key)
-            .map(|set| set.iter().map(|d| d.to_string()).collect::<Vec<_>>())
-            .unwrap_or_default();
-
-        // next: この後の最大4件（降順）
-        let next = dates[i + 1..]
-            .iter()
-            .take(4)
-            .rev()
-            .cloned()
-            .collect::<Vec<_>>();
-
-        // prev: この前の最大4件
-        let prev = dates[..i].iter().rev().take(4).cloned().collect::<Vec<_>>();
-
-        // same: 同じ月日のエントリ（自分自身を除く）
-        let mmdd = format!("--{}-{}", date_key.month(), date_key.day_of_month());
-        let same = same_days
-            .get(&mmdd)
-            .map(|set| {
-                set.iter()
-                    .filter(|d| *d != &date_key)
-                    .map(|d| d.to_string())
-                    .collect::<Vec<_>>()
-            })
-            .unwrap_or_default();
-
-        let related_json = RelatedJson {
+    let inbounds = site.inbounds();
+    let related = site.related();
+    let indices = (0..site.entries().len()).collect::<Vec<usize>>();
+    let related_written = parallel::map(jobs, &indices, |i| {
+        let to_strings = |dates: &[EntryKey]| {
+            dates
+                .iter()
+                .map(|date| date.to_string())
+                .collect::<Vec<String>>()
+        };
+        let Related {
             inbound,
             next,
             outbound,
             prev,
             same,
+        } = &related[*i];
+        let related_json = RelatedJson {
+            inbound: to_strings(inbound),
+            next: to_strings(next),
+            outbound: to_strings(outbound),
+            prev: to_strings(prev),
+            same: to_strings(same),
         };
-        write_related_json(out_dir.as_path(), date, &related_json, verbose)?;
+        write_related_json(
+            out_dir.as_path(),
+            site.entries()[*i].date().as_str(),
+            &related_json,
+            force,
+        )
+    })?;
+    for written in related_written {
+        print_written(&written, verbose);
+    }
+    for entry in site.entries() {
+        files.extend(related_json_paths(out_dir.as_path(), entry.date().as_str()));
     }
 
     fs::create_dir_all(out_dir.as_path())?;
-    write_all_json(out_dir.as_path(), &all_json, verbose)?;
-    write_tags_json(out_dir.as_path(), &tags_json, verbose)?;
-    write_linked_json(out_dir.as_path(), &inbounds, verbose)?;
-    Ok(())
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test() -> anyhow::Result<()> {
-        assert_eq!(
-            parse_links(
-                "[2021-02-03] [2021-02-04]\n\n[2021-02-03]: https://blog.bouzuya.net/2021/02/03/"
-            )?,
-            {
-                let mut set = BTreeSet::new();
-                set.insert(date_range::date::Date::from_str("2021-02-03")?);
-                set.insert(date_range::date::Date::from_str("2021-02-04")?);
-                set
-            }
+    let mut written = vec![];
+    for (period, entries) in site.years().into_iter().chain(site.months()) {
+        let archive_json = AllJson(
+            entries
+                .into_iter()
+                .map(all_json_item)
+                .collect::<anyhow::Result<Vec<AllJsonItem>>>()?,
         );
-        Ok(())
+        let path = archive_json_path(out_dir.as_path(), &period);
+        written.extend(write_json(path.as_path(), &archive_json, force)?);
+        files.insert(path);
     }
+    written.extend(write_json(
+        out_dir.join("posts.json").as_path(),
+        &all_json,
+        force,
+    )?);
+    written.extend(write_json(
+        out_dir.join("tags.json").as_path(),
+        &tags_json,
+        force,
+    )?);
+    written.extend(write_json(
+        out_dir.join("linked.json").as_path(),
+        &linked_json(&inbounds),
+        force,
+    )?);
+    print_written(&written, verbose);
+    manifest.save(out_dir.as_path())?;
+    files.extend(
+        ["posts.json", "tags.json", "linked.json"]
+            .into_iter()
+            .map(|file_name| out_dir.join(file_name)),
+    );
+    files.insert(Manifest::path(out_dir.as_path()));
+    Ok(Output { files })
 }
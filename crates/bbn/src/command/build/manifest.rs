@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+// out_dir/.bbn-build-manifest.json
+const MANIFEST_FILE_NAME: &str = ".bbn-build-manifest.json";
+
+/// 前回のビルドで出力したエントリーのハッシュ
+///
+/// bbn のバージョンが変わったときは出力形式が変わっているかもしれないので使わない。
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Manifest {
+    /// entry_id -> hash
+    entries: BTreeMap<String, String>,
+    version: String,
+}
+
+impl Manifest {
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// 存在しない・読めない・バージョンが異なる場合は空のマニフェストを返す
+    pub fn load(out_dir: &Path) -> Self {
+        fs::read_to_string(out_dir.join(MANIFEST_FILE_NAME))
+            .ok()
+            .and_then(|s| serde_json::from_str::<'_, Manifest>(s.as_str()).ok())
+            .filter(|manifest| manifest.version == env!("CARGO_PKG_VERSION"))
+            .unwrap_or_else(Self::new)
+    }
+
+    pub fn path(out_dir: &Path) -> PathBuf {
+        out_dir.join(MANIFEST_FILE_NAME)
+    }
+
+    pub fn save(&self, out_dir: &Path) -> anyhow::Result<()> {
+        write_if_changed(
+            Self::path(out_dir).as_path(),
+            serde_json::to_string(self)?.as_bytes(),
+            false,
+        )?;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, key: String, hash: String) {
+        self.entries.insert(key, hash);
+    }
+}
+
+/// 内容が同じファイルは書き込まない (mtime を変えない)。書き込んだ場合は `true` を返す
+pub fn write_if_changed(path: &Path, contents: &[u8], force: bool) -> anyhow::Result<bool> {
+    if !force && fs::read(path).is_ok_and(|current| current == contents) {
+        return Ok(false);
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn manifest_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let out_dir = temp_dir.path();
+        assert_eq!(Manifest::load(out_dir), Manifest::new());
+
+        let mut manifest = Manifest::new();
+        manifest.insert("2021-02-03".to_string(), "abc".to_string());
+        manifest.save(out_dir)?;
+        let loaded = Manifest::load(out_dir);
+        assert_eq!(loaded.get("2021-02-03"), Some("abc"));
+        assert_eq!(loaded.get("2021-02-04"), None);
+
+        fs::write(
+            out_dir.join(MANIFEST_FILE_NAME),
+            r#"{"entries":{"2021-02-03":"abc"},"version":"0.0.0"}"#,
+        )?;
+        assert_eq!(Manifest::load(out_dir), Manifest::new());
+        Ok(())
+    }
+
+    #[test]
+    fn write_if_changed_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("a").join("b.json");
+        assert!(write_if_changed(path.as_path(), b"1", false)?);
+        assert!(!write_if_changed(path.as_path(), b"1", false)?);
+        assert!(write_if_changed(path.as_path(), b"1", true)?);
+        assert!(write_if_changed(path.as_path(), b"2", false)?);
+        assert_eq!(fs::read_to_string(path)?, "2");
+        Ok(())
+    }
+}
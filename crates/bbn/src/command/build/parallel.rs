@@ -0,0 +1,97 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+/// `--jobs` が未指定のときは CPU 数を使う
+pub fn jobs(jobs: Option<NonZeroUsize>) -> NonZeroUsize {
+    jobs.or_else(|| thread::available_parallelism().ok())
+        .unwrap_or(NonZeroUsize::MIN)
+}
+
+/// `items` の各要素に `f` を `jobs` 並列で適用する
+///
+/// 結果は `items` の順に並ぶ。エラーがあれば `items` の順で最初のエラーを返す。
+pub fn map<T, U, F>(jobs: NonZeroUsize, items: &[T], f: F) -> anyhow::Result<Vec<U>>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> anyhow::Result<U> + Sync,
+{
+    let jobs = jobs.get().min(items.len());
+    if jobs <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(
+        (0..items.len())
+            .map(|_| None)
+            .collect::<Vec<Option<anyhow::Result<U>>>>(),
+    );
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    let result = f(item);
+                    let failed = result.is_err();
+                    results.lock().expect("poisoned")[index] = Some(result);
+                    if failed {
+                        // 残りの要素は処理しない
+                        next.fetch_max(items.len(), Ordering::Relaxed);
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    let mut outputs = Vec::with_capacity(items.len());
+    for result in results.into_inner().expect("poisoned") {
+        match result {
+            Some(result) => outputs.push(result?),
+            // エラーで中断したときだけ未処理の要素が残る
+            None => continue,
+        }
+    }
+    Ok(outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_test() -> anyhow::Result<()> {
+        let items = (0..100).collect::<Vec<usize>>();
+        for jobs in [1, 2, 8, 200] {
+            let jobs = NonZeroUsize::new(jobs).expect("non zero");
+            assert_eq!(
+                map(jobs, &items, |i| Ok(i * 2))?,
+                (0..100).map(|i| i * 2).collect::<Vec<usize>>()
+            );
+        }
+        assert_eq!(
+            map(jobs(None), &Vec::<usize>::new(), |i| Ok(*i))?,
+            Vec::<usize>::new()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn map_error_test() {
+        let items = (0..100).collect::<Vec<usize>>();
+        let jobs = NonZeroUsize::new(4).expect("non zero");
+        let result = map(jobs, &items, |i| {
+            if *i >= 10 {
+                anyhow::bail!("error {i}");
+            }
+            Ok(*i)
+        });
+        assert_eq!(result.map_err(|e| e.to_string()).unwrap_err(), "error 10");
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// `out_dir` 内の `files` 以外のビルドが出力する種類のファイルを削除し、空になったディレクトリも削除する
+///
+/// sitemap.xml や CNAME 、 .git/ などのビルド以外で置かれたファイルは残す。
+/// 削除した (`dry_run` のときは削除する) ファイルのパスを返す。
+pub fn prune(
+    out_dir: &Path,
+    files: &BTreeSet<PathBuf>,
+    dry_run: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut removed = vec![];
+    if out_dir.is_dir() {
+        prune_dir(out_dir, files, dry_run, &mut removed)?;
+    }
+    Ok(removed)
+}
+
+/// ディレクトリが空になった場合は `true` を返す
+fn prune_dir(
+    dir: &Path,
+    files: &BTreeSet<PathBuf>,
+    dry_run: bool,
+    removed: &mut Vec<PathBuf>,
+) -> anyhow::Result<bool> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|dir_entry| Ok(dir_entry?.path()))
+        .collect::<anyhow::Result<Vec<PathBuf>>>()?;
+    paths.sort();
+    let mut is_empty = true;
+    for path in paths {
+        let is_dotfile = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_none_or(|name| name.starts_with('.'));
+        if is_dotfile {
+            is_empty = false;
+            continue;
+        }
+        // シンボリックリンクはリンク先をたどらずにファイルとして扱う
+        if fs::symlink_metadata(path.as_path())?.is_dir() {
+            if prune_dir(path.as_path(), files, dry_run, removed)? {
+                if !dry_run {
+                    fs::remove_dir(path.as_path())?;
+                }
+            } else {
+                is_empty = false;
+            }
+        } else if files.contains(&path) || !is_build_output(path.as_path()) {
+            is_empty = false;
+        } else {
+            if !dry_run {
+                fs::remove_file(path.as_path())?;
+            }
+            removed.push(path);
+        }
+    }
+    Ok(is_empty)
+}
+
+/// ビルドが出力する種類のファイルか
+///
+/// テーマの public/ のファイルは種類を決められないので削除しない
+fn is_build_output(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == "atom.xml")
+        || path
+            .extension()
+            .is_some_and(|extension| extension == "html" || extension == "json")
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn prune_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let out_dir = temp_dir.path().join("out");
+        let keep = out_dir.join("2021").join("02").join("03.json");
+        let stale1 = out_dir.join("2021").join("02").join("03").join("old.json");
+        let stale2 = out_dir.join("2021").join("03").join("01.json");
+        for path in [&keep, &stale1, &stale2] {
+            fs::create_dir_all(path.parent().unwrap_or(out_dir.as_path()))?;
+            fs::write(path, "")?;
+        }
+        // ビルド以外で置かれたファイル
+        let others = [
+            out_dir.join(".git").join("config"),
+            out_dir.join(".nojekyll"),
+            out_dir.join("2021").join("02").join("notes.txt"),
+            out_dir.join("CNAME"),
+            out_dir.join("sitemap.xml"),
+            out_dir.join("styles").join("old.css"),
+        ];
+        for path in others.iter() {
+            fs::create_dir_all(path.parent().unwrap_or(out_dir.as_path()))?;
+            fs::write(path, "")?;
+        }
+        let files = BTreeSet::from([keep.clone()]);
+
+        assert_eq!(
+            prune(out_dir.as_path(), &files, true)?,
+            vec![stale1.clone(), stale2.clone()]
+        );
+        assert!(stale1.exists());
+        assert!(stale2.exists());
+
+        assert_eq!(
+            prune(out_dir.as_path(), &files, false)?,
+            vec![stale1.clone(), stale2.clone()]
+        );
+        assert!(keep.exists());
+        assert!(!out_dir.join("2021").join("02").join("03").exists());
+        assert!(!out_dir.join("2021").join("03").exists());
+        assert!(out_dir.exists());
+        assert!(others.iter().all(|path| path.exists()));
+
+        assert_eq!(
+            prune(temp_dir.path().join("not_found").as_path(), &files, false)?,
+            Vec::<PathBuf>::new()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,513 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::num::NonZeroUsize;
+use std::ops::Bound::Excluded;
+use std::ops::Bound::Unbounded;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use anyhow::Context;
+use bbn_data::EntryId;
+use bbn_data::EntryKey;
+use bbn_data::EntryMeta;
+use bbn_repository::BbnRepository;
+use bbn_repository::Query;
+use pulldown_cmark::Parser;
+use pulldown_cmark::html;
+use regex::Regex;
+
+use super::parallel;
+
+/// JSON と HTML の出力のもとになるサイト全体のデータ
+pub struct Site {
+    /// 日付の昇順
+    entries: Vec<SiteEntry>,
+}
+
+impl Site {
+    pub fn new(mut entries: Vec<SiteEntry>) -> Self {
+        entries.sort_by(|a, b| a.entry_id.cmp(&b.entry_id));
+        Self { entries }
+    }
+
+    /// リポジトリのすべてのエントリーを読み込む
+    pub fn load(bbn_repository: &BbnRepository, jobs: NonZeroUsize) -> anyhow::Result<Self> {
+        let query = Query::try_from("date:1970-01-01/9999-12-31")?;
+        let entry_ids = bbn_repository.find_ids_by_query(query)?;
+        let entries = parallel::map(jobs, &entry_ids, |entry_id| {
+            let meta = bbn_repository
+                .find_meta_by_id(entry_id)?
+                .context("meta not found")?;
+            let content = bbn_repository
+                .find_content_by_id(entry_id)?
+                .context("content not found")?;
+            SiteEntry::new(entry_id.clone(), meta, content)
+        })?;
+        Ok(Self::new(entries))
+    }
+
+    pub fn entries(&self) -> &[SiteEntry] {
+        &self.entries
+    }
+
+    /// 指定した日付のエントリー
+    pub fn find(&self, date: &EntryKey) -> Option<&SiteEntry> {
+        self.entries
+            .binary_search_by(|entry| entry.entry_id.date().cmp(date))
+            .ok()
+            .map(|index| &self.entries[index])
+    }
+
+    /// entries と同じ順の関連するエントリー
+    pub fn related(&self) -> Vec<Related> {
+        let inbounds = self.inbounds();
+        let same_days = self.same_days();
+        let dates = self
+            .entries
+            .iter()
+            .map(|entry| entry.entry_id.date().to_owned())
+            .collect::<Vec<EntryKey>>();
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let date = entry.entry_id.date();
+                let mmdd = format!("--{}-{}", date.month(), date.day_of_month());
+                Related {
+                    inbound: inbounds
+                        .get(date)
+                        .map(|set| set.iter().cloned().collect())
+                        .unwrap_or_default(),
+                    // この後の最大4件 (降順)
+                    next: dates[i + 1..].iter().take(4).rev().cloned().collect(),
+                    outbound: entry.links.iter().cloned().collect(),
+                    // この前の最大4件 (降順)
+                    prev: dates[..i].iter().rev().take(4).cloned().collect(),
+                    // 自分自身を除く
+                    same: same_days
+                        .get(&mmdd)
+                        .map(|set| set.iter().filter(|d| *d != date).cloned().collect())
+                        .unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+
+    /// リンク先 -> リンク元
+    pub fn inbounds(&self) -> BTreeMap<EntryKey, BTreeSet<EntryKey>> {
+        let mut inbounds = BTreeMap::new();
+        for entry in self.entries.iter() {
+            for link in entry.links.iter().cloned() {
+                inbounds
+                    .entry(link)
+                    .or_insert_with(BTreeSet::new)
+                    .insert(entry.entry_id.date().to_owned());
+            }
+        }
+        inbounds
+    }
+
+    /// "--MM-DD" -> 同じ月日のエントリー
+    pub fn same_days(&self) -> BTreeMap<String, BTreeSet<EntryKey>> {
+        let mut same_days = BTreeMap::new();
+        for entry in self.entries.iter() {
+            let date = entry.entry_id.date();
+            let mmdd = format!("--{}-{}", date.month(), date.day_of_month());
+            same_days
+                .entry(mmdd)
+                .or_insert_with(BTreeSet::new)
+                .insert(date.to_owned());
+        }
+        same_days
+    }
+
+    /// "YYYY" -> その年のエントリー (日付の昇順)
+    pub fn years(&self) -> BTreeMap<String, Vec<&SiteEntry>> {
+        self.group_by_date_prefix("YYYY".len())
+    }
+
+    /// "YYYY-MM" -> その月のエントリー (日付の昇順)
+    pub fn months(&self) -> BTreeMap<String, Vec<&SiteEntry>> {
+        self.group_by_date_prefix("YYYY-MM".len())
+    }
+
+    fn group_by_date_prefix(&self, len: usize) -> BTreeMap<String, Vec<&SiteEntry>> {
+        let mut groups = BTreeMap::new();
+        for entry in self.entries.iter() {
+            groups
+                .entry(entry.date()[..len].to_string())
+                .or_insert_with(Vec::new)
+                .push(entry);
+        }
+        groups
+    }
+
+    /// `prev` から変更されたエントリーを表示するページ
+    ///
+    /// 追加・削除されたエントリーの前後のページも含めるため `prev` と `self` の両方で探す
+    pub fn affected(&self, prev: &Site) -> Affected {
+        let mut changed = BTreeSet::new();
+        for entry in prev.entries.iter().chain(self.entries.iter()) {
+            let date = entry.entry_id.date();
+            let same = match (prev.find(date), self.find(date)) {
+                (Some(a), Some(b)) => {
+                    a.entry_id == b.entry_id && a.meta == b.meta && a.content == b.content
+                }
+                _ => false,
+            };
+            if !same {
+                changed.insert(date.to_owned());
+            }
+        }
+
+        let mut affected = Affected::default();
+        for site in [prev, self] {
+            let inbounds = site.inbounds();
+            let same_days = site.same_days();
+            let years = site.years();
+            let months = site.months();
+            for date in changed.iter() {
+                let s = date.to_string();
+                for (periods, period) in [(&years, &s[..4]), (&months, &s[..7])] {
+                    affected.periods.insert(period.to_string());
+                    // 前後の期間へのナビゲーションが変わる
+                    affected.periods.extend(
+                        periods
+                            .range::<str, _>((Unbounded, Excluded(period)))
+                            .next_back()
+                            .into_iter()
+                            .chain(
+                                periods
+                                    .range::<str, _>((Excluded(period), Unbounded))
+                                    .next(),
+                            )
+                            .map(|(period, _)| period.clone()),
+                    );
+                }
+                let mmdd = format!("--{}-{}", date.month(), date.day_of_month());
+                affected
+                    .entries
+                    .extend(same_days.get(&mmdd).into_iter().flatten().cloned());
+                affected
+                    .entries
+                    .extend(inbounds.get(date).into_iter().flatten().cloned());
+                // related の prev と next (最大 4 件) とナビゲーションの前後
+                let index = site
+                    .entries
+                    .partition_point(|entry| entry.entry_id.date() < date);
+                affected.entries.extend(
+                    site.entries[index.saturating_sub(4)..(index + 5).min(site.entries.len())]
+                        .iter()
+                        .map(|entry| entry.entry_id.date().to_owned()),
+                );
+                if let Some(entry) = site.find(date) {
+                    affected.entries.extend(entry.links.iter().cloned());
+                    affected.tags.extend(entry.meta.tags.iter().cloned());
+                }
+            }
+        }
+        affected.entries.extend(changed);
+        affected
+    }
+
+    /// タグ名 -> エントリー数
+    pub fn tag_counts(&self) -> BTreeMap<String, u32> {
+        let mut tag_counts = BTreeMap::new();
+        for entry in self.entries.iter() {
+            for name in entry.meta.tags.iter().cloned() {
+                *tag_counts.entry(name).or_insert(0) += 1;
+            }
+        }
+        tag_counts
+    }
+}
+
+/// エントリーに関連するエントリーの日付
+// <https://github.com/bouzuya/kraken/tree/v4.0.2/doc#related-json>
+#[derive(Debug, Eq, PartialEq)]
+pub struct Related {
+    /// このエントリーへのリンクを持つエントリー
+    pub inbound: Vec<EntryKey>,
+    pub next: Vec<EntryKey>,
+    /// このエントリーがリンクしているエントリー
+    pub outbound: Vec<EntryKey>,
+    pub prev: Vec<EntryKey>,
+    /// ほかの年の同じ月日のエントリー
+    pub same: Vec<EntryKey>,
+}
+
+/// 出力し直すページ
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Affected {
+    /// entry-detail と related のページを出力し直すエントリー
+    pub entries: BTreeSet<EntryKey>,
+    /// 出力し直す "YYYY" と "YYYY-MM" のページ
+    pub periods: BTreeSet<String>,
+    /// 出力し直すタグのページ
+    pub tags: BTreeSet<String>,
+}
+
+pub struct SiteEntry {
+    content: String,
+    entry_id: EntryId,
+    html: OnceLock<String>,
+    /// content 内の `[YYYY-MM-DD]` のリンク先
+    links: BTreeSet<EntryKey>,
+    meta: EntryMeta,
+}
+
+impl SiteEntry {
+    pub fn new(entry_id: EntryId, meta: EntryMeta, content: String) -> anyhow::Result<Self> {
+        let links = parse_links(&content)?;
+        Ok(Self {
+            content,
+            entry_id,
+            html: OnceLock::new(),
+            links,
+            meta,
+        })
+    }
+
+    /// Markdown
+    pub fn content(&self) -> &str {
+        self.content.as_str()
+    }
+
+    /// "YYYY-MM-DD"
+    pub fn date(&self) -> String {
+        self.entry_id.date().to_string()
+    }
+
+    pub fn entry_id(&self) -> &EntryId {
+        &self.entry_id
+    }
+
+    /// content を HTML に変換したもの (必要になったときに変換する)
+    pub fn html(&self) -> &str {
+        self.html.get_or_init(|| markdown_to_html(&self.content))
+    }
+
+    /// id_title がない場合は "diary"
+    pub fn id_title(&self) -> &str {
+        self.entry_id.id_title().unwrap_or("diary")
+    }
+
+    pub fn meta(&self) -> &EntryMeta {
+        &self.meta
+    }
+
+    /// "/YYYY/MM/DD/"
+    pub fn path(&self) -> String {
+        format!("/{}/", self.date().replace('-', "/"))
+    }
+}
+
+fn markdown_to_html(markdown: &str) -> String {
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, Parser::new(markdown));
+    html_output
+}
+
+fn parse_links(markdown: &str) -> anyhow::Result<BTreeSet<EntryKey>> {
+    let mut links = BTreeSet::new();
+    let regex = Regex::new(r#"\[([0-9]{4}-[0-1][0-9]-[0-3][0-9])\]"#)?;
+    for captures in regex.captures_iter(markdown) {
+        let m = captures.get(1).context("no captures")?;
+        links.insert(date_range::date::Date::from_str(m.as_str())?);
+    }
+    Ok(links)
+}
+
+#[cfg(test)]
+mod tests {
+    use bbn_data::DateTime;
+
+    use super::*;
+
+    fn make_entry(entry_id: &str, tags: &[&str], content: &str) -> anyhow::Result<SiteEntry> {
+        let entry_id = EntryId::from_str(entry_id)?;
+        let pubdate = DateTime::from_str(&format!("{}T00:00:00+09:00", entry_id.date()))?;
+        SiteEntry::new(
+            entry_id,
+            EntryMeta::new(
+                5,
+                pubdate,
+                tags.iter().map(|s| s.to_string()).collect(),
+                "TITLE".to_string(),
+            ),
+            content.to_string(),
+        )
+    }
+
+    #[test]
+    fn test() -> anyhow::Result<()> {
+        assert_eq!(
+            parse_links(
+                "[2021-02-03] [2021-02-04]\n\n[2021-02-03]: https://blog.bouzuya.net/2021/02/03/"
+            )?,
+            {
+                let mut set = BTreeSet::new();
+                set.insert(date_range::date::Date::from_str("2021-02-03")?);
+                set.insert(date_range::date::Date::from_str("2021-02-04")?);
+                set
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn site_entry_test() -> anyhow::Result<()> {
+        let entry = make_entry("2021-02-03", &[], "hello [2021-02-01]")?;
+        assert_eq!(entry.date(), "2021-02-03");
+        assert_eq!(entry.id_title(), "diary");
+        assert_eq!(entry.path(), "/2021/02/03/");
+        assert_eq!(entry.html(), "<p>hello [2021-02-01]</p>\n");
+        assert_eq!(entry.links.len(), 1);
+        assert_eq!(make_entry("2021-02-03-id", &[], "")?.id_title(), "id");
+        Ok(())
+    }
+
+    #[test]
+    fn site_test() -> anyhow::Result<()> {
+        let site = Site::new(vec![
+            make_entry("2022-02-03", &["a"], "")?,
+            make_entry("2021-02-04", &["a", "b"], "[2021-02-03]")?,
+            make_entry("2021-02-03", &[], "[2021-02-04]")?,
+        ]);
+        assert_eq!(
+            site.entries()
+                .iter()
+                .map(SiteEntry::date)
+                .collect::<Vec<String>>(),
+            vec!["2021-02-03", "2021-02-04", "2022-02-03"]
+        );
+        let date = |s: &str| date_range::date::Date::from_str(s);
+        assert_eq!(
+            site.inbounds(),
+            BTreeMap::from([
+                (date("2021-02-03")?, BTreeSet::from([date("2021-02-04")?])),
+                (date("2021-02-04")?, BTreeSet::from([date("2021-02-03")?])),
+            ])
+        );
+        assert_eq!(
+            site.same_days().get("--02-03"),
+            Some(&BTreeSet::from([date("2021-02-03")?, date("2022-02-03")?]))
+        );
+        let dates = |entries: &Vec<&SiteEntry>| {
+            entries
+                .iter()
+                .map(|entry| entry.date())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            site.years()
+                .iter()
+                .map(|(k, v)| (k.as_str(), dates(v)))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "2021",
+                    vec!["2021-02-03".to_string(), "2021-02-04".to_string()]
+                ),
+                ("2022", vec!["2022-02-03".to_string()]),
+            ]
+        );
+        assert_eq!(
+            site.months().keys().collect::<Vec<&String>>(),
+            vec!["2021-02", "2022-02"]
+        );
+        assert_eq!(
+            site.find(&date("2021-02-04")?).map(SiteEntry::date),
+            Some("2021-02-04".to_string())
+        );
+        assert!(site.find(&date("2021-02-05")?).is_none());
+        assert_eq!(
+            site.related(),
+            vec![
+                Related {
+                    inbound: vec![date("2021-02-04")?],
+                    next: vec![date("2022-02-03")?, date("2021-02-04")?],
+                    outbound: vec![date("2021-02-04")?],
+                    prev: vec![],
+                    same: vec![date("2022-02-03")?],
+                },
+                Related {
+                    inbound: vec![date("2021-02-03")?],
+                    next: vec![date("2022-02-03")?],
+                    outbound: vec![date("2021-02-03")?],
+                    prev: vec![date("2021-02-03")?],
+                    same: vec![],
+                },
+                Related {
+                    inbound: vec![],
+                    next: vec![],
+                    outbound: vec![],
+                    prev: vec![date("2021-02-04")?, date("2021-02-03")?],
+                    same: vec![date("2021-02-03")?],
+                },
+            ]
+        );
+        assert_eq!(
+            site.tag_counts(),
+            BTreeMap::from([("a".to_string(), 2), ("b".to_string(), 1)])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn affected_test() -> anyhow::Result<()> {
+        let date = |s: &str| date_range::date::Date::from_str(s);
+        let entries = || -> anyhow::Result<Vec<SiteEntry>> {
+            Ok(vec![
+                make_entry("2020-01-01", &[], "")?,
+                make_entry("2021-01-01", &[], "")?,
+                make_entry("2021-01-02", &[], "")?,
+                make_entry("2021-01-03", &[], "")?,
+                make_entry("2021-01-04", &[], "")?,
+                make_entry("2021-01-05", &[], "")?,
+                make_entry("2021-02-01", &["a"], "[2021-03-01]")?,
+                make_entry("2021-03-01", &["b"], "")?,
+                make_entry("2022-02-01", &[], "")?,
+            ])
+        };
+        let prev = Site::new(entries()?);
+        assert_eq!(Site::new(entries()?).affected(&prev), Affected::default());
+
+        let mut changed = entries()?;
+        changed[6] = make_entry("2021-02-01", &["c"], "")?;
+        assert_eq!(
+            Site::new(changed).affected(&prev),
+            Affected {
+                entries: BTreeSet::from([
+                    date("2021-01-02")?,
+                    date("2021-01-03")?,
+                    date("2021-01-04")?,
+                    date("2021-01-05")?,
+                    date("2021-02-01")?,
+                    date("2021-03-01")?,
+                    date("2022-02-01")?,
+                ]),
+                periods: BTreeSet::from([
+                    "2020".to_string(),
+                    "2021".to_string(),
+                    "2021-01".to_string(),
+                    "2021-02".to_string(),
+                    "2021-03".to_string(),
+                    "2022".to_string(),
+                ]),
+                tags: BTreeSet::from(["a".to_string(), "c".to_string()]),
+            }
+        );
+
+        // 削除されたエントリーの前後と、それにリンクしていたエントリー
+        let mut removed = entries()?;
+        removed.remove(7);
+        let affected = Site::new(removed).affected(&prev);
+        assert!(affected.entries.contains(&date("2021-02-01")?));
+        assert!(affected.entries.contains(&date("2021-03-01")?));
+        assert!(affected.entries.contains(&date("2022-02-01")?));
+        assert!(!affected.entries.contains(&date("2020-01-01")?));
+        assert_eq!(affected.tags, BTreeSet::from(["b".to_string()]));
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use askama::filters::Escaper;
+use minijinja::AutoEscape;
+use minijinja::Environment;
+use minijinja::ErrorKind;
+
+/// テーマディレクトリ
+///
+/// - `templates/` 内のテンプレートは組み込みのテンプレートを置き換える (minijinja で描画する)
+///   - 組み込みのテンプレート (crates/bbn/templates/) は askama と minijinja の両方で描画できる構文で書く
+///     (`{% if let %}` や `.is_empty()` などのメソッド呼び出しは使わない)。
+///     コピーしてテーマのテンプレートを作れる
+///   - ない値は "" にする (e.g. `{% if prev_path != "" %}`)
+/// - `public/` 内のファイルは組み込みの public ディレクトリのファイルを置き換える・追加する
+///
+/// テーマディレクトリにないものは組み込みのものを使う。
+/// `Theme::default()` は組み込みのものだけを使う。
+#[derive(Default)]
+pub struct Theme {
+    env: Option<Environment<'static>>,
+    public_dir: Option<PathBuf>,
+}
+
+impl Theme {
+    pub fn new(theme_dir: Option<&Path>) -> anyhow::Result<Self> {
+        let Some(theme_dir) = theme_dir else {
+            return Ok(Self::default());
+        };
+        if !theme_dir.is_dir() {
+            anyhow::bail!("theme_dir is not a directory: {}", theme_dir.display());
+        }
+        let mut env = Environment::new();
+        env.set_loader(minijinja::path_loader(theme_dir.join("templates")));
+        // 組み込みのテンプレートと同じ出力にするため askama と同じ方法でエスケープする
+        env.set_formatter(|out, state, value| match value.as_str() {
+            Some(s) if state.auto_escape() == AutoEscape::Html && !value.is_safe() => {
+                askama::filters::Html
+                    .write_escaped_str(out, s)
+                    .map_err(|_| minijinja::Error::from(ErrorKind::WriteFailure))
+            }
+            _ => minijinja::escape_formatter(out, state, value),
+        });
+        Ok(Self {
+            env: Some(env),
+            public_dir: Some(theme_dir.join("public")),
+        })
+    }
+
+    /// `name` (e.g. "page.html") のテンプレートを描画する
+    ///
+    /// テーマディレクトリに同じ名前のテンプレートがあればそちらを `template` の値で描画する
+    pub fn render<T>(&self, name: &str, template: &T) -> anyhow::Result<String>
+    where
+        T: askama::Template + serde::Serialize,
+    {
+        if let Some(env) = self.env.as_ref() {
+            match env.get_template(name) {
+                Ok(t) => {
+                    return t
+                        .render(template)
+                        .with_context(|| format!("theme template error: {name}"));
+                }
+                Err(e) if e.kind() == ErrorKind::TemplateNotFound => {}
+                Err(e) => {
+                    return Err(e).with_context(|| format!("theme template error: {name}"));
+                }
+            }
+        }
+        Ok(template.render()?)
+    }
+
+    /// public ディレクトリからの相対パス -> テーマディレクトリ内のファイルのパス
+    pub fn public_files(&self) -> anyhow::Result<BTreeMap<PathBuf, PathBuf>> {
+        let mut files = BTreeMap::new();
+        let Some(public_dir) = self.public_dir.as_ref() else {
+            return Ok(files);
+        };
+        if !public_dir.is_dir() {
+            return Ok(files);
+        }
+        let mut dirs = vec![public_dir.clone()];
+        while let Some(dir) = dirs.pop() {
+            for dir_entry in fs::read_dir(dir.as_path())? {
+                let path = dir_entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else {
+                    files.insert(path.strip_prefix(public_dir)?.to_path_buf(), path);
+                }
+            }
+        }
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use askama::Template;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[derive(Template, serde::Serialize)]
+    #[template(source = "<p>{{ name }}</p>", ext = "html")]
+    struct Hello {
+        name: String,
+    }
+
+    #[test]
+    fn render_test() -> anyhow::Result<()> {
+        let hello = Hello {
+            name: "<bbn>".to_string(),
+        };
+        assert_eq!(
+            Theme::default().render("hello.html", &hello)?,
+            "<p>&#60;bbn&#62;</p>"
+        );
+
+        let temp_dir = tempdir()?;
+        let theme_dir = temp_dir.path().join("theme");
+        fs::create_dir_all(theme_dir.join("templates"))?;
+        let theme = Theme::new(Some(theme_dir.as_path()))?;
+        // テーマにないテンプレートは組み込みのものを使う
+        assert_eq!(theme.render("hello.html", &hello)?, "<p>&#60;bbn&#62;</p>");
+
+        fs::write(
+            theme_dir.join("templates").join("hello.html"),
+            "<div>{{ name }}</div>",
+        )?;
+        let theme = Theme::new(Some(theme_dir.as_path()))?;
+        assert_eq!(
+            theme.render("hello.html", &hello)?,
+            "<div>&#60;bbn&#62;</div>"
+        );
+
+        fs::write(
+            theme_dir.join("templates").join("hello.html"),
+            "<div>{{ name </div>",
+        )?;
+        let theme = Theme::new(Some(theme_dir.as_path()))?;
+        assert!(theme.render("hello.html", &hello).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn public_files_test() -> anyhow::Result<()> {
+        assert!(Theme::default().public_files()?.is_empty());
+
+        let temp_dir = tempdir()?;
+        let theme_dir = temp_dir.path().join("theme");
+        fs::create_dir_all(theme_dir.as_path())?;
+        assert!(
+            Theme::new(Some(theme_dir.as_path()))?
+                .public_files()?
+                .is_empty()
+        );
+
+        let styles_dir = theme_dir.join("public").join("styles");
+        fs::create_dir_all(styles_dir.as_path())?;
+        fs::write(styles_dir.join("index.css"), "")?;
+        fs::write(theme_dir.join("public").join("robots.txt"), "")?;
+        assert_eq!(
+            Theme::new(Some(theme_dir.as_path()))?.public_files()?,
+            BTreeMap::from([
+                (
+                    PathBuf::from("robots.txt"),
+                    theme_dir.join("public").join("robots.txt")
+                ),
+                (
+                    PathBuf::from("styles/index.css"),
+                    styles_dir.join("index.css")
+                ),
+            ])
+        );
+
+        assert!(Theme::new(Some(temp_dir.path().join("not_found").as_path())).is_err());
+        Ok(())
+    }
+}
@@ -26,15 +26,27 @@ enum Subcommand {
 
 #[derive(Debug, Clone, clap::ValueEnum)]
 pub enum Key {
+    BaseUrl,
     DataDir,
+    HatenaBlogCategories,
     HatenaBlogDataFile,
+    Lang,
     LinkCompletionRulesFile,
     OutDir,
+    PageSize,
+    SiteTitle,
+    ThemeDir,
 }
 
 #[derive(Debug, Clone, clap::ValueEnum)]
 pub enum OptionalKey {
+    BaseUrl,
+    HatenaBlogCategories,
+    Lang,
     LinkCompletionRulesFile,
+    PageSize,
+    SiteTitle,
+    ThemeDir,
 }
 
 impl Command {
@@ -17,16 +17,19 @@ impl Command {
             .load()
             .context("The configuration file does not found. Use `bbn config init` command.")?;
         let value = match self.key {
+            Key::BaseUrl => config.base_url().unwrap_or("").to_string(),
             Key::DataDir => config
                 .data_dir()
                 .to_str()
                 .context("data-dir is not UTF-8")?
                 .to_string(),
+            Key::HatenaBlogCategories => serde_json::to_string(config.hatena_blog_categories())?,
             Key::HatenaBlogDataFile => config
                 .hatena_blog_data_file()
                 .to_str()
                 .context("hatena-blog-data-file is not UTF-8")?
                 .to_string(),
+            Key::Lang => config.lang().unwrap_or("").to_string(),
             Key::LinkCompletionRulesFile => config
                 .link_completion_rules_file()
                 .map(|p| {
@@ -42,6 +45,17 @@ impl Command {
                 .transpose()?
                 .unwrap_or("")
                 .to_string(),
+            Key::PageSize => config
+                .page_size()
+                .map(|n| n.to_string())
+                .unwrap_or_default(),
+            Key::SiteTitle => config.site_title().unwrap_or("").to_string(),
+            Key::ThemeDir => config
+                .theme_dir()
+                .map(|p| p.to_str().context("theme-dir is not UTF-8"))
+                .transpose()?
+                .unwrap_or("")
+                .to_string(),
         };
         println!("{value}");
         Ok(())
@@ -1,3 +1,4 @@
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 
 use anyhow::Context;
@@ -7,6 +8,8 @@ use crate::config_repository::ConfigRepository;
 
 #[derive(Debug, clap::Args)]
 pub struct Command {
+    #[arg(long = "base-url", name = "BASE_URL", help = "the base url")]
+    pub base_url: Option<String>,
     #[arg(long = "data-dir", name = "DATA_DIR", help = "the data dir")]
     pub data_dir: PathBuf,
     #[arg(
@@ -15,6 +18,8 @@ pub struct Command {
         help = "the hatena-blog data file"
     )]
     pub hatena_blog_data_file: PathBuf,
+    #[arg(long = "lang", name = "LANG", help = "the language of the site")]
+    pub lang: Option<String>,
     #[arg(
         long = "link-completion-rules-file",
         name = "LINK_COMPLETION_RULES_FILE",
@@ -23,6 +28,16 @@ pub struct Command {
     pub link_completion_rules_file: Option<PathBuf>,
     #[arg(long = "out-dir", name = "OUT_DIR", help = "the out dir")]
     pub out_dir: Option<PathBuf>,
+    #[arg(
+        long = "page-size",
+        name = "PAGE_SIZE",
+        help = "the number of entries per list page"
+    )]
+    pub page_size: Option<NonZeroUsize>,
+    #[arg(long = "site-title", name = "SITE_TITLE", help = "the site title")]
+    pub site_title: Option<String>,
+    #[arg(long = "theme-dir", name = "THEME_DIR", help = "the theme dir")]
+    pub theme_dir: Option<PathBuf>,
 }
 
 impl Command {
@@ -33,7 +48,12 @@ impl Command {
             self.hatena_blog_data_file,
             self.link_completion_rules_file,
             self.out_dir,
-        );
+        )
+        .with_base_url(self.base_url)
+        .with_lang(self.lang)
+        .with_page_size(self.page_size)
+        .with_site_title(self.site_title)
+        .with_theme_dir(self.theme_dir);
         config_repository.save(config)?;
         println!(
             "The configuration has been written to {}",
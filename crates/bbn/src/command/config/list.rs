@@ -11,6 +11,9 @@ impl Command {
         let config = config_repository
             .load()
             .context("The configuration file does not found. Use `bbn config init` command.")?;
+        if let Some(s) = config.base_url() {
+            println!("base-url={s}");
+        }
         println!(
             "data-dir={}",
             config
@@ -18,6 +21,12 @@ impl Command {
                 .to_str()
                 .context("data-dir is not UTF-8")?
         );
+        if !config.hatena_blog_categories().is_empty() {
+            println!(
+                "hatena-blog-categories={}",
+                serde_json::to_string(config.hatena_blog_categories())?
+            );
+        }
         println!(
             "hatena-blog-data-file={}",
             config
@@ -25,6 +34,9 @@ impl Command {
                 .to_str()
                 .context("hatena-blog-data-file is not UTF-8")?
         );
+        if let Some(s) = config.lang() {
+            println!("lang={s}");
+        }
         if let Some(p) = config.link_completion_rules_file() {
             println!(
                 "link-completion-rules-file={}",
@@ -35,6 +47,18 @@ impl Command {
         if let Some(p) = config.out_dir() {
             println!("out-dir={}", p.to_str().context("out-dir is not UTF-8")?);
         }
+        if let Some(n) = config.page_size() {
+            println!("page-size={n}");
+        }
+        if let Some(s) = config.site_title() {
+            println!("site-title={s}");
+        }
+        if let Some(p) = config.theme_dir() {
+            println!(
+                "theme-dir={}",
+                p.to_str().context("theme-dir is not UTF-8")?
+            );
+        }
         Ok(())
     }
 }
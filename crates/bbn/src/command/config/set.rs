@@ -2,7 +2,6 @@ use std::path::PathBuf;
 
 use anyhow::Context;
 
-use crate::config::Config;
 use crate::config_repository::ConfigRepository;
 
 use super::Key;
@@ -22,30 +21,27 @@ impl Command {
             .load()
             .context("The configuration file does not found. Use `bbn config init` command.")?;
         let config = match self.key {
-            Key::DataDir => Config::new(
-                PathBuf::from(&self.value),
-                config.hatena_blog_data_file().to_path_buf(),
-                config.link_completion_rules_file().map(|p| p.to_path_buf()),
-                config.out_dir().map(|p| p.to_path_buf()),
-            ),
-            Key::HatenaBlogDataFile => Config::new(
-                config.data_dir().to_path_buf(),
-                PathBuf::from(&self.value),
-                config.link_completion_rules_file().map(|p| p.to_path_buf()),
-                config.out_dir().map(|p| p.to_path_buf()),
-            ),
-            Key::LinkCompletionRulesFile => Config::new(
-                config.data_dir().to_path_buf(),
-                config.hatena_blog_data_file().to_path_buf(),
-                Some(PathBuf::from(&self.value)),
-                config.out_dir().map(|p| p.to_path_buf()),
-            ),
-            Key::OutDir => Config::new(
-                config.data_dir().to_path_buf(),
-                config.hatena_blog_data_file().to_path_buf(),
-                config.link_completion_rules_file().map(|p| p.to_path_buf()),
-                Some(PathBuf::from(&self.value)),
+            Key::BaseUrl => config.with_base_url(Some(self.value)),
+            Key::DataDir => config.with_data_dir(PathBuf::from(&self.value)),
+            Key::HatenaBlogCategories => config.with_hatena_blog_categories(
+                serde_json::from_str(&self.value)
+                    .context("hatena-blog-categories is not a JSON object (tag -> category)")?,
             ),
+            Key::HatenaBlogDataFile => {
+                config.with_hatena_blog_data_file(PathBuf::from(&self.value))
+            }
+            Key::Lang => config.with_lang(Some(self.value)),
+            Key::LinkCompletionRulesFile => {
+                config.with_link_completion_rules_file(Some(PathBuf::from(&self.value)))
+            }
+            Key::OutDir => config.with_out_dir(Some(PathBuf::from(&self.value))),
+            Key::PageSize => config.with_page_size(Some(
+                self.value
+                    .parse()
+                    .context("page-size is not a positive integer")?,
+            )),
+            Key::SiteTitle => config.with_site_title(Some(self.value)),
+            Key::ThemeDir => config.with_theme_dir(Some(PathBuf::from(&self.value))),
         };
         config_repository.save(config)?;
         Ok(())
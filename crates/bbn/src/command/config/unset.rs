@@ -1,6 +1,5 @@
 use anyhow::Context;
 
-use crate::config::Config;
 use crate::config_repository::ConfigRepository;
 
 use super::OptionalKey;
@@ -18,12 +17,15 @@ impl Command {
             .load()
             .context("The configuration file does not found. Use `bbn config init` command.")?;
         let config = match self.key {
-            OptionalKey::LinkCompletionRulesFile => Config::new(
-                config.data_dir().to_path_buf(),
-                config.hatena_blog_data_file().to_path_buf(),
-                None,
-                config.out_dir().map(|p| p.to_path_buf()),
-            ),
+            OptionalKey::BaseUrl => config.with_base_url(None),
+            OptionalKey::HatenaBlogCategories => {
+                config.with_hatena_blog_categories(Default::default())
+            }
+            OptionalKey::Lang => config.with_lang(None),
+            OptionalKey::LinkCompletionRulesFile => config.with_link_completion_rules_file(None),
+            OptionalKey::PageSize => config.with_page_size(None),
+            OptionalKey::SiteTitle => config.with_site_title(None),
+            OptionalKey::ThemeDir => config.with_theme_dir(None),
         };
         config_repository.save(config)?;
         Ok(())
@@ -0,0 +1,270 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Context;
+use bbn_data::DateTime;
+use bbn_data::EntryMeta;
+use bbn_data::Timestamp;
+use bbn_repository::BbnRepository;
+use date_range::date::Date;
+
+use crate::config_repository::ConfigRepository;
+use crate::date_like::DateLike;
+
+#[derive(Debug, clap::Args)]
+pub struct Command {
+    #[arg(
+        long = "add-minutes",
+        help = "Adds the minutes spent in the editor to `minutes`"
+    )]
+    pub add_minutes: bool,
+    #[arg(long = "bump-pubdate", help = "Sets `pubdate` to the current time")]
+    pub bump_pubdate: bool,
+    #[arg(name = "DATE_LIKE", help = "the date. e.g. 2021-02-03 or 2021-W05-3")]
+    pub date_like: DateLike,
+}
+
+impl Command {
+    pub fn handle(self) -> anyhow::Result<()> {
+        edit(self.date_like, self.add_minutes, self.bump_pubdate)
+    }
+}
+
+const FRONT_MATTER_DELIMITER: &str = "---\n";
+
+fn edit(date_like: DateLike, add_minutes: bool, bump_pubdate: bool) -> anyhow::Result<()> {
+    let date = Date::from(date_like);
+    let config_repository = ConfigRepository::new()?;
+    let config = config_repository
+        .load()
+        .context("The configuration file does not found. Use `bbn config` command.")?;
+    let data_dir = config.data_dir().to_path_buf();
+
+    let repository = BbnRepository::new(data_dir);
+    let entry_id = repository.find_id_by_date(date)?.context("not found")?;
+    let entry = repository
+        .find_entry_by_id(&entry_id)?
+        .context("not found")?;
+
+    // meta と content をひとつのファイルにまとめてエディタで開く
+    let mut temp_file = tempfile::Builder::new()
+        .prefix("bbn-edit-")
+        .suffix(".md")
+        .tempfile()?;
+    temp_file.write_all(to_front_matter(entry.meta(), entry.content()).as_bytes())?;
+    temp_file.flush()?;
+    let started_at = Instant::now();
+    run_editor(temp_file.path())?;
+    let elapsed = started_at.elapsed();
+
+    let edited = fs::read_to_string(temp_file.path())?;
+    let (mut meta, content) = match from_front_matter(entry.meta(), edited.as_str()) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            // 編集内容を失わないように一時ファイルを残す
+            let (_, temp_path) = temp_file.keep()?;
+            return Err(e.context(format!(
+                "invalid front matter. the edited file is kept at {}",
+                temp_path.display()
+            )));
+        }
+    };
+    drop(temp_file);
+
+    if add_minutes {
+        meta.minutes += elapsed_minutes(elapsed);
+    }
+    if bump_pubdate {
+        meta.pubdate = DateTime::local_from_timestamp(Timestamp::now()?);
+    }
+    if &meta == entry.meta() && content == entry.content() {
+        println!("{entry_id} is not changed");
+        return Ok(());
+    }
+    repository.save(entry.update(content, meta))?;
+    println!("{entry_id}");
+    Ok(())
+}
+
+fn run_editor(path: &Path) -> anyhow::Result<()> {
+    let editor = env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+    // e.g. EDITOR="code --wait"
+    let mut args = editor.split_whitespace();
+    let program = args.next().context("EDITOR is empty")?;
+    let status = process::Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .with_context(|| format!("failed to run the editor: {editor}"))?;
+    if !status.success() {
+        anyhow::bail!("the editor exited with {status}");
+    }
+    Ok(())
+}
+
+fn elapsed_minutes(elapsed: Duration) -> u64 {
+    // 1 分未満も 1 分として数える
+    u64::try_from(elapsed.as_millis())
+        .unwrap_or(u64::MAX)
+        .div_ceil(60_000)
+}
+
+fn to_front_matter(meta: &EntryMeta, content: &str) -> String {
+    format!(
+        "{FRONT_MATTER_DELIMITER}title: {}\ntags: {}\nminutes: {}\npubdate: {}\n{FRONT_MATTER_DELIMITER}{}",
+        meta.title,
+        meta.tags.join(", "),
+        meta.minutes,
+        meta.pubdate,
+        content
+    )
+}
+
+/// `base` の hatena_blog_* はそのまま引き継ぐ
+fn from_front_matter(base: &EntryMeta, s: &str) -> anyhow::Result<(EntryMeta, String)> {
+    let s = s
+        .strip_prefix(FRONT_MATTER_DELIMITER)
+        .context("the front matter does not start with `---`")?;
+    let (front_matter, content) = if let Some(content) = s.strip_prefix(FRONT_MATTER_DELIMITER) {
+        ("", content)
+    } else {
+        s.split_once(&format!("\n{FRONT_MATTER_DELIMITER}"))
+            .context("the front matter does not end with `---`")?
+    };
+
+    let mut title = None;
+    let mut tags = None;
+    let mut minutes = None;
+    let mut pubdate = None;
+    for line in front_matter.lines() {
+        let (key, value) = line
+            .split_once(':')
+            .with_context(|| format!("invalid line: {line}"))?;
+        let value = value.trim();
+        let duplicated = match key.trim() {
+            "title" => title.replace(value.to_string()).is_some(),
+            "tags" => tags
+                .replace(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string)
+                        .collect::<Vec<String>>(),
+                )
+                .is_some(),
+            "minutes" => minutes
+                .replace(u64::from_str(value).with_context(|| format!("invalid minutes: {value}"))?)
+                .is_some(),
+            "pubdate" => pubdate
+                .replace(
+                    DateTime::from_str(value)
+                        .with_context(|| format!("invalid pubdate: {value}"))?,
+                )
+                .is_some(),
+            key => anyhow::bail!("unknown key: {key}"),
+        };
+        if duplicated {
+            anyhow::bail!("duplicated key: {}", key.trim());
+        }
+    }
+
+    let meta = EntryMeta {
+        minutes: minutes.context("minutes is missing")?,
+        pubdate: pubdate.context("pubdate is missing")?,
+        tags: tags.context("tags is missing")?,
+        title: title.context("title is missing")?,
+        ..base.clone()
+    };
+    Ok((meta, content.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> anyhow::Result<EntryMeta> {
+        let mut meta = EntryMeta::new(
+            5,
+            DateTime::from_str("2021-02-03T00:00:00+09:00")?,
+            vec!["rust".to_string(), "diary".to_string()],
+            "TITLE: 1".to_string(),
+        );
+        meta.hatena_blog_entry_id = Some("123".to_string());
+        Ok(meta)
+    }
+
+    #[test]
+    fn front_matter_roundtrip_test() -> anyhow::Result<()> {
+        let meta = meta()?;
+        let s = to_front_matter(&meta, "hello\n---\nworld\n");
+        assert_eq!(
+            s,
+            "---\ntitle: TITLE: 1\ntags: rust, diary\nminutes: 5\npubdate: 2021-02-03T00:00:00+09:00\n---\nhello\n---\nworld\n"
+        );
+        assert_eq!(
+            from_front_matter(&meta, s.as_str())?,
+            (meta.clone(), "hello\n---\nworld\n".to_string())
+        );
+
+        let mut no_tags = meta.clone();
+        no_tags.tags = vec![];
+        let s = to_front_matter(&no_tags, "");
+        assert_eq!(
+            from_front_matter(&meta, s.as_str())?,
+            (no_tags, "".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_front_matter_test() -> anyhow::Result<()> {
+        let base = meta()?;
+        let (meta, content) = from_front_matter(
+            &base,
+            "---\npubdate: 2021-02-04T01:02:03+09:00\nminutes: 10\ntags:  a ,b,\ntitle:  T \n---\nC",
+        )?;
+        assert_eq!(meta.hatena_blog_entry_id, Some("123".to_string()));
+        assert_eq!(meta.minutes, 10);
+        assert_eq!(meta.pubdate.to_string(), "2021-02-04T01:02:03+09:00");
+        assert_eq!(meta.tags, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(meta.title, "T");
+        assert_eq!(content, "C");
+
+        let f = |s: &str| from_front_matter(&base, s).is_err();
+        assert!(f("title: T\n"));
+        assert!(f(
+            "---\ntitle: T\ntags: \nminutes: 1\npubdate: 2021-02-04T01:02:03+09:00\n"
+        ));
+        assert!(f("---\ntitle: T\ntags: \nminutes: 1\n---\n"));
+        assert!(f(
+            "---\ntitle: T\ntags: \nminutes: x\npubdate: 2021-02-04T01:02:03+09:00\n---\n"
+        ));
+        assert!(f("---\ntitle: T\ntags: \nminutes: 1\npubdate: x\n---\n"));
+        assert!(f(
+            "---\ntitle: T\ntags: \nminutes: 1\nminutes: 1\npubdate: 2021-02-04T01:02:03+09:00\n---\n"
+        ));
+        assert!(f(
+            "---\ntitle: T\ntags: \nminutes: 1\npubdate: 2021-02-04T01:02:03+09:00\nfoo: 1\n---\n"
+        ));
+        assert!(f(
+            "---\ntitle: T\ntags: \nminutes: 1\npubdate: 2021-02-04T01:02:03+09:00\nfoo\n---\n"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn elapsed_minutes_test() {
+        assert_eq!(elapsed_minutes(Duration::from_secs(0)), 0);
+        assert_eq!(elapsed_minutes(Duration::from_millis(1)), 1);
+        assert_eq!(elapsed_minutes(Duration::from_secs(1)), 1);
+        assert_eq!(elapsed_minutes(Duration::from_secs(60)), 1);
+        assert_eq!(elapsed_minutes(Duration::from_secs(61)), 2);
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::Context;
+use bbn_repository::BbnRepository;
+
+use crate::config_repository::ConfigRepository;
+
+#[derive(Debug, clap::Args)]
+pub struct Command {
+    #[arg(
+        long = "fix",
+        help = "Fixes the safe cases (e.g. creates missing .md files, moves misplaced files)"
+    )]
+    pub fix: bool,
+}
+
+impl Command {
+    pub fn handle(self) -> anyhow::Result<()> {
+        fsck(self.fix)
+    }
+}
+
+// 修正によって新しい問題が見つかることがあるので繰り返す (e.g. 作成した .md が misplaced)
+const MAX_FIX_PASSES: usize = 10;
+
+fn fsck(fix: bool) -> anyhow::Result<()> {
+    let config_repository = ConfigRepository::new()?;
+    let config = config_repository
+        .load()
+        .context("The configuration file does not found. Use `bbn config` command.")?;
+    let data_dir = config.data_dir().to_path_buf();
+
+    let repository = BbnRepository::new(data_dir);
+    if fix {
+        for _ in 0..MAX_FIX_PASSES {
+            let mut fixed = 0;
+            for problem in repository.validate()? {
+                if repository.fix(&problem)? {
+                    println!("fixed: {problem}");
+                    fixed += 1;
+                }
+            }
+            if fixed == 0 {
+                break;
+            }
+        }
+    }
+
+    let problems = repository.validate()?;
+    for problem in problems.iter() {
+        println!("{problem}");
+    }
+    if !problems.is_empty() {
+        anyhow::bail!("{} problem(s) found", problems.len());
+    }
+    Ok(())
+}
@@ -1,9 +1,52 @@
 mod diff;
 mod download;
 mod list;
+mod sync_status;
 mod upload;
 mod view;
 
+use std::time::Duration;
+
+use bbn_hatena_blog::HatenaBlogClient;
+use bbn_hatena_blog::RateLimiter;
+use bbn_hatena_blog::RetryPolicy;
+
+use crate::credentials::Credentials;
+
+/// `bbn hatena-blog download` と `bbn hatena-blog upload` に共通するオプション
+#[derive(Debug, clap::Args)]
+pub struct ClientArgs {
+    #[arg(
+        long = "interval",
+        default_value_t = 1,
+        help = "Seconds to wait between requests to the Hatena Blog"
+    )]
+    interval: u64,
+    #[arg(
+        long = "max-retries",
+        default_value_t = 3,
+        help = "Number of retries for a failed request (creating an entry is never retried)"
+    )]
+    max_retries: u32,
+}
+
+impl ClientArgs {
+    pub fn into_client(self, credentials: &Credentials) -> HatenaBlogClient {
+        let retry_policy = RetryPolicy::new(
+            self.max_retries,
+            Duration::from_secs(1),
+            Duration::from_secs(30),
+        );
+        HatenaBlogClient::new(
+            credentials.hatena_id().to_string(),
+            credentials.hatena_blog_id().to_string(),
+            credentials.hatena_api_key().to_string(),
+        )
+        .with_rate_limiter(RateLimiter::new(1, Duration::from_secs(self.interval)))
+        .with_retry_policy(retry_policy)
+    }
+}
+
 #[derive(Debug, clap::Args)]
 pub struct Command {
     #[command(subcommand)]
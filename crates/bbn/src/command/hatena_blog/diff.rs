@@ -1,5 +1,6 @@
 use anyhow::Context;
 
+use super::sync_status::SyncStatus;
 use crate::config_repository::ConfigRepository;
 use bbn_hatena_blog::HatenaBlogRepository;
 use bbn_repository::BbnRepository;
@@ -45,21 +46,15 @@ async fn diff(date: Option<String>) -> anyhow::Result<()> {
         let hatena_blog_entry = hatena_blog_repository
             .find_entry_by_entry_meta(bbn_entry.meta())
             .await?;
-        let result = hatena_blog_entry
-            .as_ref()
-            .map(|entry| bbn_entry.content() == entry.content);
-        match result {
-            None => diff_stats.0 += 1,
-            Some(false) => diff_stats.2 += 1,
-            Some(true) => diff_stats.1 += 1,
+        let status = SyncStatus::new(&bbn_entry, hatena_blog_entry.as_ref());
+        match status {
+            SyncStatus::No => diff_stats.0 += 1,
+            SyncStatus::Ne => diff_stats.2 += 1,
+            SyncStatus::Eq => diff_stats.1 += 1,
         }
-        if result != Some(true) {
+        if status != SyncStatus::Eq {
             if date.is_none() {
-                println!(
-                    "{} {}",
-                    result.map(|b| if b { "eq" } else { "ne" }).unwrap_or("no"),
-                    entry_id
-                );
+                println!("{status} {entry_id}");
             } else if let Some(entry) = hatena_blog_entry {
                 show_diff(entry.content.as_str(), bbn_entry.content());
             }
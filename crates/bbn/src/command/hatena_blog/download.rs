@@ -1,35 +1,60 @@
+use super::ClientArgs;
 use crate::config_repository::ConfigRepository;
 use anyhow::Context;
 use bbn_data::DateTime;
 use bbn_data::EntryId;
 use bbn_data::EntryMeta;
 use bbn_data::Timestamp;
+use bbn_hatena_blog::CategoryMap;
 use bbn_hatena_blog::HatenaBlogClient;
 use bbn_hatena_blog::HatenaBlogEntry;
 use bbn_hatena_blog::HatenaBlogRepository;
 use bbn_hatena_blog::IndexingId;
+use bbn_hatena_blog::SyncState;
+use bbn_hatena_blog::SyncedEntry;
 use bbn_hatena_blog::download_entry;
+use bbn_hatena_blog::with_hatena_blog_entry;
 use bbn_repository::BbnRepository;
+use bbn_repository::Query;
 use date_range::date::Date;
 use hatena_blog_api::Entry;
 use hatena_blog_api::GetEntryResponse;
+use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 use std::convert::TryFrom;
 use std::str::FromStr;
-use std::time::Duration;
-use tokio::time::sleep;
 
 #[derive(Debug, clap::Args)]
 pub struct Command {
+    #[command(flatten)]
+    pub client_args: ClientArgs,
     #[arg(long = "data-file-only")]
     pub data_file_only: bool,
     #[arg(name = "DATE")]
     pub date: Option<Date>,
+    #[arg(
+        long = "force",
+        help = "Overwrites the local entries even if they have been edited since the last sync"
+    )]
+    pub force: bool,
+    #[arg(
+        long = "mark-synced",
+        conflicts_with_all = ["data_file_only", "force"],
+        help = "Records the local entries that have never been synced as synced with the remote entries without overwriting them. Run this once after upgrading from a version without the sync records, when the local entries are up to date"
+    )]
+    pub mark_synced: bool,
 }
 
 impl Command {
     pub async fn handle(self) -> anyhow::Result<()> {
-        download(self.data_file_only, self.date).await
+        download(
+            self.client_args,
+            self.data_file_only,
+            self.date,
+            self.force,
+            self.mark_synced,
+        )
+        .await
     }
 }
 
@@ -69,7 +94,6 @@ async fn indexing(
             None => break,
             Some(page) => next_page = Some(page),
         }
-        sleep(Duration::from_secs(1)).await;
     }
 
     let indexing_succeeded_at = Timestamp::now()?;
@@ -117,71 +141,212 @@ async fn parse_entry(hatena_blog_repository: &HatenaBlogRepository) -> anyhow::R
     Ok(())
 }
 
+/// 保存したエントリーの同期の記録を返す
 fn update_bbn_entry(
     entry_id: EntryId,
     hatena_blog_entry: HatenaBlogEntry,
+    category_map: &CategoryMap,
     bbn_repository: &BbnRepository,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<SyncedEntry> {
+    let edited = Timestamp::from(hatena_blog_entry.edited);
+    let tags = category_map.tags(&hatena_blog_entry.categories);
     let entry = match bbn_repository.find_entry_by_id(&entry_id)? {
-        None => bbn_data::Entry::new(
-            entry_id,
-            EntryMeta::new(
+        None => {
+            let meta = EntryMeta::new(
                 15,
                 hatena_blog_entry.updated,
-                vec![],
-                hatena_blog_entry.title,
-            ),
-            hatena_blog_entry.content,
-        ),
+                tags,
+                hatena_blog_entry.title.clone(),
+            );
+            let meta = with_hatena_blog_entry(meta, &hatena_blog_entry);
+            bbn_data::Entry::new(entry_id, meta, hatena_blog_entry.content)
+        }
         Some(bbn_entry) => {
-            let meta = bbn_entry.meta().clone();
-            bbn_entry.update(
-                hatena_blog_entry.content,
-                EntryMeta::new(
-                    meta.minutes,
-                    hatena_blog_entry.updated,
-                    meta.tags,
-                    hatena_blog_entry.title,
-                ),
-            )
+            // minutes や hatena_blog_ignore などはそのまま残す
+            // categories なしでアップロードされたエントリーもあるので、空なら tags も残す
+            let tags = if hatena_blog_entry.categories.is_empty() {
+                bbn_entry.meta().tags.clone()
+            } else {
+                tags
+            };
+            let meta = EntryMeta {
+                pubdate: hatena_blog_entry.updated,
+                tags,
+                title: hatena_blog_entry.title.clone(),
+                ..bbn_entry.meta().clone()
+            };
+            let meta = with_hatena_blog_entry(meta, &hatena_blog_entry);
+            bbn_entry.update(hatena_blog_entry.content, meta)
         }
     };
-    bbn_repository.save(entry)
+    let synced_entry = SyncedEntry::new(entry.meta(), entry.content(), edited);
+    bbn_repository.save(entry)?;
+    Ok(synced_entry)
+}
+
+/// bbn のエントリーが Hatena Blog のエントリーと同じ title と tags と content か
+///
+/// categories が空なら tags は比べない (update_bbn_entry で tags を残すため)
+fn matches_hatena_blog_entry(
+    bbn_entry: &bbn_data::Entry,
+    hatena_blog_entry: &HatenaBlogEntry,
+    category_map: &CategoryMap,
+) -> bool {
+    bbn_entry.meta().title == hatena_blog_entry.title
+        && bbn_entry.content() == hatena_blog_entry.content
+        && (hatena_blog_entry.categories.is_empty()
+            || BTreeSet::from_iter(&bbn_entry.meta().tags)
+                == BTreeSet::from_iter(&category_map.tags(&hatena_blog_entry.categories)))
+}
+
+/// Hatena Blog の entry id -> 記録されている bbn の entry id
+fn find_linked_entry_ids(
+    bbn_repository: &BbnRepository,
+) -> anyhow::Result<BTreeMap<String, EntryId>> {
+    let mut linked = BTreeMap::new();
+    for entry_id in bbn_repository.find_ids_by_query(Query::try_from("")?)? {
+        if let Some(hatena_blog_entry_id) = bbn_repository
+            .find_meta_by_id(&entry_id)?
+            .and_then(|meta| meta.hatena_blog_entry_id)
+        {
+            linked.insert(hatena_blog_entry_id, entry_id);
+        }
+    }
+    Ok(linked)
 }
 
+/// `force` が `false` の場合、前回の同期から bbn 側で編集されたエントリーは上書きしない
+///
+/// 同期の記録がなく内容が異なるエントリーも上書きしない
+///
+/// `mark_synced` が `true` の場合、同期の記録がないエントリーを上書きせずに現在の状態で記録する
 async fn update_bbn_entries(
     target_entry_id: Option<EntryId>,
+    force: bool,
+    mark_synced: bool,
+    category_map: &CategoryMap,
     bbn_repository: &BbnRepository,
     hatena_blog_repository: &HatenaBlogRepository,
 ) -> anyhow::Result<()> {
+    let linked = find_linked_entry_ids(bbn_repository)?;
+    let mut skipped = vec![];
     for (updated, _) in hatena_blog_repository
         .find_entries_updated_and_title()
         .await?
     {
-        let timestamp = Timestamp::try_from(i64::from(updated))?;
-        let datetime = DateTime::local_from_timestamp(timestamp);
-        let date = Date::from_str(datetime.to_string().get(0..10).unwrap())?;
-        let entry_id = match bbn_repository.find_id_by_date(date)? {
-            None => EntryId::new(date, None),
-            Some(entry_id) => entry_id,
+        let hatena_blog_entry = hatena_blog_repository
+            .find_entry_by_updated(updated)
+            .await?
+            .unwrap();
+        // 記録されている entry id を優先し、なければ pubdate の日付で対応させる
+        let entry_id = match linked.get(&hatena_blog_entry.id.to_string()) {
+            Some(entry_id) => entry_id.clone(),
+            None => {
+                let timestamp = Timestamp::try_from(i64::from(updated))?;
+                let datetime = DateTime::local_from_timestamp(timestamp);
+                let date = Date::from_str(datetime.to_string().get(0..10).unwrap())?;
+                match bbn_repository.find_id_by_date(date)? {
+                    None => EntryId::new(date, None),
+                    Some(entry_id) => {
+                        let linked_to_other = bbn_repository
+                            .find_meta_by_id(&entry_id)?
+                            .and_then(|meta| meta.hatena_blog_entry_id)
+                            .is_some();
+                        if linked_to_other {
+                            eprintln!(
+                                "skipped member id: {} ({} is linked to another member)",
+                                hatena_blog_entry.id, entry_id
+                            );
+                            continue;
+                        }
+                        entry_id
+                    }
+                }
+            }
         };
         if let Some(ref target) = target_entry_id {
             if target != &entry_id {
                 continue;
             }
         }
-        let hatena_blog_entry = hatena_blog_repository
-            .find_entry_by_updated(updated)
-            .await?
-            .unwrap();
-        update_bbn_entry(entry_id, hatena_blog_entry, bbn_repository)?;
+        let hatena_blog_entry_id = hatena_blog_entry.id.clone();
+        let remote_edited = Timestamp::from(hatena_blog_entry.edited);
+        match bbn_repository.find_entry_by_id(&entry_id)? {
+            None if mark_synced => continue,
+            None => {}
+            Some(bbn_entry) => {
+                let last_synced_entry = hatena_blog_repository
+                    .find_synced_entry(&hatena_blog_entry_id)
+                    .await?;
+                let state = SyncState::new(
+                    last_synced_entry.as_ref(),
+                    bbn_entry.meta(),
+                    bbn_entry.content(),
+                    remote_edited,
+                );
+                if mark_synced {
+                    if state == SyncState::Unknown {
+                        let synced_entry =
+                            SyncedEntry::new(bbn_entry.meta(), bbn_entry.content(), remote_edited);
+                        hatena_blog_repository
+                            .create_or_replace_synced_entry(
+                                &hatena_blog_entry_id,
+                                &synced_entry,
+                                Timestamp::now()?,
+                            )
+                            .await?;
+                        println!(
+                            "marked as synced: {entry_id} (member id: {hatena_blog_entry_id})"
+                        );
+                    }
+                    continue;
+                }
+                let reason = match state {
+                    SyncState::Conflict => Some("conflict"),
+                    // bbn 側の変更は upload で反映する
+                    SyncState::LocalChanged => Some("local changed"),
+                    // 同期の記録がなければどちらが編集されたかわからない
+                    // 同じ内容なら下で同期の記録を残す
+                    SyncState::Unknown
+                        if !matches_hatena_blog_entry(
+                            &bbn_entry,
+                            &hatena_blog_entry,
+                            category_map,
+                        ) =>
+                    {
+                        Some("not synced")
+                    }
+                    SyncState::Unknown | SyncState::Unchanged | SyncState::RemoteChanged => None,
+                };
+                if let Some(reason) = reason.filter(|_| !force) {
+                    eprintln!("skipped: {entry_id} (member id: {hatena_blog_entry_id}, {reason})");
+                    skipped.push(entry_id);
+                    continue;
+                }
+            }
+        }
+        let synced_entry =
+            update_bbn_entry(entry_id, hatena_blog_entry, category_map, bbn_repository)?;
+        hatena_blog_repository
+            .create_or_replace_synced_entry(&hatena_blog_entry_id, &synced_entry, Timestamp::now()?)
+            .await?;
+    }
+    if !skipped.is_empty() {
+        anyhow::bail!(
+            "{} entries have local changes and were skipped. Use --force to overwrite the local entries, or --mark-synced to record the entries that have never been synced as synced",
+            skipped.len()
+        );
     }
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn download_impl(
     data_file_only: bool,
     date: Option<Date>,
+    force: bool,
+    mark_synced: bool,
+    category_map: &CategoryMap,
     bbn_repository: &BbnRepository,
     hatena_blog_repository: &HatenaBlogRepository,
     hatena_blog_client: &HatenaBlogClient,
@@ -201,7 +366,15 @@ async fn download_impl(
         return if data_file_only {
             Ok(())
         } else {
-            update_bbn_entries(Some(entry_id), bbn_repository, hatena_blog_repository).await
+            update_bbn_entries(
+                Some(entry_id),
+                force,
+                mark_synced,
+                category_map,
+                bbn_repository,
+                hatena_blog_repository,
+            )
+            .await
         };
     }
 
@@ -251,7 +424,6 @@ async fn download_impl(
                     "downloaded member id: {}",
                     member_request.hatena_blog_entry_id
                 );
-                sleep(Duration::from_secs(1)).await;
             }
         }
     }
@@ -261,11 +433,25 @@ async fn download_impl(
     if data_file_only {
         Ok(())
     } else {
-        update_bbn_entries(None, bbn_repository, hatena_blog_repository).await
+        update_bbn_entries(
+            None,
+            force,
+            mark_synced,
+            category_map,
+            bbn_repository,
+            hatena_blog_repository,
+        )
+        .await
     }
 }
 
-async fn download(data_file_only: bool, date: Option<Date>) -> anyhow::Result<()> {
+async fn download(
+    client_args: ClientArgs,
+    data_file_only: bool,
+    date: Option<Date>,
+    force: bool,
+    mark_synced: bool,
+) -> anyhow::Result<()> {
     let config_repository = ConfigRepository::new()?;
     let config = config_repository
         .load()
@@ -280,18 +466,348 @@ async fn download(data_file_only: bool, date: Option<Date>) -> anyhow::Result<()
     })?;
 
     let bbn_repository = BbnRepository::new(data_dir);
+    let category_map = CategoryMap::from(config.hatena_blog_categories().clone());
     let hatena_blog_repository = HatenaBlogRepository::new(data_file).await?;
-    let hatena_blog_client = HatenaBlogClient::new(
-        credentials.hatena_id().to_string(),
-        credentials.hatena_blog_id().to_string(),
-        credentials.hatena_api_key().to_string(),
-    );
+    let hatena_blog_client = client_args.into_client(&credentials);
     download_impl(
         data_file_only,
         date,
+        force,
+        mark_synced,
+        &category_map,
         &bbn_repository,
         &hatena_blog_repository,
         &hatena_blog_client,
     )
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn hatena_blog_entry(categories: Vec<String>) -> anyhow::Result<HatenaBlogEntry> {
+        let updated = DateTime::from_str("2021-02-03T16:17:18+09:00")?;
+        Ok(HatenaBlogEntry {
+            author_name: "bouzuya".to_string(),
+            categories,
+            content: "content".to_string(),
+            draft: false,
+            edit_url: "https://blog.hatena.ne.jp/bouzuya/bouzuya.hatenablog.com/atom/entry/1"
+                .to_string(),
+            edited: updated,
+            id: "1".parse()?,
+            published: updated,
+            title: "title".to_string(),
+            updated,
+            url: "https://bouzuya.hatenablog.com/entry/2021/02/03/161718".to_string(),
+        })
+    }
+
+    #[test]
+    fn update_bbn_entry_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let bbn_repository = BbnRepository::new(temp_dir.path().join("data"));
+        let category_map = CategoryMap::from(BTreeMap::from([(
+            "tag1".to_string(),
+            "category1".to_string(),
+        )]));
+        let entry_id = EntryId::from_str("2021-02-03")?;
+
+        // 新しいエントリーは categories から tags を作る
+        update_bbn_entry(
+            entry_id.clone(),
+            hatena_blog_entry(vec!["category1".to_string(), "tag2".to_string()])?,
+            &category_map,
+            &bbn_repository,
+        )?;
+        let entry = bbn_repository.find_entry_by_id(&entry_id)?.unwrap();
+        assert_eq!(
+            entry.meta().tags,
+            vec!["tag1".to_string(), "tag2".to_string()]
+        );
+        assert_eq!(entry.meta().hatena_blog_entry_id, Some("1".to_string()));
+        assert_eq!(
+            entry.meta().hatena_blog_entry_url,
+            Some("https://bouzuya.hatenablog.com/entry/2021/02/03/161718".to_string())
+        );
+        assert_eq!(
+            find_linked_entry_ids(&bbn_repository)?,
+            BTreeMap::from([("1".to_string(), entry_id.clone())])
+        );
+        let meta = EntryMeta {
+            hatena_blog_ignore: Some(false),
+            minutes: 30,
+            ..entry.meta().clone()
+        };
+        bbn_repository.save(entry.update("content".to_string(), meta))?;
+
+        // 既存のエントリーの tags は categories で置き換える
+        update_bbn_entry(
+            entry_id.clone(),
+            hatena_blog_entry(vec!["tag3".to_string()])?,
+            &category_map,
+            &bbn_repository,
+        )?;
+        let entry = bbn_repository.find_entry_by_id(&entry_id)?.unwrap();
+        assert_eq!(entry.meta().tags, vec!["tag3".to_string()]);
+        assert_eq!(entry.meta().hatena_blog_entry_id, Some("1".to_string()));
+        assert_eq!(entry.meta().hatena_blog_ignore, Some(false));
+        assert_eq!(entry.meta().minutes, 30);
+
+        // categories が空なら既存のエントリーの tags を残す
+        update_bbn_entry(
+            entry_id.clone(),
+            hatena_blog_entry(vec![])?,
+            &category_map,
+            &bbn_repository,
+        )?;
+        let entry = bbn_repository.find_entry_by_id(&entry_id)?.unwrap();
+        assert_eq!(entry.meta().tags, vec!["tag3".to_string()]);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn update_bbn_entries_conflict_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        std::fs::create_dir_all(data_dir.as_path())?;
+        let bbn_repository = BbnRepository::new(data_dir);
+        let hatena_blog_repository =
+            HatenaBlogRepository::new(temp_dir.path().join("hatena_blog.db")).await?;
+        let category_map = CategoryMap::default();
+        let entry_id = EntryId::from_str("2021-02-03")?;
+        let content = || -> anyhow::Result<String> {
+            let entry = bbn_repository.find_entry_by_id(&entry_id)?.unwrap();
+            Ok(entry.content().to_string())
+        };
+
+        let save_content = |s: &str| -> anyhow::Result<()> {
+            let entry = bbn_repository.find_entry_by_id(&entry_id)?.unwrap();
+            let meta = entry.meta().clone();
+            bbn_repository.save(entry.update(s.to_string(), meta))
+        };
+
+        let remote = hatena_blog_entry(vec![])?;
+        hatena_blog_repository
+            .create_entry(Entry::from(remote.clone()), Timestamp::now()?)
+            .await?;
+
+        // 同期の記録がなく内容が異なるものは上書きしない
+        update_bbn_entry(
+            entry_id.clone(),
+            remote.clone(),
+            &category_map,
+            &bbn_repository,
+        )?;
+        save_content("local")?;
+        assert!(
+            update_bbn_entries(
+                None,
+                false,
+                false,
+                &category_map,
+                &bbn_repository,
+                &hatena_blog_repository,
+            )
+            .await
+            .is_err()
+        );
+        assert_eq!(content()?, "local");
+
+        // 同期の記録がなくても内容が同じなら同期する
+        save_content("content")?;
+        update_bbn_entries(
+            None,
+            false,
+            false,
+            &category_map,
+            &bbn_repository,
+            &hatena_blog_repository,
+        )
+        .await?;
+        assert_eq!(content()?, "content");
+        assert!(
+            hatena_blog_repository
+                .find_synced_entry(&remote.id)
+                .await?
+                .is_some()
+        );
+
+        // title の変更も bbn 側の変更とみなす
+        let entry = bbn_repository.find_entry_by_id(&entry_id)?.unwrap();
+        let meta = EntryMeta {
+            title: "local title".to_string(),
+            ..entry.meta().clone()
+        };
+        bbn_repository.save(entry.update("content".to_string(), meta))?;
+        assert!(
+            update_bbn_entries(
+                None,
+                false,
+                false,
+                &category_map,
+                &bbn_repository,
+                &hatena_blog_repository,
+            )
+            .await
+            .is_err()
+        );
+        let entry = bbn_repository.find_entry_by_id(&entry_id)?.unwrap();
+        assert_eq!(entry.meta().title, "local title");
+        let meta = EntryMeta {
+            title: "title".to_string(),
+            ..entry.meta().clone()
+        };
+        bbn_repository.save(entry.update("content".to_string(), meta))?;
+
+        // bbn 側だけの変更は上書きしない
+        save_content("local")?;
+        assert!(
+            update_bbn_entries(
+                None,
+                false,
+                false,
+                &category_map,
+                &bbn_repository,
+                &hatena_blog_repository,
+            )
+            .await
+            .is_err()
+        );
+        assert_eq!(content()?, "local");
+
+        // 両方の変更は衝突する
+        hatena_blog_repository
+            .delete_entry(&hatena_blog_api::EntryId::from(&remote.id))
+            .await?;
+        let remote = HatenaBlogEntry {
+            content: "remote".to_string(),
+            edited: DateTime::from_str("2021-02-04T00:00:00+09:00")?,
+            ..remote
+        };
+        hatena_blog_repository
+            .create_entry(Entry::from(remote), Timestamp::now()?)
+            .await?;
+        assert!(
+            update_bbn_entries(
+                None,
+                false,
+                false,
+                &category_map,
+                &bbn_repository,
+                &hatena_blog_repository,
+            )
+            .await
+            .is_err()
+        );
+        assert_eq!(content()?, "local");
+
+        // --force で上書きする
+        update_bbn_entries(
+            None,
+            true,
+            false,
+            &category_map,
+            &bbn_repository,
+            &hatena_blog_repository,
+        )
+        .await?;
+        assert_eq!(content()?, "remote");
+        update_bbn_entries(
+            None,
+            false,
+            false,
+            &category_map,
+            &bbn_repository,
+            &hatena_blog_repository,
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn update_bbn_entries_mark_synced_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        std::fs::create_dir_all(data_dir.as_path())?;
+        let bbn_repository = BbnRepository::new(data_dir);
+        let hatena_blog_repository =
+            HatenaBlogRepository::new(temp_dir.path().join("hatena_blog.db")).await?;
+        let category_map = CategoryMap::default();
+        let entry_id = EntryId::from_str("2021-02-03")?;
+        let content = || -> anyhow::Result<String> {
+            let entry = bbn_repository.find_entry_by_id(&entry_id)?.unwrap();
+            Ok(entry.content().to_string())
+        };
+
+        let remote = hatena_blog_entry(vec![])?;
+        hatena_blog_repository
+            .create_entry(Entry::from(remote.clone()), Timestamp::now()?)
+            .await?;
+        update_bbn_entry(
+            entry_id.clone(),
+            remote.clone(),
+            &category_map,
+            &bbn_repository,
+        )?;
+        let entry = bbn_repository.find_entry_by_id(&entry_id)?.unwrap();
+        let meta = entry.meta().clone();
+        bbn_repository.save(entry.update("local".to_string(), meta))?;
+
+        // 同期の記録がないエントリーを上書きせずに記録する
+        update_bbn_entries(
+            None,
+            false,
+            true,
+            &category_map,
+            &bbn_repository,
+            &hatena_blog_repository,
+        )
+        .await?;
+        assert_eq!(content()?, "local");
+        assert!(
+            hatena_blog_repository
+                .find_synced_entry(&remote.id)
+                .await?
+                .is_some()
+        );
+
+        // 記録した後は変更がなければそのまま同期できる
+        update_bbn_entries(
+            None,
+            false,
+            false,
+            &category_map,
+            &bbn_repository,
+            &hatena_blog_repository,
+        )
+        .await?;
+
+        // Hatena Blog 側の変更は反映する
+        hatena_blog_repository
+            .delete_entry(&hatena_blog_api::EntryId::from(&remote.id))
+            .await?;
+        let remote = HatenaBlogEntry {
+            content: "remote".to_string(),
+            edited: DateTime::from_str("2021-02-04T00:00:00+09:00")?,
+            ..remote
+        };
+        hatena_blog_repository
+            .create_entry(Entry::from(remote), Timestamp::now()?)
+            .await?;
+        update_bbn_entries(
+            None,
+            false,
+            false,
+            &category_map,
+            &bbn_repository,
+            &hatena_blog_repository,
+        )
+        .await?;
+        assert_eq!(content()?, "remote");
+        Ok(())
+    }
+}
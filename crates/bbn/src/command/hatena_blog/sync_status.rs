@@ -0,0 +1,83 @@
+use bbn_data::Entry;
+use bbn_hatena_blog::HatenaBlogEntry;
+
+/// bbn のエントリーと Hatena Blog のエントリーの比較結果
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncStatus {
+    /// 内容が同じ
+    Eq,
+    /// 内容が異なる
+    Ne,
+    /// Hatena Blog にない
+    No,
+}
+
+impl SyncStatus {
+    pub fn new(bbn_entry: &Entry, hatena_blog_entry: Option<&HatenaBlogEntry>) -> Self {
+        match hatena_blog_entry {
+            None => Self::No,
+            Some(entry) if bbn_entry.content() == entry.content => Self::Eq,
+            Some(_) => Self::Ne,
+        }
+    }
+}
+
+impl std::fmt::Display for SyncStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Eq => "eq",
+            Self::Ne => "ne",
+            Self::No => "no",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bbn_data::DateTime;
+    use bbn_data::EntryId;
+    use bbn_data::EntryMeta;
+
+    use super::*;
+
+    #[test]
+    fn new_test() -> anyhow::Result<()> {
+        let pubdate = DateTime::from_str("2021-02-03T16:17:18+09:00")?;
+        let bbn_entry = Entry::new(
+            EntryId::from_str("2021-02-03")?,
+            EntryMeta::new(5, pubdate, vec![], "title".to_string()),
+            "content".to_string(),
+        );
+        let hatena_blog_entry = |content: &str| -> anyhow::Result<HatenaBlogEntry> {
+            Ok(HatenaBlogEntry {
+                author_name: "bouzuya".to_string(),
+                categories: vec![],
+                content: content.to_string(),
+                draft: false,
+                edit_url: "https://blog.hatena.ne.jp/bouzuya/bouzuya.hatenablog.com/atom/entry/1"
+                    .to_string(),
+                edited: pubdate,
+                id: "1".parse()?,
+                published: pubdate,
+                title: "title".to_string(),
+                updated: pubdate,
+                url: "https://bouzuya.hatenablog.com/entry/2021/02/03/161718".to_string(),
+            })
+        };
+        assert_eq!(SyncStatus::new(&bbn_entry, None), SyncStatus::No);
+        assert_eq!(
+            SyncStatus::new(&bbn_entry, Some(&hatena_blog_entry("content")?)),
+            SyncStatus::Eq
+        );
+        assert_eq!(
+            SyncStatus::new(&bbn_entry, Some(&hatena_blog_entry("changed")?)),
+            SyncStatus::Ne
+        );
+        assert_eq!(SyncStatus::Ne.to_string(), "ne");
+        assert_eq!(serde_json::to_string(&SyncStatus::No)?, r#""no""#);
+        Ok(())
+    }
+}
@@ -7,8 +7,11 @@ use anyhow::bail;
 use console::StyledObject;
 use date_range::date::Date;
 
+use super::ClientArgs;
+use super::sync_status::SyncStatus;
 use crate::config_repository::ConfigRepository;
-use bbn_hatena_blog::HatenaBlogClient;
+use bbn_data::EntryId;
+use bbn_hatena_blog::CategoryMap;
 use bbn_hatena_blog::HatenaBlogRepository;
 use bbn_hatena_blog::upload_entry;
 use bbn_repository::BbnRepository;
@@ -16,21 +19,93 @@ use bbn_repository::Query;
 
 #[derive(Debug, clap::Args)]
 pub struct Command {
+    #[command(flatten)]
+    pub client_args: ClientArgs,
     #[arg(name = "DATE", help = "date")]
     pub date: Option<Date>,
+    #[arg(
+        long = "apply",
+        conflicts_with_all = ["DATE", "interactive"],
+        help = "Uploads the entries in the plan. The plan is recomputed from the current entries, so it may differ from the one printed before"
+    )]
+    pub apply: bool,
     #[arg(long = "draft")]
     pub draft: bool,
+    #[arg(
+        long = "force",
+        help = "Overwrites the remote entry even if it has been edited since the last sync"
+    )]
+    pub force: bool,
     #[arg(long = "interactive")]
     pub interactive: bool,
+    #[arg(
+        long = "json",
+        conflicts_with_all = ["DATE", "interactive"],
+        help = "Prints the plan and the results in the JSON format"
+    )]
+    pub json: bool,
 }
 
 impl Command {
     pub async fn handle(self) -> anyhow::Result<()> {
-        upload(self.date, self.draft, self.interactive).await
+        upload(self).await
     }
 }
 
-async fn upload(date: Option<Date>, draft: bool, interactive: bool) -> anyhow::Result<()> {
+/// アップロードの計画の 1 件
+#[derive(Debug, Eq, PartialEq, serde::Serialize)]
+struct PlanItem {
+    entry_id: String,
+    status: SyncStatus,
+}
+
+/// アップロードの結果の 1 件
+#[derive(Debug, serde::Serialize)]
+struct ResultItem {
+    entry_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    result: &'static str,
+    status: SyncStatus,
+}
+
+/// アップロードが必要なエントリー (ne と no) を返す
+///
+/// `bbn hatena-blog diff` と同じく、hatena_blog_ignore のエントリーは除く
+async fn plan(
+    bbn_repository: &BbnRepository,
+    hatena_blog_repository: &HatenaBlogRepository,
+) -> anyhow::Result<Vec<PlanItem>> {
+    let mut items = vec![];
+    for entry_id in bbn_repository.find_ids_by_query(Query::try_from("")?)? {
+        let bbn_entry = bbn_repository.find_entry_by_id(&entry_id)?.unwrap();
+        if bbn_entry.meta().hatena_blog_ignore == Some(true) {
+            continue;
+        }
+        let hatena_blog_entry = hatena_blog_repository
+            .find_entry_by_entry_meta(bbn_entry.meta())
+            .await?;
+        let status = SyncStatus::new(&bbn_entry, hatena_blog_entry.as_ref());
+        if status != SyncStatus::Eq {
+            items.push(PlanItem {
+                entry_id: entry_id.to_string(),
+                status,
+            });
+        }
+    }
+    Ok(items)
+}
+
+async fn upload(command: Command) -> anyhow::Result<()> {
+    let Command {
+        apply,
+        client_args,
+        date,
+        draft,
+        force,
+        interactive,
+        json,
+    } = command;
     let config_repository = ConfigRepository::new()?;
     let config = config_repository
         .load()
@@ -46,16 +121,15 @@ async fn upload(date: Option<Date>, draft: bool, interactive: bool) -> anyhow::R
     let bbn_repository = BbnRepository::new(data_dir);
     let hatena_blog_data_file = config.hatena_blog_data_file().to_path_buf();
     let hatena_blog_repository = HatenaBlogRepository::new(hatena_blog_data_file).await?;
-    let hatena_blog_client = HatenaBlogClient::new(
-        credentials.hatena_id().to_string(),
-        credentials.hatena_blog_id().to_string(),
-        credentials.hatena_api_key().to_string(),
-    );
+    let hatena_blog_client = client_args.into_client(&credentials);
+    let category_map = CategoryMap::from(config.hatena_blog_categories().clone());
     if let Some(date) = date {
         let (created, entry_id) = upload_entry(
             date,
             draft,
+            force,
             credentials.hatena_id(),
+            &category_map,
             &bbn_repository,
             &hatena_blog_repository,
             &hatena_blog_client,
@@ -66,40 +140,86 @@ async fn upload(date: Option<Date>, draft: bool, interactive: bool) -> anyhow::R
             if created { "created" } else { "updated" },
             entry_id
         );
-    } else {
-        if !interactive {
-            bail!("interfactive = false is not supported");
+    } else if !interactive {
+        let plan = plan(&bbn_repository, &hatena_blog_repository).await?;
+        if !apply {
+            if json {
+                println!("{}", serde_json::to_string(&plan)?);
+            } else {
+                for item in plan.iter() {
+                    println!("{} {}", item.status, item.entry_id);
+                }
+                println!(
+                    "plan: ne = {} no = {}",
+                    plan.iter().filter(|it| it.status == SyncStatus::Ne).count(),
+                    plan.iter().filter(|it| it.status == SyncStatus::No).count(),
+                );
+            }
+            return Ok(());
         }
 
-        let query = Query::try_from("")?;
-        let entry_ids = bbn_repository.find_ids_by_query(query)?;
-        for entry_id in entry_ids {
-            let bbn_entry = bbn_repository.find_entry_by_id(&entry_id)?.unwrap();
-            if bbn_entry.meta().hatena_blog_ignore == Some(true) {
-                continue;
-            }
-            let hatena_blog_entry = hatena_blog_repository
-                .find_entry_by_entry_meta(bbn_entry.meta())
-                .await?;
-            let result = match hatena_blog_entry {
-                None => None,
-                Some(ref entry) => {
-                    if bbn_entry.content() != entry.content {
-                        Some(false)
-                    } else {
-                        Some(true)
-                    }
-                }
+        let mut results = vec![];
+        // HatenaBlogClient が流量を制限する
+        for item in plan {
+            let entry_id = EntryId::from_str(item.entry_id.as_str())?;
+            let result = upload_entry(
+                *entry_id.date(),
+                draft,
+                force,
+                credentials.hatena_id(),
+                &category_map,
+                &bbn_repository,
+                &hatena_blog_repository,
+                &hatena_blog_client,
+            )
+            .await;
+            let result = match result {
+                Ok((created, _)) => ResultItem {
+                    entry_id: item.entry_id,
+                    error: None,
+                    result: if created { "created" } else { "updated" },
+                    status: item.status,
+                },
+                // 1 件の失敗で止めずに残りもアップロードする
+                Err(e) => ResultItem {
+                    entry_id: item.entry_id,
+                    error: Some(format!("{e:#}")),
+                    result: "failed",
+                    status: item.status,
+                },
             };
-            if result == Some(true) {
-                continue;
+            if !json {
+                match result.error.as_ref() {
+                    None => println!("{} {}", result.result, result.entry_id),
+                    Some(e) => println!("{} {} ({})", result.result, result.entry_id, e),
+                }
             }
+            results.push(result);
+        }
+        let count = |r: &str| results.iter().filter(|it| it.result == r).count();
+        let failed = count("failed");
+        if json {
+            println!("{}", serde_json::to_string(&results)?);
+        } else {
             println!(
-                "{} {}",
-                result.map(|b| if b { "eq" } else { "ne" }).unwrap_or("no"),
-                entry_id
+                "result: created = {} updated = {} failed = {}",
+                count("created"),
+                count("updated"),
+                failed
             );
-            match hatena_blog_entry {
+        }
+        if failed > 0 {
+            bail!("failed to upload {failed} entries");
+        }
+    } else {
+        for item in plan(&bbn_repository, &hatena_blog_repository).await? {
+            let entry_id = EntryId::from_str(item.entry_id.as_str())?;
+            let bbn_entry = bbn_repository.find_entry_by_id(&entry_id)?.unwrap();
+            println!("{} {}", item.status, item.entry_id);
+            match hatena_blog_repository
+                .find_entry_by_entry_meta(bbn_entry.meta())
+                .await?
+            {
                 None => println!("no entry"),
                 Some(entry) => {
                     show_2line_diff(entry.content.as_str(), bbn_entry.content());
@@ -110,11 +230,12 @@ async fn upload(date: Option<Date>, draft: bool, interactive: bool) -> anyhow::R
                 .with_prompt("upload ?")
                 .interact()?;
             if yes {
-                let date = Date::from_str(entry_id.to_string().get(0..10).unwrap())?;
                 let (created, entry_id) = upload_entry(
-                    date,
+                    *entry_id.date(),
                     draft,
+                    force,
                     credentials.hatena_id(),
+                    &category_map,
                     &bbn_repository,
                     &hatena_blog_repository,
                     &hatena_blog_client,
@@ -156,3 +277,100 @@ fn show_2line_diff(left: &str, right: &str) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bbn_data::DateTime;
+    use bbn_data::Entry;
+    use bbn_data::EntryMeta;
+    use bbn_data::Timestamp;
+    use bbn_hatena_blog::HatenaBlogEntry;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn plan_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        std::fs::create_dir_all(data_dir.as_path())?;
+        let bbn_repository = BbnRepository::new(data_dir);
+        let hatena_blog_repository =
+            HatenaBlogRepository::new(temp_dir.path().join("hatena_blog.db")).await?;
+
+        let save = |date: &str,
+                    hatena_blog_entry_id: Option<&str>,
+                    hatena_blog_ignore: Option<bool>,
+                    content: &str|
+         -> anyhow::Result<()> {
+            let pubdate = DateTime::from_str(&format!("{date}T00:00:00+09:00"))?;
+            let meta = EntryMeta {
+                hatena_blog_entry_id: hatena_blog_entry_id.map(str::to_string),
+                hatena_blog_ignore,
+                ..EntryMeta::new(5, pubdate, vec![], "title".to_string())
+            };
+            bbn_repository.save(Entry::new(
+                EntryId::from_str(date)?,
+                meta,
+                content.to_string(),
+            ))
+        };
+        let remote = |id: &str, content: &str| -> anyhow::Result<HatenaBlogEntry> {
+            let updated = DateTime::from_str("2021-02-03T00:00:00+09:00")?;
+            Ok(HatenaBlogEntry {
+                author_name: "bouzuya".to_string(),
+                categories: vec![],
+                content: content.to_string(),
+                draft: false,
+                edit_url: format!(
+                    "https://blog.hatena.ne.jp/bouzuya/bouzuya.hatenablog.com/atom/entry/{id}"
+                ),
+                edited: updated,
+                id: id.parse()?,
+                published: updated,
+                title: "title".to_string(),
+                updated,
+                url: format!("https://bouzuya.hatenablog.com/entry/{id}"),
+            })
+        };
+        for (id, content) in [("1", "same"), ("2", "remote"), ("4", "remote")] {
+            hatena_blog_repository
+                .create_entry(
+                    hatena_blog_api::Entry::from(remote(id, content)?),
+                    Timestamp::now()?,
+                )
+                .await?;
+        }
+
+        // eq
+        save("2021-02-03", Some("1"), None, "same")?;
+        // ne
+        save("2021-02-04", Some("2"), None, "local")?;
+        // no
+        save("2021-02-05", None, None, "local")?;
+        // hatena_blog_ignore は内容が異なっても Hatena Blog になくても除く
+        save("2021-02-06", Some("4"), Some(true), "local")?;
+        save("2021-02-07", None, Some(true), "local")?;
+        // hatena_blog_ignore が false なら除かない
+        save("2021-02-08", None, Some(false), "local")?;
+
+        assert_eq!(
+            plan(&bbn_repository, &hatena_blog_repository).await?,
+            vec![
+                PlanItem {
+                    entry_id: "2021-02-04".to_string(),
+                    status: SyncStatus::Ne,
+                },
+                PlanItem {
+                    entry_id: "2021-02-05".to_string(),
+                    status: SyncStatus::No,
+                },
+                PlanItem {
+                    entry_id: "2021-02-08".to_string(),
+                    status: SyncStatus::No,
+                },
+            ]
+        );
+        Ok(())
+    }
+}
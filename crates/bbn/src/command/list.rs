@@ -1,6 +1,7 @@
 use anyhow::Context;
 
 use crate::config_repository::ConfigRepository;
+use crate::site_config::SiteConfig;
 
 #[derive(Debug, clap::Args)]
 pub struct Command {
@@ -8,11 +9,11 @@ pub struct Command {
     pub json: bool,
     #[arg(
         name = "query",
-        help = "query. e.g. date:2021 or date:2021-02 or date:2021-02-03 or date:--02-03 or date:---03"
+        allow_hyphen_values = true,
+        help = "query. e.g. date:2021 or date:2021-02 or date:2021-02-03 or date:--02-03 or date:---03 or tag:rust or date:2021 (tag:rust OR tag:go) -tag:diary"
     )]
-    pub query: Option<String>,
+    pub query: Vec<String>,
 }
-use bbn_data::EntryId;
 use bbn_repository::BbnRepository;
 use bbn_repository::Query;
 use std::convert::TryFrom;
@@ -23,7 +24,7 @@ impl Command {
     }
 }
 
-fn list(json: bool, query: Option<String>) -> anyhow::Result<()> {
+fn list(json: bool, query: Vec<String>) -> anyhow::Result<()> {
     #[derive(serde::Serialize)]
     struct OutputJson {
         date: String,
@@ -36,10 +37,11 @@ fn list(json: bool, query: Option<String>) -> anyhow::Result<()> {
         .load()
         .context("The configuration file does not found. Use `bbn config` command.")?;
     let data_dir = config.data_dir().to_path_buf();
+    let site_config = SiteConfig::from(&config);
 
     let bbn_repository = BbnRepository::new(data_dir);
-    let query = query.unwrap_or_default();
-    let query = Query::try_from(query.as_str()).unwrap();
+    let query = query.join(" ");
+    let query = Query::try_from(query.as_str())?;
     let mut entry_ids = bbn_repository.find_ids_by_query(query)?;
     entry_ids.sort();
     entry_ids.reverse();
@@ -52,7 +54,7 @@ fn list(json: bool, query: Option<String>) -> anyhow::Result<()> {
             let output_json = OutputJson {
                 date: entry_id.date().to_string(),
                 title: entry_meta.title,
-                url: entry_url(&entry_id),
+                url: site_config.entry_url(&entry_id),
             };
             output.push(serde_json::to_string(&output_json)?);
         } else {
@@ -60,7 +62,7 @@ fn list(json: bool, query: Option<String>) -> anyhow::Result<()> {
                 "{} {} <{}>",
                 entry_id.date(),
                 entry_meta.title,
-                entry_url(&entry_id),
+                site_config.entry_url(&entry_id),
             ));
         }
     }
@@ -76,10 +78,3 @@ fn list(json: bool, query: Option<String>) -> anyhow::Result<()> {
     println!("{output}");
     Ok(())
 }
-
-fn entry_url(entry_id: &EntryId) -> String {
-    format!(
-        "https://blog.bouzuya.net/{}/",
-        entry_id.date().to_string().replace('-', "/")
-    )
-}
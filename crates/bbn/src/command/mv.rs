@@ -0,0 +1,67 @@
+use anyhow::Context;
+use bbn_data::EntryId;
+use bbn_repository::BbnRepository;
+use date_range::date::Date;
+
+use crate::config_repository::ConfigRepository;
+use crate::date_like::DateLike;
+
+#[derive(Debug, clap::Args)]
+#[command(group(
+    clap::ArgGroup::new("target")
+        .required(true)
+        .multiple(true)
+        .args(["date", "id_title", "no_id_title"])
+))]
+pub struct Command {
+    #[arg(name = "DATE_LIKE", help = "the date. e.g. 2021-02-03 or 2021-W05-3")]
+    pub date_like: DateLike,
+    #[arg(
+        long = "date",
+        help = "the new date. e.g. 2021-02-04 ([YYYY-MM-DD] links in other entries are rewritten)"
+    )]
+    pub date: Option<DateLike>,
+    #[arg(
+        long = "id-title",
+        conflicts_with = "no_id_title",
+        help = "the new id title. e.g. my-first-post"
+    )]
+    pub id_title: Option<String>,
+    #[arg(long = "no-id-title", help = "Removes the id title")]
+    pub no_id_title: bool,
+}
+
+impl Command {
+    pub fn handle(self) -> anyhow::Result<()> {
+        mv(self.date_like, self.date, self.id_title, self.no_id_title)
+    }
+}
+
+fn mv(
+    date_like: DateLike,
+    new_date_like: Option<DateLike>,
+    id_title: Option<String>,
+    no_id_title: bool,
+) -> anyhow::Result<()> {
+    let date = Date::from(date_like);
+    let config_repository = ConfigRepository::new()?;
+    let config = config_repository
+        .load()
+        .context("The configuration file does not found. Use `bbn config` command.")?;
+    let data_dir = config.data_dir().to_path_buf();
+
+    let repository = BbnRepository::new(data_dir);
+    let entry_id = repository.find_id_by_date(date)?.context("not found")?;
+    // 日付と id title の変更は 1 回の書き込みで行う (途中で失敗しても半端に移動しない)
+    let new_entry_id = EntryId::new(
+        new_date_like.map(Date::from).unwrap_or(*entry_id.date()),
+        if id_title.is_some() || no_id_title {
+            id_title
+        } else {
+            entry_id.id_title().map(str::to_string)
+        },
+    );
+    let entry_id = repository.relocate(&entry_id, new_entry_id)?;
+    println!("{entry_id}");
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use std::str::FromStr;
+
+use anyhow::Context;
+use bbn_data::DateTime;
+use bbn_data::Entry;
+use bbn_data::EntryId;
+use bbn_data::EntryMeta;
+use bbn_data::Timestamp;
+use bbn_repository::BbnRepository;
+use date_range::date::Date;
+
+use crate::config_repository::ConfigRepository;
+use crate::date_like::DateLike;
+
+#[derive(Debug, clap::Args)]
+pub struct Command {
+    #[arg(
+        name = "DATE_LIKE",
+        help = "the date. e.g. 2021-02-03 or 2021-W05-3 (default: today)"
+    )]
+    pub date_like: Option<DateLike>,
+    #[arg(long = "id-title", help = "the id title. e.g. my-first-post")]
+    pub id_title: Option<String>,
+    #[arg(long = "tag", help = "the tag (can be specified multiple times)")]
+    pub tags: Vec<String>,
+    #[arg(long = "title", default_value = "", help = "the title")]
+    pub title: String,
+}
+
+impl Command {
+    pub fn handle(self) -> anyhow::Result<()> {
+        new(self.date_like, self.id_title, self.tags, self.title)
+    }
+}
+
+fn new(
+    date_like: Option<DateLike>,
+    id_title: Option<String>,
+    tags: Vec<String>,
+    title: String,
+) -> anyhow::Result<()> {
+    let config_repository = ConfigRepository::new()?;
+    let config = config_repository
+        .load()
+        .context("The configuration file does not found. Use `bbn config` command.")?;
+    let data_dir = config.data_dir().to_path_buf();
+
+    let pubdate = DateTime::local_from_timestamp(Timestamp::now()?);
+    let date = match date_like {
+        Some(date_like) => Date::from(date_like),
+        None => today(pubdate)?,
+    };
+    if let Some(id_title) = id_title.as_deref() {
+        EntryId::validate_id_title(id_title)?;
+    }
+
+    let repository = BbnRepository::new(data_dir);
+    if let Some(entry_id) = repository.find_id_by_date(date)? {
+        anyhow::bail!("the entry already exists: {entry_id}");
+    }
+    let entry_id = EntryId::new(date, id_title);
+    let entry = Entry::new(
+        entry_id.clone(),
+        EntryMeta::new(0, pubdate, tags, title),
+        String::new(),
+    );
+    repository.save(entry)?;
+    println!("{entry_id}");
+    Ok(())
+}
+
+fn today(now: DateTime) -> anyhow::Result<Date> {
+    // "YYYY-MM-DDTHH:MM:SS+09:00" の先頭が現地の日付
+    let s = now.to_string();
+    let date = s.get(0..10).context("invalid date time")?;
+    Ok(Date::from_str(date)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn today_test() -> anyhow::Result<()> {
+        let now = DateTime::from_str("2021-02-03T23:59:59+09:00")?;
+        assert_eq!(today(now)?, Date::from_str("2021-02-03")?);
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use anyhow::Context;
+use bbn_repository::BbnRepository;
+use date_range::date::Date;
+
+use crate::config_repository::ConfigRepository;
+use crate::date_like::DateLike;
+
+#[derive(Debug, clap::Args)]
+pub struct Command {
+    #[arg(name = "DATE_LIKE", help = "the date. e.g. 2021-02-03 or 2021-W05-3")]
+    pub date_like: DateLike,
+}
+
+impl Command {
+    pub fn handle(self) -> anyhow::Result<()> {
+        rm(self.date_like)
+    }
+}
+
+fn rm(date_like: DateLike) -> anyhow::Result<()> {
+    let date = Date::from(date_like);
+    let config_repository = ConfigRepository::new()?;
+    let config = config_repository
+        .load()
+        .context("The configuration file does not found. Use `bbn config` command.")?;
+    let data_dir = config.data_dir().to_path_buf();
+
+    let repository = BbnRepository::new(data_dir);
+    let entry_id = repository.find_id_by_date(date)?.context("not found")?;
+    repository.delete(&entry_id)?;
+    println!("{entry_id}");
+    Ok(())
+}
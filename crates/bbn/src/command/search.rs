@@ -0,0 +1,163 @@
+use anyhow::Context;
+use bbn_repository::BbnRepository;
+use bbn_repository::Query;
+use bbn_repository::find_ignore_ascii_case;
+use std::convert::TryFrom;
+
+use crate::config_repository::ConfigRepository;
+use crate::site_config::SiteConfig;
+
+#[derive(Debug, clap::Args)]
+pub struct Command {
+    #[arg(long = "json", help = "json")]
+    pub json: bool,
+    #[arg(
+        name = "query",
+        allow_hyphen_values = true,
+        required = true,
+        help = "query. e.g. rust or \"hello world\" or title:rust or date:2021 tag:rust nom"
+    )]
+    pub query: Vec<String>,
+}
+
+impl Command {
+    pub fn handle(self) -> anyhow::Result<()> {
+        search(self.json, self.query)
+    }
+}
+
+// スニペットとしてマッチ位置の前後に表示する文字数
+const SNIPPET_CONTEXT_CHARS: usize = 30;
+
+fn search(json: bool, query: Vec<String>) -> anyhow::Result<()> {
+    #[derive(serde::Serialize)]
+    struct OutputJson {
+        date: String,
+        snippet: Option<String>,
+        title: String,
+        url: String,
+    }
+
+    let config_repository = ConfigRepository::new()?;
+    let config = config_repository
+        .load()
+        .context("The configuration file does not found. Use `bbn config` command.")?;
+    let data_dir = config.data_dir().to_path_buf();
+    let site_config = SiteConfig::from(&config);
+
+    let bbn_repository = BbnRepository::new(data_dir);
+    let query = query.join(" ");
+    let query = Query::try_from(query.as_str())?;
+    let text_terms = query.text_terms();
+    let mut entry_ids = bbn_repository.find_ids_by_query(query)?;
+    entry_ids.sort();
+    entry_ids.reverse();
+    let mut output = vec![];
+    for entry_id in entry_ids {
+        let entry = bbn_repository
+            .find_entry_by_id(&entry_id)?
+            .context("entry not found")?;
+        let snippet = snippet(entry.content(), &text_terms);
+        if json {
+            let output_json = OutputJson {
+                date: entry_id.date().to_string(),
+                snippet: snippet
+                    .map(|(before, matched, after)| format!("{before}{matched}{after}")),
+                title: entry.meta().title.clone(),
+                url: site_config.entry_url(&entry_id),
+            };
+            output.push(serde_json::to_string(&output_json)?);
+        } else {
+            let mut line = format!(
+                "{} {} <{}>",
+                entry_id.date(),
+                entry.meta().title,
+                site_config.entry_url(&entry_id),
+            );
+            if let Some((before, matched, after)) = snippet {
+                line.push_str(&format!(
+                    "\n  {}{}{}",
+                    before,
+                    console::style(matched).bold().yellow(),
+                    after
+                ));
+            }
+            output.push(line);
+        }
+    }
+    let output = if json {
+        let mut s = String::new();
+        s.push('[');
+        s.push_str(&output.join(","));
+        s.push(']');
+        s
+    } else {
+        output.join("\n")
+    };
+    println!("{output}");
+    Ok(())
+}
+
+/// 最初にマッチした語の前後を (前, マッチ部分, 後) として返す
+fn snippet<'a>(content: &'a str, text_terms: &[&str]) -> Option<(String, &'a str, String)> {
+    let (start, end) = text_terms
+        .iter()
+        .filter_map(|term| find_ignore_ascii_case(content, term).map(|i| (i, i + term.len())))
+        .min()?;
+    let before = content[..start]
+        .chars()
+        .rev()
+        .take(SNIPPET_CONTEXT_CHARS)
+        .collect::<Vec<char>>()
+        .into_iter()
+        .rev()
+        .collect::<String>();
+    let before = format!(
+        "{}{}",
+        if before.len() < start { "..." } else { "" },
+        before
+    );
+    let after = content[end..]
+        .chars()
+        .take(SNIPPET_CONTEXT_CHARS)
+        .collect::<String>();
+    let after = format!(
+        "{}{}",
+        after,
+        if end + after.len() < content.len() {
+            "..."
+        } else {
+            ""
+        }
+    );
+    let one_line = |s: &str| s.replace(['\r', '\n'], " ");
+    Some((one_line(&before), &content[start..end], one_line(&after)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippet_test() {
+        assert_eq!(snippet("hello", &[]), None);
+        assert_eq!(snippet("hello", &["rust"]), None);
+        assert_eq!(
+            snippet("I like Rust.\nAnd nom.", &["nom", "rust"]),
+            Some(("I like ".to_string(), "Rust", ". And nom.".to_string()))
+        );
+        let content = format!("{}rust{}", "a".repeat(40), "b".repeat(40));
+        assert_eq!(
+            snippet(content.as_str(), &["rust"]),
+            Some((
+                format!("...{}", "a".repeat(30)),
+                "rust",
+                format!("{}...", "b".repeat(30))
+            ))
+        );
+        assert_eq!(
+            snippet("日本語のrust記事", &["RUST"]),
+            Some(("日本語の".to_string(), "rust", "記事".to_string()))
+        );
+    }
+}
@@ -0,0 +1,487 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use super::build;
+use super::build::BuildArgs;
+use super::build::Builder;
+use super::build::Site;
+
+const LIVE_RELOAD_PATH: &str = "/_bbn/live-reload";
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>new EventSource("/_bbn/live-reload").addEventListener("reload", () => location.reload());</script>"#;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, clap::Args)]
+pub struct Command {
+    #[command(flatten)]
+    build_args: BuildArgs,
+    #[arg(
+        long = "port",
+        default_value_t = 8000,
+        help = "Port number to listen on"
+    )]
+    port: u16,
+    #[arg(
+        long = "prune",
+        help = "Removes the HTML, JSON and atom.xml files in the output directory that are not produced by the build (at startup and on every rebuild)"
+    )]
+    prune: bool,
+}
+
+impl Command {
+    pub fn handle(self) -> anyhow::Result<()> {
+        let builder = self.build_args.into_builder()?;
+        let site = builder.load_site()?;
+        let files = builder.build_site(&site, None, true, true, false, false)?;
+        if self.prune {
+            prune_out_dir(&builder, &files)?;
+        }
+
+        let out_dir = builder.out_dir().to_path_buf();
+        let listener = TcpListener::bind(("127.0.0.1", self.port))?;
+        println!(
+            "serving {} at http://{}/",
+            out_dir.display(),
+            listener.local_addr()?
+        );
+
+        let live_reload = LiveReload::default();
+        {
+            let live_reload = live_reload.clone();
+            let prune = self.prune;
+            thread::spawn(move || watch(builder, site, prune, live_reload));
+        }
+
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let out_dir = out_dir.clone();
+            let live_reload = live_reload.clone();
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, out_dir.as_path(), &live_reload) {
+                    eprintln!("{e}");
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+/// 開いているブラウザーに再読み込みを通知する (Server-Sent Events)
+#[derive(Clone, Default)]
+struct LiveReload(Arc<Mutex<Vec<mpsc::Sender<()>>>>);
+
+impl LiveReload {
+    fn subscribe(&self) -> mpsc::Receiver<()> {
+        let (sender, receiver) = mpsc::channel();
+        self.0.lock().unwrap().push(sender);
+        receiver
+    }
+
+    fn notify(&self) {
+        // 切断されたものは取り除く
+        self.0
+            .lock()
+            .unwrap()
+            .retain(|sender| sender.send(()).is_ok());
+    }
+}
+
+/// data_dir (と theme_dir) を監視し、変更されたら再ビルドする
+///
+/// JSON は build manifest により変更されたエントリーのものだけを書き込む。
+/// HTML は変更されたエントリーに関係するページだけを書き込む (theme_dir の変更ではすべて)。
+/// `prune` のときは出力されなくなったページを削除する。
+fn watch(builder: Builder, mut site: Site, prune: bool, live_reload: LiveReload) {
+    let mut dirs = vec![builder.data_dir().to_path_buf()];
+    dirs.extend(builder.theme_dir().map(Path::to_path_buf));
+    let mut built = snapshot(&dirs).unwrap_or_default();
+    let mut prev = built.clone();
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let curr = match snapshot(&dirs) {
+            Ok(curr) => curr,
+            Err(e) => {
+                eprintln!("{e}");
+                continue;
+            }
+        };
+        // 書き込み中のものを読まないように、変更が落ち着いてから再ビルドする
+        let stable = curr == prev;
+        prev = curr;
+        if !stable || prev == built {
+            continue;
+        }
+        let theme_changed = builder
+            .theme_dir()
+            .is_some_and(|theme_dir| changed_in(theme_dir, &built, &prev));
+        built = prev.clone();
+        match rebuild(&builder, &site, theme_changed, prune) {
+            Ok(rebuilt) => {
+                site = rebuilt;
+                println!("rebuilt");
+                live_reload.notify();
+            }
+            Err(e) => eprintln!("build failed: {e:?}"),
+        }
+    }
+}
+
+fn rebuild(
+    builder: &Builder,
+    prev: &Site,
+    theme_changed: bool,
+    prune: bool,
+) -> anyhow::Result<Site> {
+    let site = builder.load_site()?;
+    let prev = (!theme_changed).then_some(prev);
+    let files = builder.build_site(&site, prev, true, true, false, false)?;
+    if prune {
+        prune_out_dir(builder, &files)?;
+    }
+    Ok(site)
+}
+
+fn prune_out_dir(builder: &Builder, files: &BTreeSet<PathBuf>) -> anyhow::Result<()> {
+    for path in build::prune(builder.out_dir(), files, false)? {
+        println!("removed: {}", path.display());
+    }
+    Ok(())
+}
+
+type Snapshot = BTreeMap<PathBuf, (SystemTime, u64)>;
+
+/// `dir` 内のファイルが追加・変更・削除されたか
+fn changed_in(dir: &Path, a: &Snapshot, b: &Snapshot) -> bool {
+    let a = a.iter().filter(|(path, _)| path.starts_with(dir));
+    let b = b.iter().filter(|(path, _)| path.starts_with(dir));
+    a.ne(b)
+}
+
+/// ファイルのパス -> (更新日時, サイズ)
+///
+/// "." で始まるもの (一時ファイルやジャーナル) は除く
+fn snapshot(dirs: &[PathBuf]) -> anyhow::Result<Snapshot> {
+    let mut files = BTreeMap::new();
+    let mut dirs = dirs.to_vec();
+    while let Some(dir) = dirs.pop() {
+        if !dir.is_dir() {
+            continue;
+        }
+        for dir_entry in fs::read_dir(dir.as_path())? {
+            let dir_entry = dir_entry?;
+            if dir_entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = dir_entry.path();
+            let metadata = fs::metadata(path.as_path())?;
+            if metadata.is_dir() {
+                dirs.push(path);
+            } else {
+                files.insert(path, (metadata.modified()?, metadata.len()));
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    out_dir: &Path,
+    live_reload: &LiveReload,
+) -> anyhow::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // ヘッダーは使わないので読み捨てる
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let mut stream = stream;
+    let mut parts = request_line.split_whitespace();
+    let (Some("GET"), Some(target)) = (parts.next(), parts.next()) else {
+        return write_response(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain; charset=utf-8",
+            b"method not allowed",
+        );
+    };
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+    if path == LIVE_RELOAD_PATH {
+        return serve_events(stream, live_reload);
+    }
+    match resolve(out_dir, path) {
+        None => write_response(
+            &mut stream,
+            "404 Not Found",
+            "text/plain; charset=utf-8",
+            b"not found",
+        ),
+        Some(file) => {
+            let content_type = content_type(file.as_path());
+            let mut body = fs::read(file.as_path())?;
+            if content_type.starts_with("text/html") {
+                body = inject_live_reload_script(&String::from_utf8_lossy(&body)).into_bytes();
+            }
+            write_response(&mut stream, "200 OK", content_type, &body)
+        }
+    }
+}
+
+fn serve_events(mut stream: TcpStream, live_reload: &LiveReload) -> anyhow::Result<()> {
+    stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+    )?;
+    stream.flush()?;
+    let receiver = live_reload.subscribe();
+    loop {
+        let message: &[u8] = match receiver.recv_timeout(Duration::from_secs(15)) {
+            Ok(()) => b"event: reload\ndata: \n\n",
+            // 切断を検知するために定期的にコメントを送る
+            Err(mpsc::RecvTimeoutError::Timeout) => b": ping\n\n",
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+        // ブラウザーが閉じられた場合は書き込みに失敗する
+        if stream
+            .write_all(message)
+            .and_then(|_| stream.flush())
+            .is_err()
+        {
+            return Ok(());
+        }
+    }
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> anyhow::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// build の write_html と同じ規則で URL のパスを out_dir 内のファイルに対応させる
+///
+/// - "/" -> "index.html"
+/// - "/2021/02/03/" -> "2021/02/03/index.html"
+/// - "/2021/02/03" -> "2021/02/03.html"
+fn resolve(out_dir: &Path, path: &str) -> Option<PathBuf> {
+    let path = percent_decode(path)?;
+    let mut file = out_dir.to_path_buf();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        if segment == "." || segment == ".." || segment.contains('\\') {
+            return None;
+        }
+        file.push(segment);
+    }
+    if path.ends_with('/') {
+        file.push("index.html");
+    } else if !file.is_file() {
+        let mut html = file.into_os_string();
+        html.push(".html");
+        file = PathBuf::from(html);
+    }
+    file.is_file().then_some(file)
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = vec![];
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("css") => "text/css; charset=utf-8",
+        Some("html") => "text/html; charset=utf-8",
+        Some("ico") => "image/x-icon",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("png") => "image/png",
+        Some("svg") => "image/svg+xml",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("xml") => "application/xml",
+        _ => "application/octet-stream",
+    }
+}
+
+fn inject_live_reload_script(html: &str) -> String {
+    match html.rfind("</body>") {
+        Some(index) => format!("{}{}{}", &html[..index], LIVE_RELOAD_SCRIPT, &html[index..]),
+        None => format!("{}{}", html, LIVE_RELOAD_SCRIPT),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn resolve_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let out_dir = temp_dir.path();
+        fs::create_dir_all(out_dir.join("2021/02/03"))?;
+        fs::create_dir_all(out_dir.join("tags/日記"))?;
+        fs::create_dir_all(out_dir.join("tags/a%2Fb"))?;
+        for file in [
+            "index.html",
+            "atom.xml",
+            "2021/02/03/index.html",
+            "2021/02/03.html",
+            "2021/02/03.json",
+            "tags/日記/index.html",
+            "tags/a%2Fb/index.html",
+        ] {
+            fs::write(out_dir.join(file), "")?;
+        }
+
+        assert_eq!(resolve(out_dir, "/"), Some(out_dir.join("index.html")));
+        assert_eq!(
+            resolve(out_dir, "/atom.xml"),
+            Some(out_dir.join("atom.xml"))
+        );
+        assert_eq!(
+            resolve(out_dir, "/2021/02/03/"),
+            Some(out_dir.join("2021/02/03/index.html"))
+        );
+        assert_eq!(
+            resolve(out_dir, "/2021/02/03"),
+            Some(out_dir.join("2021/02/03.html"))
+        );
+        assert_eq!(
+            resolve(out_dir, "/2021/02/03.json"),
+            Some(out_dir.join("2021/02/03.json"))
+        );
+        assert_eq!(
+            resolve(out_dir, "/tags/%E6%97%A5%E8%A8%98/"),
+            Some(out_dir.join("tags/日記/index.html"))
+        );
+        assert_eq!(
+            resolve(out_dir, "/tags/a%252Fb/"),
+            Some(out_dir.join("tags/a%2Fb/index.html"))
+        );
+        assert_eq!(resolve(out_dir, "/2021/02/04/"), None);
+        assert_eq!(resolve(out_dir, "/2021/"), None);
+        assert_eq!(resolve(out_dir, "/../index.html"), None);
+        assert_eq!(resolve(out_dir, "/%2E%2E/index.html"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn percent_decode_test() {
+        assert_eq!(percent_decode("/a%20b/"), Some("/a b/".to_string()));
+        assert_eq!(percent_decode("/%E6%97%A5"), Some("/日".to_string()));
+        assert_eq!(percent_decode("/%E6"), None);
+        assert_eq!(percent_decode("/%zz"), None);
+        assert_eq!(percent_decode("/%2"), None);
+    }
+
+    #[test]
+    fn content_type_test() {
+        assert_eq!(
+            content_type(Path::new("index.html")),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(content_type(Path::new("posts.json")), "application/json");
+        assert_eq!(
+            content_type(Path::new("styles/index.css")),
+            "text/css; charset=utf-8"
+        );
+        assert_eq!(content_type(Path::new("a")), "application/octet-stream");
+    }
+
+    #[test]
+    fn inject_live_reload_script_test() {
+        assert_eq!(
+            inject_live_reload_script("<body><p>a</p></body>\n</html>"),
+            format!("<body><p>a</p>{}</body>\n</html>", LIVE_RELOAD_SCRIPT)
+        );
+        assert_eq!(
+            inject_live_reload_script("<p>a</p>"),
+            format!("<p>a</p>{}", LIVE_RELOAD_SCRIPT)
+        );
+    }
+
+    #[test]
+    fn live_reload_test() {
+        let live_reload = LiveReload::default();
+        let receiver1 = live_reload.subscribe();
+        let receiver2 = live_reload.subscribe();
+        drop(receiver2);
+        live_reload.notify();
+        assert_eq!(receiver1.try_recv(), Ok(()));
+        assert_eq!(live_reload.0.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn snapshot_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        fs::create_dir_all(data_dir.join("2021").join("02"))?;
+        fs::write(data_dir.join("2021").join("02").join("2021-02-03.md"), "a")?;
+        fs::write(data_dir.join(".bbn-journal.json"), "{}")?;
+        let dirs = vec![data_dir.clone(), temp_dir.path().join("not_found")];
+        let files = snapshot(&dirs)?;
+        assert_eq!(
+            files.keys().collect::<Vec<&PathBuf>>(),
+            vec![&data_dir.join("2021").join("02").join("2021-02-03.md")]
+        );
+
+        fs::write(data_dir.join("2021").join("02").join("2021-02-03.md"), "ab")?;
+        assert_ne!(snapshot(&dirs)?, files);
+        Ok(())
+    }
+
+    #[test]
+    fn changed_in_test() {
+        let time = SystemTime::UNIX_EPOCH;
+        let a = Snapshot::from([
+            (PathBuf::from("/data/a.md"), (time, 1)),
+            (PathBuf::from("/theme/a.html"), (time, 1)),
+        ]);
+        let mut b = a.clone();
+        b.insert(PathBuf::from("/data/b.md"), (time, 1));
+        assert!(!changed_in(Path::new("/theme"), &a, &b));
+        assert!(changed_in(Path::new("/data"), &a, &b));
+        b.insert(PathBuf::from("/theme/a.html"), (time, 2));
+        assert!(changed_in(Path::new("/theme"), &a, &b));
+        b.remove(Path::new("/theme/a.html"));
+        assert!(changed_in(Path::new("/theme"), &a, &b));
+    }
+}
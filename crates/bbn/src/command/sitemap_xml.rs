@@ -14,6 +14,7 @@ use sitemap_xml_writer::SitemapWriter;
 use sitemap_xml_writer::Url;
 
 use crate::config_repository::ConfigRepository;
+use crate::site_config::SiteConfig;
 
 impl Command {
     pub fn handle(self) -> anyhow::Result<()> {
@@ -27,6 +28,7 @@ fn run(out_dir: PathBuf) -> anyhow::Result<()> {
         .load()
         .context("The configuration file does not found. Use `bbn config` command.")?;
     let data_dir = config.data_dir().to_path_buf();
+    let site_config = SiteConfig::from(&config);
 
     let bbn_repository = BbnRepository::new(data_dir);
     let query = Query::try_from("date:1970-01-01/9999-12-31")?;
@@ -42,12 +44,8 @@ fn run(out_dir: PathBuf) -> anyhow::Result<()> {
             .find_meta_by_id(&entry_id)?
             .context("meta not found")?;
 
-        let date = entry_id.date();
-        let yyyy = date.year().to_string();
-        let mm = date.month().to_string();
-        let dd = date.day_of_month().to_string();
         writer.write(
-            Url::loc(format!("https://blog.bouzuya.net/{yyyy}/{mm}/{dd}/").as_str())?
+            Url::loc(site_config.entry_url(&entry_id).as_str())?
                 .lastmod(meta.pubdate.to_string().as_str())?,
         )?;
     }
@@ -1,5 +1,6 @@
 use crate::config_repository::ConfigRepository;
 use crate::date_like::DateLike;
+use crate::site_config::SiteConfig;
 
 #[derive(Debug, clap::Args)]
 pub struct Command {
@@ -46,12 +47,22 @@ struct MetaJson {
     url: String,
 }
 
-fn print_json_content(_: EntryId, _: EntryMeta, entry_content: String) -> anyhow::Result<()> {
+fn print_json_content(
+    _: &SiteConfig,
+    _: EntryId,
+    _: EntryMeta,
+    entry_content: String,
+) -> anyhow::Result<()> {
     println!("{}", serde_json::to_string(&entry_content)?);
     Ok(())
 }
 
-fn print_json_meta(entry_id: EntryId, entry_meta: EntryMeta, _: String) -> anyhow::Result<()> {
+fn print_json_meta(
+    site_config: &SiteConfig,
+    entry_id: EntryId,
+    entry_meta: EntryMeta,
+    _: String,
+) -> anyhow::Result<()> {
     println!(
         "{}",
         serde_json::to_string(&MetaJson {
@@ -59,13 +70,14 @@ fn print_json_meta(entry_id: EntryId, entry_meta: EntryMeta, _: String) -> anyho
             pubdate: entry_meta.pubdate.to_string(),
             tags: entry_meta.tags,
             title: entry_meta.title,
-            url: entry_url(&entry_id)
+            url: site_config.entry_url(&entry_id)
         })?
     );
     Ok(())
 }
 
 fn print_json_content_meta(
+    site_config: &SiteConfig,
     entry_id: EntryId,
     entry_meta: EntryMeta,
     entry_content: String,
@@ -78,18 +90,24 @@ fn print_json_content_meta(
             pubdate: entry_meta.pubdate.to_string(),
             tags: entry_meta.tags,
             title: entry_meta.title,
-            url: entry_url(&entry_id)
+            url: site_config.entry_url(&entry_id)
         })?
     );
     Ok(())
 }
 
-fn print_text_content(_: EntryId, _: EntryMeta, entry_content: String) -> anyhow::Result<()> {
+fn print_text_content(
+    _: &SiteConfig,
+    _: EntryId,
+    _: EntryMeta,
+    entry_content: String,
+) -> anyhow::Result<()> {
     println!("{entry_content}");
     Ok(())
 }
 
 fn print_text_content_meta(
+    site_config: &SiteConfig,
     entry_id: EntryId,
     entry_meta: EntryMeta,
     entry_content: String,
@@ -102,13 +120,18 @@ fn print_text_content_meta(
             .map(|s| format!(" {s}"))
             .unwrap_or_default(),
         entry_meta.title,
-        entry_url(&entry_id),
+        site_config.entry_url(&entry_id),
         entry_content
     );
     Ok(())
 }
 
-fn print_text_meta(entry_id: EntryId, entry_meta: EntryMeta, _: String) -> anyhow::Result<()> {
+fn print_text_meta(
+    site_config: &SiteConfig,
+    entry_id: EntryId,
+    entry_meta: EntryMeta,
+    _: String,
+) -> anyhow::Result<()> {
     println!(
         "{}{} {} <{}>",
         entry_id.date(),
@@ -117,18 +140,11 @@ fn print_text_meta(entry_id: EntryId, entry_meta: EntryMeta, _: String) -> anyho
             .map(|s| format!(" {s}"))
             .unwrap_or_default(),
         entry_meta.title,
-        entry_url(&entry_id)
+        site_config.entry_url(&entry_id)
     );
     Ok(())
 }
 
-fn entry_url(entry_id: &EntryId) -> String {
-    format!(
-        "https://blog.bouzuya.net/{}/",
-        entry_id.date().to_string().replace('-', "/")
-    )
-}
-
 impl Command {
     pub fn handle(self) -> anyhow::Result<()> {
         view(self.date_like, self.content, self.json, self.meta, self.web)
@@ -148,6 +164,7 @@ fn view(
         .load()
         .context("The configuration file does not found. Use `bbn config` command.")?;
     let data_dir = config.data_dir().to_path_buf();
+    let site_config = SiteConfig::from(&config);
 
     let repository = BbnRepository::new(data_dir);
     let entry_id = repository.find_id_by_date(date)?;
@@ -161,7 +178,7 @@ fn view(
         })
         .context("not found")?;
     if web {
-        open::that(entry_url(&entry_id))?;
+        open::that(site_config.entry_url(&entry_id))?;
         return Ok(());
     }
     let print = match (content, meta, json) {
@@ -172,5 +189,5 @@ fn view(
         (false, false, true) | (true, false, true) => print_json_content,
         (true, true, true) => print_json_content_meta,
     };
-    print(entry_id, entry_meta, entry_content)
+    print(&site_config, entry_id, entry_meta, entry_content)
 }
@@ -1,12 +1,20 @@
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::path::PathBuf;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Config {
+    base_url: Option<String>,
     data_dir: PathBuf,
+    hatena_blog_categories: BTreeMap<String, String>,
     hatena_blog_data_file: PathBuf,
+    lang: Option<String>,
     link_completion_rules_file: Option<PathBuf>,
     out_dir: Option<PathBuf>,
+    page_size: Option<NonZeroUsize>,
+    site_title: Option<String>,
+    theme_dir: Option<PathBuf>,
 }
 
 impl Config {
@@ -17,21 +25,95 @@ impl Config {
         out_dir: Option<PathBuf>,
     ) -> Self {
         Self {
+            base_url: None,
             data_dir,
+            hatena_blog_categories: BTreeMap::new(),
             hatena_blog_data_file,
+            lang: None,
             link_completion_rules_file,
             out_dir,
+            page_size: None,
+            site_title: None,
+            theme_dir: None,
         }
     }
 
+    pub fn with_base_url(self, base_url: Option<String>) -> Self {
+        Self { base_url, ..self }
+    }
+
+    pub fn with_data_dir(self, data_dir: PathBuf) -> Self {
+        Self { data_dir, ..self }
+    }
+
+    pub fn with_hatena_blog_categories(
+        self,
+        hatena_blog_categories: BTreeMap<String, String>,
+    ) -> Self {
+        Self {
+            hatena_blog_categories,
+            ..self
+        }
+    }
+
+    pub fn with_hatena_blog_data_file(self, hatena_blog_data_file: PathBuf) -> Self {
+        Self {
+            hatena_blog_data_file,
+            ..self
+        }
+    }
+
+    pub fn with_lang(self, lang: Option<String>) -> Self {
+        Self { lang, ..self }
+    }
+
+    pub fn with_link_completion_rules_file(
+        self,
+        link_completion_rules_file: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            link_completion_rules_file,
+            ..self
+        }
+    }
+
+    pub fn with_out_dir(self, out_dir: Option<PathBuf>) -> Self {
+        Self { out_dir, ..self }
+    }
+
+    pub fn with_page_size(self, page_size: Option<NonZeroUsize>) -> Self {
+        Self { page_size, ..self }
+    }
+
+    pub fn with_site_title(self, site_title: Option<String>) -> Self {
+        Self { site_title, ..self }
+    }
+
+    pub fn with_theme_dir(self, theme_dir: Option<PathBuf>) -> Self {
+        Self { theme_dir, ..self }
+    }
+
+    pub fn base_url(&self) -> Option<&str> {
+        self.base_url.as_deref()
+    }
+
     pub fn data_dir(&self) -> &Path {
         self.data_dir.as_path()
     }
 
+    /// tag -> Hatena Blog の category
+    pub fn hatena_blog_categories(&self) -> &BTreeMap<String, String> {
+        &self.hatena_blog_categories
+    }
+
     pub fn hatena_blog_data_file(&self) -> &Path {
         self.hatena_blog_data_file.as_path()
     }
 
+    pub fn lang(&self) -> Option<&str> {
+        self.lang.as_deref()
+    }
+
     pub fn link_completion_rules_file(&self) -> Option<&Path> {
         self.link_completion_rules_file.as_deref()
     }
@@ -39,6 +121,19 @@ impl Config {
     pub fn out_dir(&self) -> Option<&Path> {
         self.out_dir.as_deref()
     }
+
+    /// entry-list のページあたりのエントリー数
+    pub fn page_size(&self) -> Option<NonZeroUsize> {
+        self.page_size
+    }
+
+    pub fn site_title(&self) -> Option<&str> {
+        self.site_title.as_deref()
+    }
+
+    pub fn theme_dir(&self) -> Option<&Path> {
+        self.theme_dir.as_deref()
+    }
 }
 
 #[cfg(test)]
@@ -65,6 +160,7 @@ mod tests {
             hatena_blog_data_file.as_path()
         );
         assert_eq!(config.link_completion_rules_file(), None);
+        assert!(config.hatena_blog_categories().is_empty());
         assert_eq!(config.out_dir(), None);
         assert_eq!(config.clone(), config);
 
@@ -84,7 +180,37 @@ mod tests {
             Some(link_completion_rules_file.as_path())
         );
         assert_eq!(config.out_dir(), Some(out_dir.as_path()));
+        assert_eq!(config.base_url(), None);
+        assert_eq!(config.lang(), None);
+        assert_eq!(config.page_size(), None);
+        assert_eq!(config.site_title(), None);
+        assert_eq!(config.theme_dir(), None);
         assert_eq!(config.clone(), config);
+
+        let config = config
+            .with_base_url(Some("https://example.com".to_string()))
+            .with_hatena_blog_categories(BTreeMap::from([(
+                "tag1".to_string(),
+                "category1".to_string(),
+            )]))
+            .with_lang(Some("en".to_string()))
+            .with_page_size(NonZeroUsize::new(20))
+            .with_site_title(Some("example".to_string()))
+            .with_theme_dir(Some(PathBuf::from("theme")));
+        assert_eq!(config.data_dir(), data_dir.as_path());
+        assert_eq!(config.out_dir(), Some(out_dir.as_path()));
+        assert_eq!(config.base_url(), Some("https://example.com"));
+        assert_eq!(
+            config.hatena_blog_categories(),
+            &BTreeMap::from([("tag1".to_string(), "category1".to_string())])
+        );
+        assert_eq!(config.lang(), Some("en"));
+        assert_eq!(config.page_size(), NonZeroUsize::new(20));
+        assert_eq!(config.site_title(), Some("example"));
+        assert_eq!(config.theme_dir(), Some(PathBuf::from("theme").as_path()));
+
+        let config = config.with_base_url(None);
+        assert_eq!(config.base_url(), None);
         Ok(())
     }
 }
@@ -1,5 +1,7 @@
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -13,10 +15,22 @@ use crate::credentials::Credentials;
 
 #[derive(Debug, Deserialize, Serialize)]
 struct ConfigJson {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    base_url: Option<String>,
     data_dir: PathBuf,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    hatena_blog_categories: BTreeMap<String, String>,
     hatena_blog_data_file: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lang: Option<String>,
     link_completion_rules_file: Option<PathBuf>,
     out_dir: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    page_size: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    site_title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    theme_dir: Option<PathBuf>,
 }
 
 impl From<ConfigJson> for Config {
@@ -27,18 +41,30 @@ impl From<ConfigJson> for Config {
             config_json.link_completion_rules_file,
             config_json.out_dir,
         )
+        .with_base_url(config_json.base_url)
+        .with_hatena_blog_categories(config_json.hatena_blog_categories)
+        .with_lang(config_json.lang)
+        .with_page_size(config_json.page_size)
+        .with_site_title(config_json.site_title)
+        .with_theme_dir(config_json.theme_dir)
     }
 }
 
 impl From<Config> for ConfigJson {
     fn from(config: Config) -> Self {
         Self {
+            base_url: config.base_url().map(|it| it.to_string()),
             data_dir: config.data_dir().to_path_buf(),
+            hatena_blog_categories: config.hatena_blog_categories().clone(),
             hatena_blog_data_file: config.hatena_blog_data_file().to_path_buf(),
+            lang: config.lang().map(|it| it.to_string()),
             link_completion_rules_file: config
                 .link_completion_rules_file()
                 .map(|it| it.to_path_buf()),
             out_dir: config.out_dir().map(|it| it.to_path_buf()),
+            page_size: config.page_size(),
+            site_title: config.site_title().map(|it| it.to_string()),
+            theme_dir: config.theme_dir().map(|it| it.to_path_buf()),
         }
     }
 }
@@ -230,4 +256,47 @@ mod tests {
             },
         )
     }
+
+    #[test]
+    fn repository_site_config_test() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let data_dir = temp_dir.path().join("data");
+        let hatena_blog_data_file = temp_dir.path().join("hatena_blog.db");
+        let config_dir = temp_dir.path().join("config");
+        let config_file = config_dir.join("config.json");
+
+        let config = Config::new(data_dir.clone(), hatena_blog_data_file.clone(), None, None)
+            .with_base_url(Some("https://example.com".to_string()))
+            .with_hatena_blog_categories(BTreeMap::from([(
+                "tag1".to_string(),
+                "category1".to_string(),
+            )]))
+            .with_lang(Some("en".to_string()))
+            .with_site_title(Some("example".to_string()))
+            .with_theme_dir(Some(PathBuf::from("/theme")));
+
+        temp_env::with_var(
+            "BBN_TEST_CONFIG_DIR",
+            Some(config_dir.to_str().context("config dir is not UTF-8")?),
+            || -> anyhow::Result<()> {
+                let repository = ConfigRepository::new()?;
+                repository.save(config.clone())?;
+                let loaded = repository.load()?;
+                assert_eq!(loaded, config);
+
+                let saved = fs::read_to_string(config_file.as_path())?;
+                assert_eq!(
+                    saved,
+                    format!(
+                        r#"{{"base_url":"https://example.com","data_dir":"{}","hatena_blog_categories":{{"tag1":"category1"}},"hatena_blog_data_file":"{}","lang":"en","link_completion_rules_file":null,"out_dir":null,"site_title":"example","theme_dir":"/theme"}}"#,
+                        data_dir.to_str().context("data_dir.to_str()")?,
+                        hatena_blog_data_file
+                            .to_str()
+                            .context("hatena_blog_data_file.to_str()")?,
+                    )
+                );
+                Ok(())
+            },
+        )
+    }
 }
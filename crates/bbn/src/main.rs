@@ -4,6 +4,7 @@ mod config;
 mod config_repository;
 mod credentials;
 mod date_like;
+mod site_config;
 
 pub use bbn_date_range::bbn_date_range;
 
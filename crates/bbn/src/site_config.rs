@@ -0,0 +1,147 @@
+use std::num::NonZeroUsize;
+
+use bbn_data::EntryId;
+
+use crate::config::Config;
+
+const DEFAULT_BASE_URL: &str = "https://blog.bouzuya.net";
+const DEFAULT_LANG: &str = "ja";
+const DEFAULT_PAGE_SIZE: NonZeroUsize = NonZeroUsize::new(9).unwrap();
+const DEFAULT_SITE_TITLE: &str = "blog.bouzuya.net";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SiteConfig {
+    base_url: String,
+    lang: String,
+    page_size: NonZeroUsize,
+    site_title: String,
+}
+
+impl SiteConfig {
+    pub fn new(base_url: String, lang: String, site_title: String) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            lang,
+            page_size: DEFAULT_PAGE_SIZE,
+            site_title,
+        }
+    }
+
+    pub fn with_page_size(self, page_size: NonZeroUsize) -> Self {
+        Self { page_size, ..self }
+    }
+
+    /// e.g. "https://blog.bouzuya.net" (without the trailing slash)
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_str()
+    }
+
+    pub fn lang(&self) -> &str {
+        self.lang.as_str()
+    }
+
+    /// entry-list のページあたりのエントリー数
+    pub fn page_size(&self) -> NonZeroUsize {
+        self.page_size
+    }
+
+    pub fn site_title(&self) -> &str {
+        self.site_title.as_str()
+    }
+
+    /// `path` は "/" で始まる想定
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    pub fn entry_url(&self, entry_id: &EntryId) -> String {
+        self.url(&format!(
+            "/{}/",
+            entry_id.date().to_string().replace('-', "/")
+        ))
+    }
+}
+
+impl Default for SiteConfig {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_BASE_URL.to_string(),
+            DEFAULT_LANG.to_string(),
+            DEFAULT_SITE_TITLE.to_string(),
+        )
+    }
+}
+
+impl From<&Config> for SiteConfig {
+    fn from(config: &Config) -> Self {
+        Self::new(
+            config.base_url().unwrap_or(DEFAULT_BASE_URL).to_string(),
+            config.lang().unwrap_or(DEFAULT_LANG).to_string(),
+            config
+                .site_title()
+                .unwrap_or(DEFAULT_SITE_TITLE)
+                .to_string(),
+        )
+        .with_page_size(config.page_size().unwrap_or(DEFAULT_PAGE_SIZE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn default_test() {
+        let site_config = SiteConfig::default();
+        assert_eq!(site_config.base_url(), "https://blog.bouzuya.net");
+        assert_eq!(site_config.lang(), "ja");
+        assert_eq!(site_config.site_title(), "blog.bouzuya.net");
+        assert_eq!(site_config.page_size().get(), 9);
+    }
+
+    #[test]
+    fn from_config_test() {
+        let config = Config::new(
+            PathBuf::from("data"),
+            PathBuf::from("hatena_blog.db"),
+            None,
+            None,
+        );
+        assert_eq!(SiteConfig::from(&config), SiteConfig::default());
+
+        let config = config
+            .with_base_url(Some("https://example.com/".to_string()))
+            .with_lang(Some("en".to_string()))
+            .with_page_size(NonZeroUsize::new(20))
+            .with_site_title(Some("example".to_string()));
+        assert_eq!(
+            SiteConfig::from(&config),
+            SiteConfig::new(
+                "https://example.com".to_string(),
+                "en".to_string(),
+                "example".to_string()
+            )
+            .with_page_size(NonZeroUsize::new(20).unwrap())
+        );
+    }
+
+    #[test]
+    fn url_test() -> anyhow::Result<()> {
+        let site_config = SiteConfig::new(
+            "https://example.com/".to_string(),
+            "en".to_string(),
+            "example".to_string(),
+        );
+        assert_eq!(site_config.base_url(), "https://example.com");
+        assert_eq!(site_config.url("/"), "https://example.com/");
+        assert_eq!(site_config.url("/atom.xml"), "https://example.com/atom.xml");
+        assert_eq!(
+            site_config.entry_url(&EntryId::from_str("2021-02-03-id")?),
+            "https://example.com/2021/02/03/"
+        );
+        Ok(())
+    }
+}
@@ -60,6 +60,179 @@ fn list_test() {
         ));
 }
 
+#[test]
+fn list_tag_test() {
+    let temp_dir = tempdir().unwrap();
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path()).unwrap();
+    fs::write(
+        entry_dir.join("2021-02-03.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":["rust"],"title":"TITLE1"}"#,
+    )
+    .unwrap();
+    fs::write(
+        entry_dir.join("2021-02-04.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-04T00:00:00+09:00","tags":["diary"],"title":"TITLE2"}"#,
+    )
+    .unwrap();
+    let entry_dir = data_dir.join("2022").join("02");
+    fs::create_dir_all(entry_dir.as_path()).unwrap();
+    fs::write(
+        entry_dir.join("2022-02-03.json"),
+        r#"{"minutes":5,"pubdate":"2022-02-03T00:00:00+09:00","tags":["rust"],"title":"TITLE3"}"#,
+    )
+    .unwrap();
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+
+    Command::cargo_bin("bbn")
+        .unwrap()
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(hatena_blog_data_file)
+        .env(
+            "BBN_TEST_CONFIG_DIR",
+            config_dir.as_os_str().to_str().unwrap(),
+        )
+        .assert()
+        .success();
+    Command::cargo_bin("bbn")
+        .unwrap()
+        .arg("list")
+        .arg("tag:rust")
+        .env(
+            "BBN_TEST_CONFIG_DIR",
+            config_dir.as_os_str().to_str().unwrap(),
+        )
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq(
+            b"2022-02-03 TITLE3 <https://blog.bouzuya.net/2022/02/03/>\n2021-02-03 TITLE1 <https://blog.bouzuya.net/2021/02/03/>\n" as &[u8],
+        ));
+    Command::cargo_bin("bbn")
+        .unwrap()
+        .arg("list")
+        .arg("date:2021")
+        .arg("tag:rust")
+        .env(
+            "BBN_TEST_CONFIG_DIR",
+            config_dir.as_os_str().to_str().unwrap(),
+        )
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq(
+            b"2021-02-03 TITLE1 <https://blog.bouzuya.net/2021/02/03/>\n" as &[u8],
+        ));
+    Command::cargo_bin("bbn")
+        .unwrap()
+        .arg("list")
+        .arg("--json")
+        .arg("(tag:rust OR tag:diary)")
+        .arg("-date:2021-02-03")
+        .env(
+            "BBN_TEST_CONFIG_DIR",
+            config_dir.as_os_str().to_str().unwrap(),
+        )
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq(
+            b"[{\"date\":\"2022-02-03\",\"title\":\"TITLE3\",\"url\":\"https://blog.bouzuya.net/2022/02/03/\"},{\"date\":\"2021-02-04\",\"title\":\"TITLE2\",\"url\":\"https://blog.bouzuya.net/2021/02/04/\"}]\n" as &[u8],
+        ));
+    Command::cargo_bin("bbn")
+        .unwrap()
+        .arg("list")
+        .arg("(tag:rust")
+        .env(
+            "BBN_TEST_CONFIG_DIR",
+            config_dir.as_os_str().to_str().unwrap(),
+        )
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("parse error at column 10"));
+    Command::cargo_bin("bbn")
+        .unwrap()
+        .arg("list")
+        .arg("tag:")
+        .env(
+            "BBN_TEST_CONFIG_DIR",
+            config_dir.as_os_str().to_str().unwrap(),
+        )
+        .assert()
+        .failure();
+}
+
+#[test]
+fn list_with_base_url_test() {
+    let temp_dir = tempdir().unwrap();
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path()).unwrap();
+    let data20210203 = entry_dir.join("2021-02-03-TITLE.json");
+    fs::write(
+        data20210203,
+        r#"{"minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":[],"title":"TITLE1"}"#,
+    )
+    .unwrap();
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+
+    Command::cargo_bin("bbn")
+        .unwrap()
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(hatena_blog_data_file)
+        .env(
+            "BBN_TEST_CONFIG_DIR",
+            config_dir.as_os_str().to_str().unwrap(),
+        )
+        .assert()
+        .success();
+    Command::cargo_bin("bbn")
+        .unwrap()
+        .arg("config")
+        .arg("set")
+        .arg("base-url")
+        .arg("https://example.com/")
+        .env(
+            "BBN_TEST_CONFIG_DIR",
+            config_dir.as_os_str().to_str().unwrap(),
+        )
+        .assert()
+        .success();
+    Command::cargo_bin("bbn")
+        .unwrap()
+        .arg("config")
+        .arg("get")
+        .arg("base-url")
+        .env(
+            "BBN_TEST_CONFIG_DIR",
+            config_dir.as_os_str().to_str().unwrap(),
+        )
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq(b"https://example.com/\n" as &[u8]));
+    Command::cargo_bin("bbn")
+        .unwrap()
+        .arg("list")
+        .arg("date:--02-03")
+        .env(
+            "BBN_TEST_CONFIG_DIR",
+            config_dir.as_os_str().to_str().unwrap(),
+        )
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq(
+            b"2021-02-03 TITLE1 <https://example.com/2021/02/03/>\n" as &[u8],
+        ));
+}
+
 #[test]
 fn view_test() {
     let temp_dir = tempdir().unwrap();
@@ -0,0 +1,88 @@
+use std::fs;
+
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_bbn_build_atom() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path())?;
+    let meta1 = entry_dir.join("2021-02-03-TITLE.json");
+    fs::write(
+        meta1,
+        r#"{"minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":[],"title":"TITLE1"}"#,
+    )?;
+    let content1 = entry_dir.join("2021-02-03-TITLE.md");
+    fs::write(content1, "hello")?;
+    let meta2 = entry_dir.join("2021-02-04.json");
+    fs::write(
+        meta2,
+        r#"{"minutes":5,"pubdate":"2021-02-04T00:00:00+09:00","tags":["tag1"],"title":"A & B"}"#,
+    )?;
+    let content2 = entry_dir.join("2021-02-04.md");
+    fs::write(content2, "good bye")?;
+
+    let out_dir = temp_dir.path().join("out");
+    fs::create_dir_all(out_dir.as_path())?;
+
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(&data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(&hatena_blog_data_file)
+        .arg("--out-dir")
+        .arg(out_dir.as_path())
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    Command::cargo_bin("bbn")?
+        .arg("build")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    let atom_xml = fs::read_to_string(out_dir.join("atom.xml"))?;
+    assert!(atom_xml.starts_with(r#"<?xml version="1.0" encoding="utf-8"?>"#));
+    assert!(atom_xml.contains(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#));
+    assert!(atom_xml.contains("<title>blog.bouzuya.net</title>"));
+    assert!(atom_xml.contains("<updated>2021-02-04T00:00:00+09:00</updated>"));
+    assert!(atom_xml.contains(
+        r#"<link rel="self" type="application/atom+xml" href="https://blog.bouzuya.net/atom.xml"/>"#
+    ));
+    // 新しいエントリが先頭
+    let entry1 = atom_xml
+        .find("<id>https://blog.bouzuya.net/2021/02/03/</id>")
+        .expect("entry 2021-02-03");
+    let entry2 = atom_xml
+        .find("<id>https://blog.bouzuya.net/2021/02/04/</id>")
+        .expect("entry 2021-02-04");
+    assert!(entry2 < entry1);
+    // タイトルと本文はエスケープされること
+    assert!(atom_xml.contains("<title>2021-02-04 A &#38; B</title>"));
+    assert!(atom_xml.contains(r#"<content type="html">&#60;p&#62;hello&#60;/p&#62;"#));
+    assert!(atom_xml.contains("<published>2021-02-03T00:00:00+09:00</published>"));
+
+    // --atom-entries で件数を指定できること
+    Command::cargo_bin("bbn")?
+        .arg("build")
+        .arg("--html")
+        .arg("--atom-entries")
+        .arg("1")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+    let atom_xml = fs::read_to_string(out_dir.join("atom.xml"))?;
+    assert_eq!(atom_xml.matches("<entry>").count(), 1);
+    assert!(atom_xml.contains("<id>https://blog.bouzuya.net/2021/02/04/</id>"));
+    assert!(!atom_xml.contains("<id>https://blog.bouzuya.net/2021/02/03/</id>"));
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use std::fs;
+
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_bbn_edit() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path())?;
+    let meta_path = entry_dir.join("2021-02-03.json");
+    let content_path = entry_dir.join("2021-02-03.md");
+    let meta = r#"{"hatena_blog_entry_id":"123","minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":["rust"],"title":"TITLE1"}"#;
+    fs::write(meta_path.as_path(), meta)?;
+    fs::write(content_path.as_path(), "hello\n")?;
+
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(&data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(&hatena_blog_data_file)
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    // meta と content をまとめて編集できる
+    Command::cargo_bin("bbn")?
+        .arg("edit")
+        .arg("2021-02-03")
+        .arg("--add-minutes")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .env(
+            "EDITOR",
+            "sed -i -e s/hello/bye/ -e s/^title:.*/title:TITLE2/",
+        )
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq("2021-02-03\n"));
+    assert_eq!(fs::read_to_string(content_path.as_path())?, "bye\n");
+    let meta_json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&meta_path)?)?;
+    assert_eq!(meta_json["hatena_blog_entry_id"], "123");
+    assert_eq!(meta_json["minutes"], 6);
+    assert_eq!(meta_json["pubdate"], "2021-02-03T00:00:00+09:00");
+    assert_eq!(meta_json["tags"], serde_json::json!(["rust"]));
+    assert_eq!(meta_json["title"], "TITLE2");
+
+    // 不正な front matter は保存しない
+    let meta = fs::read_to_string(meta_path.as_path())?;
+    Command::cargo_bin("bbn")?
+        .arg("edit")
+        .arg("2021-02-03")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .env(
+            "EDITOR",
+            "sed -i -e s/bye/hello/ -e s/^minutes:.*/minutes:x/",
+        )
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("invalid minutes: x"));
+    assert_eq!(fs::read_to_string(meta_path.as_path())?, meta);
+    assert_eq!(fs::read_to_string(content_path.as_path())?, "bye\n");
+
+    // エディタが失敗したら保存しない
+    Command::cargo_bin("bbn")?
+        .arg("edit")
+        .arg("2021-02-03")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .env("EDITOR", "false")
+        .assert()
+        .failure();
+    assert_eq!(fs::read_to_string(meta_path.as_path())?, meta);
+
+    // pubdate を更新できる
+    Command::cargo_bin("bbn")?
+        .arg("edit")
+        .arg("2021-02-03")
+        .arg("--bump-pubdate")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .env("EDITOR", "true")
+        .assert()
+        .success();
+    let meta_json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&meta_path)?)?;
+    assert_ne!(meta_json["pubdate"], "2021-02-03T00:00:00+09:00");
+
+    // 変更がなければ保存しない
+    Command::cargo_bin("bbn")?
+        .arg("edit")
+        .arg("2021-02-03")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .env("EDITOR", "true")
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq("2021-02-03 is not changed\n"));
+
+    Command::cargo_bin("bbn")?
+        .arg("edit")
+        .arg("2021-02-04")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .env("EDITOR", "true")
+        .assert()
+        .failure();
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use std::fs;
+
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_bbn_fsck() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path())?;
+    fs::write(
+        entry_dir.join("2021-02-03.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":[],"title":"TITLE1"}"#,
+    )?;
+    fs::write(entry_dir.join("2021-02-03.md"), "hello")?;
+
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(&data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(&hatena_blog_data_file)
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    Command::cargo_bin("bbn")?
+        .arg("fsck")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq(""));
+
+    // .md がない・別の月のディレクトリにある・短いファイル名
+    fs::write(
+        entry_dir.join("2021-03-04.json"),
+        r#"{"minutes":5,"pubdate":"2021-03-04T00:00:00+09:00","tags":[],"title":"TITLE2"}"#,
+    )?;
+    fs::write(entry_dir.join("2021.json"), "{}")?;
+
+    let misplaced = entry_dir.join("2021-03-04.json");
+    let expected = data_dir.join("2021").join("03").join("2021-03-04.json");
+    Command::cargo_bin("bbn")?
+        .arg("fsck")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .failure()
+        .stdout(predicates::str::contains(format!(
+            "{}: misplaced (expected: {})",
+            misplaced.display(),
+            expected.display()
+        )))
+        .stdout(predicates::str::contains(format!(
+            "{}: content file not found",
+            misplaced.display()
+        )))
+        .stdout(predicates::str::contains(format!(
+            "{}: invalid file name",
+            entry_dir.join("2021.json").display()
+        )))
+        .stderr(predicates::str::contains("3 problem(s) found"));
+
+    Command::cargo_bin("bbn")?
+        .arg("fsck")
+        .arg("--fix")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .failure()
+        .stdout(predicates::str::contains(format!(
+            "fixed: {}: misplaced",
+            misplaced.display()
+        )))
+        .stderr(predicates::str::contains("1 problem(s) found"));
+    assert!(expected.is_file());
+    assert_eq!(fs::read_to_string(expected.with_extension("md"))?, "");
+
+    fs::remove_file(entry_dir.join("2021.json"))?;
+    Command::cargo_bin("bbn")?
+        .arg("fsck")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use std::fs;
+
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_bbn_hatena_blog_upload_plan() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path())?;
+    fs::write(
+        entry_dir.join("2021-02-03.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":[],"title":"TITLE1"}"#,
+    )?;
+    fs::write(entry_dir.join("2021-02-03.md"), "hello")?;
+    fs::write(
+        entry_dir.join("2021-02-04.json"),
+        r#"{"hatena_blog_ignore":true,"minutes":5,"pubdate":"2021-02-04T00:00:00+09:00","tags":[],"title":"TITLE2"}"#,
+    )?;
+    fs::write(entry_dir.join("2021-02-04.md"), "good bye")?;
+
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(&data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(&hatena_blog_data_file)
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+    fs::write(
+        config_dir.join("credentials.json"),
+        r#"{"hatena_api_key":"api_key","hatena_blog_id":"blog_id","hatena_id":"hatena_id"}"#,
+    )?;
+
+    // Hatena Blog にないエントリーが計画に含まれること (hatena_blog_ignore は除く)
+    Command::cargo_bin("bbn")?
+        .arg("hatena-blog")
+        .arg("upload")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout("no 2021-02-03\nplan: ne = 0 no = 1\n");
+    Command::cargo_bin("bbn")?
+        .arg("hatena-blog")
+        .arg("upload")
+        .arg("--json")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout("[{\"entry_id\":\"2021-02-03\",\"status\":\"no\"}]\n");
+
+    // 計画が空なら何もアップロードしないこと
+    fs::remove_file(entry_dir.join("2021-02-03.json"))?;
+    fs::remove_file(entry_dir.join("2021-02-03.md"))?;
+    Command::cargo_bin("bbn")?
+        .arg("hatena-blog")
+        .arg("upload")
+        .arg("--apply")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout("result: created = 0 updated = 0 failed = 0\n");
+    Command::cargo_bin("bbn")?
+        .arg("hatena-blog")
+        .arg("upload")
+        .arg("--apply")
+        .arg("--json")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout("[]\n");
+
+    // DATE と --apply は同時に指定できないこと
+    Command::cargo_bin("bbn")?
+        .arg("hatena-blog")
+        .arg("upload")
+        .arg("--apply")
+        .arg("2021-02-03")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .failure();
+    Ok(())
+}
@@ -1,4 +1,7 @@
+use std::collections::BTreeMap;
 use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
 
 use assert_cmd::Command;
 use tempfile::tempdir;
@@ -21,7 +24,7 @@ fn test_bbn_build_html() -> anyhow::Result<()> {
     let meta2 = entry_dir.join("2021-02-04.json");
     fs::write(
         meta2,
-        r#"{"minutes":5,"pubdate":"2021-02-04T00:00:00+09:00","tags":["tag1"],"title":"TITLE2"}"#,
+        r#"{"minutes":5,"pubdate":"2021-02-04T00:00:00+09:00","tags":["tag1","a/b?"],"title":"TITLE2"}"#,
     )?;
     let content2 = entry_dir.join("2021-02-04.md");
     fs::write(content2, "good bye")?;
@@ -88,6 +91,8 @@ fn test_bbn_build_html() -> anyhow::Result<()> {
     // related ページが存在すること
     let related_html = fs::read_to_string(out_dir.join("2021/02/03/related/index.html"))?;
     assert!(related_html.contains("の関連記事"));
+    assert!(related_html.contains("前後の記事"));
+    assert!(related_html.contains(r#"<a href="/2021/02/04/">"#));
     assert!(fs::read_to_string(out_dir.join("2021/02/03/related.html")).is_ok());
 
     // idTitle ページが存在すること（2021-02-03 は TITLE という idTitle を持つ）
@@ -100,5 +105,471 @@ fn test_bbn_build_html() -> anyhow::Result<()> {
     assert!(diary_html.contains("TITLE2"));
     assert!(fs::read_to_string(out_dir.join("2021/02/04/diary.html")).is_ok());
 
+    // entry-detail ページにタグへのリンクがあること
+    assert!(detail_html2.contains(r#"<a href="/tags/tag1/">tag1</a>"#));
+    assert!(!detail_html.contains(r#"class="tags""#));
+
+    // タグ一覧ページが存在すること
+    let tags_html = fs::read_to_string(out_dir.join("tags/index.html"))?;
+    assert!(tags_html.contains("タグ一覧"));
+    assert!(tags_html.contains(r#"href="/tags/tag1/""#));
+    assert!(tags_html.contains(r#"<span class="id">1</span>"#));
+    assert!(fs::read_to_string(out_dir.join("tags.html")).is_ok());
+
+    // タグごとのページが存在すること
+    let tag_html = fs::read_to_string(out_dir.join("tags/tag1/index.html"))?;
+    assert!(tag_html.contains("タグ tag1 の記事"));
+    assert!(tag_html.contains("TITLE2"));
+    assert!(!tag_html.contains("TITLE1"));
+    assert!(fs::read_to_string(out_dir.join("tags/tag1.html")).is_ok());
+
+    // パスに使えない文字を含むタグのページは "%XX" にしたディレクトリに出力すること
+    assert!(detail_html2.contains(r#"<a href="/tags/a%252Fb%253F/">a/b?</a>"#));
+    let tag_html = fs::read_to_string(out_dir.join("tags/a%2Fb%3F/index.html"))?;
+    assert!(tag_html.contains("タグ a/b? の記事"));
+    assert!(tag_html.contains("TITLE2"));
+
+    // 年・月のページが存在すること
+    let year_html = fs::read_to_string(out_dir.join("2021/index.html"))?;
+    assert!(year_html.contains("2021 の記事"));
+    assert!(year_html.contains("TITLE1"));
+    assert!(year_html.contains("TITLE2"));
+    assert!(year_html.contains(r#"<a class="nav-list" href="/">home</a>"#));
+    assert!(fs::read_to_string(out_dir.join("2021.html")).is_ok());
+    let month_html = fs::read_to_string(out_dir.join("2021/02/index.html"))?;
+    assert!(month_html.contains("2021-02 の記事"));
+    assert!(month_html.find("TITLE2").unwrap() < month_html.find("TITLE1").unwrap());
+    assert!(month_html.contains(r#"<a class="nav-list" href="/2021/">2021</a>"#));
+    assert!(fs::read_to_string(out_dir.join("2021/02.html")).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn test_bbn_build_html_with_site_config() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path())?;
+    fs::write(
+        entry_dir.join("2021-02-03.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":[],"title":"TITLE1"}"#,
+    )?;
+    fs::write(entry_dir.join("2021-02-03.md"), "hello")?;
+
+    let out_dir = temp_dir.path().join("out");
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(&data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(&hatena_blog_data_file)
+        .arg("--out-dir")
+        .arg(out_dir.as_path())
+        .arg("--base-url")
+        .arg("https://example.com/")
+        .arg("--lang")
+        .arg("en")
+        .arg("--site-title")
+        .arg("Example Blog")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    Command::cargo_bin("bbn")?
+        .arg("build")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    let root_html = fs::read_to_string(out_dir.join("index.html"))?;
+    assert!(root_html.contains(r#"<html lang="en""#));
+    assert!(root_html.contains(r#"<a href="/">Example Blog</a>"#));
+    assert!(!root_html.contains("blog.bouzuya.net"));
+
+    let detail_html = fs::read_to_string(out_dir.join("2021/02/03/index.html"))?;
+    assert!(detail_html.contains("2021-02-03 TITLE1 - Example Blog"));
+    assert!(detail_html.contains(r#"content="https://example.com/2021/02/03/""#));
+    assert!(detail_html.contains(r#"content="https://example.com/images/favicon.png""#));
+
+    let atom_xml = fs::read_to_string(out_dir.join("atom.xml"))?;
+    assert!(atom_xml.contains("<title>Example Blog</title>"));
+    assert!(atom_xml.contains(r#"href="https://example.com/atom.xml""#));
+    assert!(atom_xml.contains("<id>https://example.com/2021/02/03/</id>"));
+
+    Ok(())
+}
+
+#[test]
+fn test_bbn_build_html_page_size() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path())?;
+    for day in 1..=3 {
+        let date = format!("2021-02-{:02}", day);
+        fs::write(
+            entry_dir.join(format!("{date}.json")),
+            format!(
+                r#"{{"minutes":5,"pubdate":"{date}T00:00:00+09:00","tags":["tag1"],"title":"TITLE{day}"}}"#
+            ),
+        )?;
+        fs::write(entry_dir.join(format!("{date}.md")), "hello")?;
+    }
+
+    let out_dir = temp_dir.path().join("out");
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(&data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(&hatena_blog_data_file)
+        .arg("--out-dir")
+        .arg(out_dir.as_path())
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    // 設定ファイルの page-size を使う
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("set")
+        .arg("page-size")
+        .arg("2")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    Command::cargo_bin("bbn")?
+        .arg("build")
+        .arg("--html")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    // 1 ページ目は新しい 2 件
+    let root_html = fs::read_to_string(out_dir.join("index.html"))?;
+    assert!(root_html.contains("TITLE3"));
+    assert!(root_html.contains("TITLE2"));
+    assert!(!root_html.contains("TITLE1"));
+    assert!(root_html.contains(r#"<link rel="next" href="https://blog.bouzuya.net/page/2/">"#));
+    assert!(!root_html.contains(r#"<link rel="prev""#));
+    assert!(root_html.contains(r#"<a class="nav-older" href="/page/2/" rel="next">older</a>"#));
+
+    // 2 ページ目は残りの 1 件
+    let page2_html = fs::read_to_string(out_dir.join("page/2/index.html"))?;
+    assert!(page2_html.contains("TITLE1"));
+    assert!(!page2_html.contains("TITLE2"));
+    assert!(page2_html.contains(r#"<link rel="prev" href="https://blog.bouzuya.net/">"#));
+    assert!(!page2_html.contains(r#"<link rel="next""#));
+    assert!(page2_html.contains(r#"<a class="nav-newer" href="/" rel="prev">newer</a>"#));
+    assert!(fs::read_to_string(out_dir.join("page/2.html")).is_ok());
+    assert!(!out_dir.join("page/3").exists());
+
+    // タグのページも分ける
+    let tag_html = fs::read_to_string(out_dir.join("tags/tag1/index.html"))?;
+    assert!(tag_html.contains("TITLE3"));
+    assert!(tag_html.contains("TITLE2"));
+    assert!(!tag_html.contains("TITLE1"));
+    assert!(
+        tag_html.contains(r#"<link rel="next" href="https://blog.bouzuya.net/tags/tag1/page/2/">"#)
+    );
+    let tag_page2_html = fs::read_to_string(out_dir.join("tags/tag1/page/2/index.html"))?;
+    assert!(tag_page2_html.contains("タグ tag1 の記事 (2 ページ目)"));
+    assert!(tag_page2_html.contains("TITLE1"));
+    assert!(!tag_page2_html.contains("TITLE2"));
+    assert!(
+        tag_page2_html.contains(r#"<a class="nav-newer" href="/tags/tag1/" rel="prev">newer</a>"#)
+    );
+
+    // 期間のページも分ける
+    let month_html = fs::read_to_string(out_dir.join("2021/02/index.html"))?;
+    assert!(!month_html.contains("TITLE1"));
+    let month_page2_html = fs::read_to_string(out_dir.join("2021/02/page/2/index.html"))?;
+    assert!(month_page2_html.contains("TITLE1"));
+
+    // --page-size は設定ファイルより優先する
+    Command::cargo_bin("bbn")?
+        .arg("build")
+        .arg("--html")
+        .arg("--page-size")
+        .arg("3")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+    let tag_html = fs::read_to_string(out_dir.join("tags/tag1/index.html"))?;
+    assert!(tag_html.contains("TITLE1"));
+
+    // 0 は指定できない
+    Command::cargo_bin("bbn")?
+        .arg("build")
+        .arg("--html")
+        .arg("--page-size")
+        .arg("0")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_bbn_build_html_with_theme_dir() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path())?;
+    fs::write(
+        entry_dir.join("2021-02-03.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":[],"title":"A & B"}"#,
+    )?;
+    fs::write(entry_dir.join("2021-02-03.md"), "hello")?;
+
+    let theme_dir = temp_dir.path().join("theme");
+    fs::create_dir_all(theme_dir.join("templates"))?;
+    fs::write(
+        theme_dir.join("templates").join("page.html"),
+        "<title>{{ title }}</title>{% if rel.next %}<link rel=\"next\">{% endif %}{{ content|safe }}",
+    )?;
+    fs::create_dir_all(theme_dir.join("public").join("styles"))?;
+    fs::write(
+        theme_dir.join("public").join("styles").join("index.css"),
+        "body {}",
+    )?;
+    fs::write(theme_dir.join("public").join("robots.txt"), "User-agent: *")?;
+
+    let out_dir = temp_dir.path().join("out");
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(&data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(&hatena_blog_data_file)
+        .arg("--out-dir")
+        .arg(out_dir.as_path())
+        .arg("--theme-dir")
+        .arg(theme_dir.as_path())
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    Command::cargo_bin("bbn")?
+        .arg("build")
+        .arg("--html")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    // テーマのテンプレートで描画し、テーマにないテンプレートは組み込みのものを使う
+    let detail_html = fs::read_to_string(out_dir.join("2021/02/03/index.html"))?;
+    assert!(detail_html.starts_with("<title>2021-02-03 A &#38; B</title>"));
+    assert!(!detail_html.contains("<!DOCTYPE html>"));
+    assert!(detail_html.contains(r#"<div class="entry-detail">"#));
+    assert!(detail_html.contains("<p>hello</p>"));
+
+    // テーマの public ディレクトリのファイルで置き換え・追加する
+    assert_eq!(
+        fs::read_to_string(out_dir.join("styles/index.css"))?,
+        "body {}"
+    );
+    assert_eq!(
+        fs::read_to_string(out_dir.join("robots.txt"))?,
+        "User-agent: *"
+    );
+
+    // テーマのテンプレートの誤りはエラーにする
+    fs::write(theme_dir.join("templates").join("page.html"), "{{ title ")?;
+    Command::cargo_bin("bbn")?
+        .arg("build")
+        .arg("--html")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_bbn_build_jobs() -> anyhow::Result<()> {
+    fn read_tree(dir: &Path) -> anyhow::Result<BTreeMap<PathBuf, Vec<u8>>> {
+        let mut files = BTreeMap::new();
+        let mut dirs = vec![dir.to_path_buf()];
+        while let Some(d) = dirs.pop() {
+            for dir_entry in fs::read_dir(d)? {
+                let path = dir_entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else {
+                    files.insert(path.strip_prefix(dir)?.to_path_buf(), fs::read(&path)?);
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    let temp_dir = tempdir()?;
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    for day in 1..=20 {
+        let entry_dir = data_dir.join("2021").join(format!("{:02}", day % 3 + 1));
+        fs::create_dir_all(entry_dir.as_path())?;
+        let date = format!("2021-{:02}-{:02}", day % 3 + 1, day);
+        fs::write(
+            entry_dir.join(format!("{date}.json")),
+            format!(
+                r#"{{"minutes":{day},"pubdate":"{date}T00:00:00+09:00","tags":["tag{}"],"title":"TITLE{day}"}}"#,
+                day % 4
+            ),
+        )?;
+        fs::write(
+            entry_dir.join(format!("{date}.md")),
+            format!("# {day}\n\n[2021-01-0{}]\n", day % 9 + 1),
+        )?;
+    }
+
+    let mut outputs = vec![];
+    for jobs in ["1", "4"] {
+        let out_dir = temp_dir.path().join(format!("out{jobs}"));
+        let output = Command::cargo_bin("bbn")?
+            .arg("build")
+            .arg("--data-dir")
+            .arg(data_dir.as_path())
+            .arg("--out-dir")
+            .arg(out_dir.as_path())
+            .arg("--jobs")
+            .arg(jobs)
+            .arg("--verbose")
+            .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let stdout = String::from_utf8(output)?.replace(out_dir.to_str().unwrap_or_default(), "");
+        outputs.push((stdout, read_tree(out_dir.as_path())?));
+    }
+    assert!(!outputs[0].1.is_empty());
+    assert_eq!(outputs[0], outputs[1]);
+    Ok(())
+}
+
+#[test]
+fn test_bbn_build_prune() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let out_dir = temp_dir.path().join("out");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path())?;
+    fs::write(
+        entry_dir.join("2021-02-03-old.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":[],"title":"TITLE1"}"#,
+    )?;
+    fs::write(entry_dir.join("2021-02-03-old.md"), "hello")?;
+    fs::write(
+        entry_dir.join("2021-02-04.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-04T00:00:00+09:00","tags":[],"title":"TITLE2"}"#,
+    )?;
+    fs::write(entry_dir.join("2021-02-04.md"), "good bye")?;
+
+    let build = |args: &[&str]| -> anyhow::Result<String> {
+        let output = Command::cargo_bin("bbn")?
+            .arg("build")
+            .arg("--data-dir")
+            .arg(data_dir.as_path())
+            .arg("--out-dir")
+            .arg(out_dir.as_path())
+            .args(args)
+            .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        Ok(String::from_utf8(output)?.replace(&format!("{}/", out_dir.display()), ""))
+    };
+
+    build(&[])?;
+    assert!(out_dir.join("2021/02/03/old/index.html").is_file());
+
+    // id_title の変更とエントリーの削除
+    fs::rename(
+        entry_dir.join("2021-02-03-old.json"),
+        entry_dir.join("2021-02-03-new.json"),
+    )?;
+    fs::rename(
+        entry_dir.join("2021-02-03-old.md"),
+        entry_dir.join("2021-02-03-new.md"),
+    )?;
+    fs::remove_file(entry_dir.join("2021-02-04.json"))?;
+    fs::remove_file(entry_dir.join("2021-02-04.md"))?;
+
+    let expected = [
+        "2021/02/03/old/index.html",
+        "2021/02/03/old/index.json",
+        "2021/02/03/old.html",
+        "2021/02/03/old.json",
+        "2021/02/04/diary/index.html",
+        "2021/02/04/diary/index.json",
+        "2021/02/04/diary.html",
+        "2021/02/04/diary.json",
+        "2021/02/04/index.html",
+        "2021/02/04/index.json",
+        "2021/02/04/related/index.html",
+        "2021/02/04/related/index.json",
+        "2021/02/04/related.html",
+        "2021/02/04/related.json",
+        "2021/02/04.html",
+        "2021/02/04.json",
+    ];
+    let stdout = build(&["--prune", "--dry-run"])?;
+    assert_eq!(
+        stdout
+            .lines()
+            .filter_map(|line| line.strip_prefix("would remove: "))
+            .collect::<Vec<&str>>(),
+        expected
+    );
+    assert!(out_dir.join("2021/02/04.json").is_file());
+    // 古い id_title のディレクトリが残っていても新しい id_title のページを出力する
+    assert!(out_dir.join("2021/02/03/new/index.html").is_file());
+
+    let stdout = build(&["--prune"])?;
+    assert_eq!(
+        stdout
+            .lines()
+            .filter_map(|line| line.strip_prefix("removed: "))
+            .collect::<Vec<&str>>(),
+        expected
+    );
+    assert!(!out_dir.join("2021/02/03/old").exists());
+    assert!(!out_dir.join("2021/02/04").exists());
+    assert!(out_dir.join("2021/02/03/new/index.html").is_file());
+    assert!(out_dir.join("styles/index.css").is_file());
+    assert_eq!(build(&["--prune"])?, "");
+
+    Command::cargo_bin("bbn")?
+        .arg("build")
+        .arg("--json")
+        .arg("--prune")
+        .arg("--data-dir")
+        .arg(data_dir.as_path())
+        .arg("--out-dir")
+        .arg(out_dir.as_path())
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .failure();
     Ok(())
 }
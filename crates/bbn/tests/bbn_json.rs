@@ -89,5 +89,93 @@ fn test_bbn_json() -> anyhow::Result<()> {
         r#"[{"name":"tag1","count":1}]"#
     );
 
+    let archive_json_content = r#"[{"date":"2021-02-03","minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":[],"title":"TITLE1"},{"date":"2021-02-04","minutes":5,"pubdate":"2021-02-04T00:00:00+09:00","tags":["tag1"],"title":"TITLE2"}]"#;
+    assert_eq!(
+        fs::read_to_string(out_dir.join("2021.json"))?,
+        archive_json_content
+    );
+    assert_eq!(
+        fs::read_to_string(out_dir.join("2021/02.json"))?,
+        archive_json_content
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_bbn_json_incremental() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path())?;
+    fs::write(
+        entry_dir.join("2021-02-03.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":[],"title":"TITLE1"}"#,
+    )?;
+    fs::write(entry_dir.join("2021-02-03.md"), "hello")?;
+    fs::write(
+        entry_dir.join("2021-02-04.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-04T00:00:00+09:00","tags":[],"title":"TITLE2"}"#,
+    )?;
+    fs::write(entry_dir.join("2021-02-04.md"), "good bye")?;
+
+    let out_dir = temp_dir.path().join("out");
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(hatena_blog_data_file)
+        .arg("--out-dir")
+        .arg(out_dir.as_path())
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    let build = |force: bool| -> anyhow::Result<Vec<String>> {
+        let mut command = Command::cargo_bin("bbn")?;
+        command
+            .arg("build")
+            .arg("--json")
+            .arg("--verbose")
+            .env("BBN_TEST_CONFIG_DIR", config_dir.as_path());
+        if force {
+            command.arg("--force");
+        }
+        let output = command.assert().success().get_output().stdout.clone();
+        let out_dir = format!("{}/", out_dir.display());
+        Ok(String::from_utf8(output)?
+            .lines()
+            .map(|line| line.trim_start_matches(out_dir.as_str()).to_string())
+            .collect())
+    };
+
+    assert_eq!(build(false)?.len(), 4 * 2 + 2 * 2 + 3 + 2);
+
+    // 変更がなければ何も書き込まない
+    assert_eq!(build(false)?, Vec::<String>::new());
+
+    // 変更したエントリーのファイルだけを書き込む
+    fs::write(entry_dir.join("2021-02-04.md"), "good night")?;
+    assert_eq!(
+        build(false)?,
+        vec![
+            "2021/02/04.json",
+            "2021/02/04/index.json",
+            "2021/02/04/diary.json",
+            "2021/02/04/diary/index.json",
+        ]
+    );
+    assert!(fs::read_to_string(out_dir.join("2021/02/04.json"))?.contains("good night"));
+
+    // 出力ファイルが消えていれば書き直す
+    fs::remove_file(out_dir.join("2021/02/03.json"))?;
+    assert_eq!(build(false)?, vec!["2021/02/03.json"]);
+
+    assert_eq!(build(true)?.len(), 4 * 2 + 2 * 2 + 3 + 2);
     Ok(())
 }
@@ -0,0 +1,141 @@
+use std::fs;
+
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_bbn_mv_and_rm() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path())?;
+    fs::write(
+        entry_dir.join("2021-02-03.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":[],"title":"TITLE1"}"#,
+    )?;
+    fs::write(entry_dir.join("2021-02-03.md"), "hello")?;
+    fs::write(
+        entry_dir.join("2021-02-04.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-04T00:00:00+09:00","tags":[],"title":"TITLE2"}"#,
+    )?;
+    fs::write(
+        entry_dir.join("2021-02-04.md"),
+        "[2021-02-03]\n\n[2021-02-03]: https://blog.bouzuya.net/2021/02/03/\n",
+    )?;
+
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(&data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(&hatena_blog_data_file)
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    Command::cargo_bin("bbn")?
+        .arg("mv")
+        .arg("2021-02-03")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .failure();
+
+    Command::cargo_bin("bbn")?
+        .arg("mv")
+        .arg("2021-02-03")
+        .arg("--date")
+        .arg("2021-02-04")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "the entry already exists: 2021-02-04",
+        ));
+
+    // 日付が使われているときは id title も変更しない
+    Command::cargo_bin("bbn")?
+        .arg("mv")
+        .arg("2021-02-03")
+        .arg("--date")
+        .arg("2021-02-04")
+        .arg("--id-title")
+        .arg("renamed")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "the entry already exists: 2021-02-04",
+        ));
+    assert!(entry_dir.join("2021-02-03.json").exists());
+    assert_eq!(
+        fs::read_to_string(entry_dir.join("2021-02-03.md"))?,
+        "hello"
+    );
+    assert!(!entry_dir.join("2021-02-03-renamed.json").exists());
+    assert!(!entry_dir.join("2021-02-03-renamed.md").exists());
+
+    Command::cargo_bin("bbn")?
+        .arg("mv")
+        .arg("2021-02-03")
+        .arg("--date")
+        .arg("2021-03-01")
+        .arg("--id-title")
+        .arg("moved")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq("2021-03-01-moved\n"));
+    assert!(!entry_dir.join("2021-02-03.json").exists());
+    assert_eq!(
+        fs::read_to_string(data_dir.join("2021").join("03").join("2021-03-01-moved.md"))?,
+        "hello"
+    );
+    assert_eq!(
+        fs::read_to_string(entry_dir.join("2021-02-04.md"))?,
+        "[2021-03-01]\n\n[2021-03-01]: https://blog.bouzuya.net/2021/03/01/\n"
+    );
+
+    Command::cargo_bin("bbn")?
+        .arg("mv")
+        .arg("2021-03-01")
+        .arg("--no-id-title")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq("2021-03-01\n"));
+
+    Command::cargo_bin("bbn")?
+        .arg("rm")
+        .arg("2021-03-01")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq("2021-03-01\n"));
+    assert!(
+        !data_dir
+            .join("2021")
+            .join("03")
+            .join("2021-03-01.json")
+            .exists()
+    );
+    assert!(
+        !data_dir
+            .join("2021")
+            .join("03")
+            .join("2021-03-01.md")
+            .exists()
+    );
+
+    Command::cargo_bin("bbn")?
+        .arg("rm")
+        .arg("2021-03-01")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .failure();
+
+    Ok(())
+}
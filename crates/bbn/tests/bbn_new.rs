@@ -0,0 +1,87 @@
+use std::fs;
+
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_bbn_new() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(&data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(&hatena_blog_data_file)
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    Command::cargo_bin("bbn")?
+        .arg("new")
+        .arg("2021-W05-3")
+        .arg("--id-title")
+        .arg("my-post")
+        .arg("--title")
+        .arg("TITLE1")
+        .arg("--tag")
+        .arg("rust")
+        .arg("--tag")
+        .arg("diary")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq("2021-02-03-my-post\n"));
+
+    let entry_dir = data_dir.join("2021").join("02");
+    let meta = fs::read_to_string(entry_dir.join("2021-02-03-my-post.json"))?;
+    let meta: serde_json::Value = serde_json::from_str(&meta)?;
+    assert_eq!(meta["minutes"], 0);
+    assert_eq!(meta["tags"], serde_json::json!(["rust", "diary"]));
+    assert_eq!(meta["title"], "TITLE1");
+    assert!(meta["pubdate"].as_str().is_some());
+    assert_eq!(
+        fs::read_to_string(entry_dir.join("2021-02-03-my-post.md"))?,
+        ""
+    );
+
+    // 同じ日付のエントリがあれば上書きしない
+    Command::cargo_bin("bbn")?
+        .arg("new")
+        .arg("2021-02-03")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "the entry already exists: 2021-02-03-my-post",
+        ));
+    assert!(!entry_dir.join("2021-02-03.json").exists());
+
+    Command::cargo_bin("bbn")?
+        .arg("new")
+        .arg("2021-02-04")
+        .arg("--id-title")
+        .arg("a/b")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .failure();
+
+    // 日付を省略すると今日のエントリを作る
+    Command::cargo_bin("bbn")?
+        .arg("new")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+    Command::cargo_bin("bbn")?
+        .arg("list")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("2021-02-03 TITLE1"));
+
+    Ok(())
+}
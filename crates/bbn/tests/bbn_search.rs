@@ -0,0 +1,86 @@
+use std::fs;
+
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+#[test]
+fn test_bbn_search() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path())?;
+    fs::write(
+        entry_dir.join("2021-02-03.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":["rust"],"title":"Parser combinators"}"#,
+    )?;
+    fs::write(
+        entry_dir.join("2021-02-03.md"),
+        "I wrote a parser with nom.\n",
+    )?;
+    fs::write(
+        entry_dir.join("2021-02-04.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-04T00:00:00+09:00","tags":[],"title":"Diary"}"#,
+    )?;
+    fs::write(entry_dir.join("2021-02-04.md"), "Nothing happened.\n")?;
+
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(&data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(&hatena_blog_data_file)
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    // 本文にマッチ
+    Command::cargo_bin("bbn")?
+        .arg("search")
+        .arg("NOM")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq(
+            "2021-02-03 Parser combinators <https://blog.bouzuya.net/2021/02/03/>\n  I wrote a parser with nom. \n",
+        ));
+
+    // タイトルにマッチ
+    Command::cargo_bin("bbn")?
+        .arg("search")
+        .arg("title:diary")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq(
+            "2021-02-04 Diary <https://blog.bouzuya.net/2021/02/04/>\n",
+        ));
+
+    // 他の条件との組み合わせ
+    Command::cargo_bin("bbn")?
+        .arg("search")
+        .arg("--json")
+        .arg("tag:rust")
+        .arg("parser")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq(
+            r#"[{"date":"2021-02-03","snippet":"I wrote a parser with nom. ","title":"Parser combinators","url":"https://blog.bouzuya.net/2021/02/03/"}]
+"#,
+        ));
+
+    Command::cargo_bin("bbn")?
+        .arg("search")
+        .arg("--json")
+        .arg("serde")
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success()
+        .stdout(predicates::ord::eq("[]\n"));
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use std::fs;
+use std::io::Read;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::process::Child;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+/// テスト終了時に bbn serve を停止する
+struct Server(Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn get(port: u16, path: &str) -> anyhow::Result<String> {
+    let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n"
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    Ok(response)
+}
+
+fn wait_until<F>(timeout: Duration, mut f: F) -> bool
+where
+    F: FnMut() -> bool,
+{
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if f() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    false
+}
+
+#[test]
+fn test_bbn_serve() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    let config_dir = temp_dir.path().join("config");
+    let data_dir = temp_dir.path().join("data");
+    let entry_dir = data_dir.join("2021").join("02");
+    fs::create_dir_all(entry_dir.as_path())?;
+    fs::write(
+        entry_dir.join("2021-02-03.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-03T00:00:00+09:00","tags":[],"title":"TITLE1"}"#,
+    )?;
+    let content1 = entry_dir.join("2021-02-03.md");
+    fs::write(content1.as_path(), "hello")?;
+    fs::write(
+        entry_dir.join("2021-02-05.json"),
+        r#"{"minutes":5,"pubdate":"2021-02-05T00:00:00+09:00","tags":[],"title":"TITLE2"}"#,
+    )?;
+    fs::write(entry_dir.join("2021-02-05.md"), "world")?;
+
+    let out_dir = temp_dir.path().join("out");
+    // 前回のビルドで出力されたが、もう出力されないファイル
+    let stale = out_dir.join("2021").join("01").join("01.json");
+    fs::create_dir_all(stale.parent().unwrap_or(out_dir.as_path()))?;
+    fs::write(stale.as_path(), "{}")?;
+    let hatena_blog_data_file = temp_dir.path().join("hatena-blog.db");
+    Command::cargo_bin("bbn")?
+        .arg("config")
+        .arg("init")
+        .arg("--data-dir")
+        .arg(&data_dir)
+        .arg("--hatena-blog-data-file")
+        .arg(&hatena_blog_data_file)
+        .arg("--out-dir")
+        .arg(out_dir.as_path())
+        .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+        .assert()
+        .success();
+
+    // 空いているポートを探す
+    let port = TcpListener::bind(("127.0.0.1", 0))?.local_addr()?.port();
+    let _server = Server(
+        std::process::Command::new(env!("CARGO_BIN_EXE_bbn"))
+            .arg("serve")
+            .arg("--port")
+            .arg(port.to_string())
+            .arg("--prune")
+            .env("BBN_TEST_CONFIG_DIR", config_dir.as_path())
+            .stdout(Stdio::null())
+            .spawn()?,
+    );
+    assert!(wait_until(Duration::from_secs(30), || {
+        TcpStream::connect(("127.0.0.1", port)).is_ok()
+    }));
+
+    // 起動時にビルドされていること
+    assert!(out_dir.join("2021/02/03/index.html").exists());
+    assert!(out_dir.join("2021/02/03.json").exists());
+    assert!(!stale.exists());
+
+    // /YYYY/MM/DD/ と /YYYY/MM/DD は write_html の出力に対応すること
+    let response = get(port, "/2021/02/03/")?;
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("Content-Type: text/html; charset=utf-8\r\n"));
+    assert!(response.contains("<p>hello</p>"));
+    // 再読み込みのためのスクリプトが挿入されること
+    assert!(response.contains("/_bbn/live-reload"));
+    let response = get(port, "/2021/02/03")?;
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("<p>hello</p>"));
+    let response = get(port, "/2021/02/03.json")?;
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("Content-Type: application/json\r\n"));
+    assert!(!response.contains("/_bbn/live-reload"));
+    let response = get(port, "/2021/02/04/")?;
+    assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+    // 開いているブラウザーの代わりにイベントを待つ
+    let mut events = TcpStream::connect(("127.0.0.1", port))?;
+    write!(
+        events,
+        "GET /_bbn/live-reload HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n"
+    )?;
+    events.set_read_timeout(Some(Duration::from_secs(30)))?;
+    let mut buf = [0; 1024];
+    let n = events.read(&mut buf)?;
+    assert!(String::from_utf8_lossy(&buf[..n]).contains("text/event-stream"));
+
+    // data_dir の変更が反映されること
+    fs::write(content1.as_path(), "updated")?;
+    assert!(wait_until(Duration::from_secs(30), || {
+        get(port, "/2021/02/03/").is_ok_and(|response| response.contains("<p>updated</p>"))
+    }));
+    let mut received = String::new();
+    while !received.contains("event: reload") {
+        let n = events.read(&mut buf)?;
+        assert_ne!(n, 0);
+        received.push_str(&String::from_utf8_lossy(&buf[..n]));
+    }
+    let json = fs::read_to_string(out_dir.join("2021/02/03.json"))?;
+    assert!(json.contains("updated"));
+
+    // 削除されたエントリーのページは削除されること
+    assert!(out_dir.join("2021/02/05/index.html").exists());
+    fs::remove_file(entry_dir.join("2021-02-05.json"))?;
+    fs::remove_file(entry_dir.join("2021-02-05.md"))?;
+    assert!(wait_until(Duration::from_secs(30), || {
+        !out_dir.join("2021/02/05/index.html").exists()
+    }));
+    assert!(!out_dir.join("2021/02/05.json").exists());
+    assert!(out_dir.join("2021/02/03/index.html").exists());
+    Ok(())
+}